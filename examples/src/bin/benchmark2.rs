@@ -83,7 +83,7 @@ fn main() {
                 )
                 .expect("");
         }
-        writer.close();
+        writer.close().expect("");
 
         let end = SystemTime::now();
 
@@ -15,6 +15,7 @@ ffibuilder.cdef("""
     
     ts_file_writer file_writer_new(char* filename, schema s);
     ts_file_writer file_writer_write_int32(ts_file_writer, char* device_id, char* measurement_id, int timestamp, int value);
+    ts_file_writer file_writer_write_double(ts_file_writer, char* device_id, char* measurement_id, int timestamp, double value);
     void file_writer_close(ts_file_writer writer);
 """)
 
@@ -74,6 +75,14 @@ class TsFileWriter(object):
         else:
             raise RuntimeError("No instance or already destroyed")
 
+    def write_double(self, devide_id, measurement_id, timestamp, value):
+        if self.instance:
+            lib.file_writer_write_double(self.instance, self.schema.variable_holder.get(devide_id),
+                                         self.schema.variable_holder.get(measurement_id),
+                                         timestamp, value)
+        else:
+            raise RuntimeError("No instance or already destroyed")
+
     def close(self):
         """
         If used in a non context-manager way
@@ -87,19 +96,33 @@ class TsFileWriter(object):
 
 
 class TSDataType(IntEnum):
+    BOOLEAN = 0
     INT32 = 1
     INT64 = 2
     FLOAT = 3
+    DOUBLE = 4
+    TEXT = 5
 
 
 class TSEncoding(IntEnum):
     PLAIN = 0
+    RLE = 2
     TS2DIFF = 4
+    DICTIONARY = 7
+    GORILLA = 8
+    ZIGZAG = 9
+    CHIMP = 11
+    SPRINTZ = 12
 
 
 class CompressionType(IntEnum):
     UNCOMPRESSED = 0
     SNAPPY = 1
+    # The following types require the respective cargo feature
+    GZIP = 2
+    LZ4 = 7
+    ZSTD = 8
+    LZMA2 = 9
 
 
 if __name__ == '__main__':
@@ -11,13 +11,13 @@ use tsfile_writer::writer::{IoTDBValue, Schema, WriteWrapper};
 /// this function is intended for C usage, so unsafe is part of it....
 #[allow(clippy::not_unsafe_ptr_arg_deref)]
 #[no_mangle]
-pub extern "C" fn schema_simple<'a>(
+pub extern "C" fn schema_simple(
     device_id: *const c_char,
     measurement_id: *const c_char,
     data_type: u8,
     encoding: u8,
     compression: u8,
-) -> *mut Schema<'a> {
+) -> *mut Schema {
     let device_id = unsafe {
         assert!(!device_id.is_null());
 
@@ -124,6 +124,45 @@ pub extern "C" fn file_writer_write_int32(
     Box::into_raw(writer)
 }
 
+/// # Safety
+/// this function is intended for C usage, so unsafe is part of it....
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+#[no_mangle]
+pub extern "C" fn file_writer_write_double(
+    writer: *mut TsFileWriter<WriteWrapper<File>>,
+    device_id: *const c_char,
+    measurement_id: *const c_char,
+    timestamp: i64,
+    number: f64,
+) -> *mut TsFileWriter<WriteWrapper<File>> {
+    if writer.is_null() {
+        panic!("Null writer given!")
+    }
+    let device_id = unsafe {
+        assert!(!device_id.is_null());
+
+        CStr::from_ptr(device_id)
+    };
+    let measurement_id = unsafe {
+        assert!(!measurement_id.is_null());
+
+        CStr::from_ptr(measurement_id)
+    };
+    let mut writer = unsafe { Box::from_raw(writer) };
+
+    writer
+        .write(
+            device_id.to_str().unwrap(),
+            measurement_id.to_str().unwrap(),
+            timestamp,
+            IoTDBValue::DOUBLE(number),
+        )
+        .unwrap();
+
+    // Return ref back
+    Box::into_raw(writer)
+}
+
 /// # Safety
 /// this function is intended for C usage, so unsafe is part of it....
 #[allow(clippy::not_unsafe_ptr_arg_deref)]
@@ -131,6 +170,6 @@ pub extern "C" fn file_writer_write_int32(
 pub extern "C" fn file_writer_close(writer: *mut TsFileWriter<WriteWrapper<File>>) {
     if !writer.is_null() {
         let mut _b = unsafe { Box::from_raw(writer) };
-        _b.close();
+        _b.close().unwrap();
     }
 }
@@ -1,14 +1,18 @@
 //! This is a not yet feature complete Writer for TsFiles Version 3 (as defined from the Apache IoTDB Project).
+//! It supports all data types, non aligned and aligned devices and the encodings PLAIN, RLE,
+//! TS2DIFF, DICTIONARY, GORILLA, ZIGZAG, CHIMP and SPRINTZ.
 //! Currently not all features of TsFiles are supported.
 //! Most notably:
 //!
-//! * No Aligned Chunks can be written
-//! * Not all Encodings are available
-//! * Not all DataTypes are supported
-//! * Not all Compression Types are supported
+//! * Not all Encodings are available, e.g. FREQ and RLBE are missing
+//! * The compression types GZIP, LZ4, ZSTD and LZMA2 need the cargo feature of the same name in lower case,
+//!   UNCOMPRESSED and SNAPPY are always available
 //!
 //! But generally, the TsFiles written with this client are 100% compatible with TsFiles written in Java.
 //!
+//! The [`reader`] module reads TsFiles back, e.g. to query the points of a series in a time range
+//! or to aggregate them from their statistics.
+//!
 //! ## Quickstart
 //!
 //! To write a TsFile just do something like
@@ -80,6 +84,7 @@
 //! writer.write("d2", "s1", 1, IoTDBValue::LONG(14));
 //! writer.write("d2", "s2", 1, IoTDBValue::FLOAT(14.0 as f32));
 //! ```
+pub mod reader;
 #[cfg(feature = "sync_sender")]
 pub mod sync;
 pub mod writer;
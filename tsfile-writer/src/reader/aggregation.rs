@@ -0,0 +1,425 @@
+//! Aggregations (COUNT, MIN, MAX, FIRST, LAST, SUM, AVG) of a series in a time range.
+//!
+//! The aggregations are answered from the statistics in the footer, chunk metadata and page
+//! headers wherever possible. Only pages that straddle a boundary of the time range are
+//! decoded, chunks and pages that lie completely inside the range are never touched.
+//!
+//! ```
+//! use tsfile_writer::reader::query::TimeRange;
+//! use tsfile_writer::reader::TsFileSequenceReader;
+//! use tsfile_writer::writer::compression::CompressionType;
+//! use tsfile_writer::writer::encoding::TSEncoding;
+//! use tsfile_writer::writer::tsfile_writer::TsFileWriter;
+//! use tsfile_writer::writer::{IoTDBValue, Schema, TSDataType};
+//!
+//! let schema = Schema::simple(
+//!     "d1",
+//!     "s1",
+//!     TSDataType::INT64,
+//!     TSEncoding::PLAIN,
+//!     CompressionType::UNCOMPRESSED,
+//! );
+//! let mut writer = TsFileWriter::new("target/aggregation_doc.tsfile", schema, Default::default()).unwrap();
+//! for i in 0..100 {
+//!     writer.write("d1", "s1", i, IoTDBValue::LONG(i)).unwrap();
+//! }
+//! writer.close().unwrap();
+//!
+//! let mut reader = TsFileSequenceReader::open("target/aggregation_doc.tsfile", Default::default()).unwrap();
+//! let result = reader.aggregate("d1", "s1", TimeRange::new(10, 19)).unwrap().unwrap();
+//! assert_eq!(10, result.count());
+//! assert_eq!(Some(IoTDBValue::LONG(19)), result.max_value());
+//! assert_eq!(Some(14.5), result.avg());
+//! ```
+use crate::reader::page_reader::PageReader;
+use crate::reader::query::TimeRange;
+use crate::reader::{Page, TsFileSequenceReader};
+use crate::writer::errors::TsFileError;
+use crate::writer::statistics::Statistics;
+use crate::writer::{ChunkMetadata, IoTDBValue, TSDataType};
+use std::io::{Read, Seek};
+
+/// Aggregated values of a series in a time range
+#[derive(Clone, Debug)]
+pub struct AggregationResult {
+    statistics: Statistics,
+    /// Number of pages that had to be decoded
+    decoded_pages: u32,
+}
+
+impl AggregationResult {
+    fn new(data_type: TSDataType) -> AggregationResult {
+        AggregationResult {
+            statistics: Statistics::new(data_type),
+            decoded_pages: 0,
+        }
+    }
+
+    pub fn count(&self) -> u64 {
+        self.statistics.count() as u64
+    }
+
+    pub fn min_value(&self) -> Option<IoTDBValue> {
+        self.statistics.min_value()
+    }
+
+    pub fn max_value(&self) -> Option<IoTDBValue> {
+        self.statistics.max_value()
+    }
+
+    pub fn first_value(&self) -> Option<IoTDBValue> {
+        self.statistics.first_value()
+    }
+
+    pub fn last_value(&self) -> Option<IoTDBValue> {
+        self.statistics.last_value()
+    }
+
+    /// Timestamp of the first value, None if there are no values in the range
+    pub fn first_time(&self) -> Option<i64> {
+        self.first_value().map(|_| self.statistics.start_time())
+    }
+
+    /// Timestamp of the last value, None if there are no values in the range
+    pub fn last_time(&self) -> Option<i64> {
+        self.last_value().map(|_| self.statistics.end_time())
+    }
+
+    /// Sum of the values, None for TEXT series
+    pub fn sum(&self) -> Option<f64> {
+        self.statistics.sum()
+    }
+
+    /// Average of the values, None if there are no values in the range or for TEXT series
+    pub fn avg(&self) -> Option<f64> {
+        match (self.count(), self.sum()) {
+            (0, _) => None,
+            (count, sum) => sum.map(|sum| sum / count as f64),
+        }
+    }
+}
+
+impl<R: Read + Seek> TsFileSequenceReader<R> {
+    /// Aggregates the series `device.measurement` in the time range.
+    /// Returns None if the series does not exist in the file.
+    pub fn aggregate(
+        &mut self,
+        device: &str,
+        measurement: &str,
+        time_range: TimeRange,
+    ) -> Result<Option<AggregationResult>, TsFileError> {
+        let timeseries_metadata = match self.read_timeseries_metadata_of(device, measurement)? {
+            Some(metadata) => metadata,
+            None => return Ok(None),
+        };
+        let mut result = AggregationResult::new(timeseries_metadata.data_type());
+        let statistics = timeseries_metadata.statistics();
+        if !time_range.overlaps_statistics(statistics) {
+            return Ok(Some(result));
+        }
+        if time_range.covers_statistics(statistics) {
+            result.statistics.merge(statistics);
+            return Ok(Some(result));
+        }
+        for (chunk_metadata, time_chunk_metadata) in
+            self.chunk_metadata_with_time_chunks(device, &timeseries_metadata)?
+        {
+            let statistics = chunk_metadata.statistics();
+            if !time_range.overlaps_statistics(statistics) {
+                continue;
+            }
+            if time_range.covers_statistics(statistics) {
+                result.statistics.merge(statistics);
+                continue;
+            }
+            self.aggregate_chunk(
+                &chunk_metadata,
+                time_chunk_metadata.as_ref(),
+                time_range,
+                &mut result,
+            )?;
+        }
+        Ok(Some(result))
+    }
+
+    /// Aggregates a chunk that straddles a boundary of the time range page by page.
+    /// Value chunks of aligned devices are read together with their time chunk.
+    fn aggregate_chunk(
+        &mut self,
+        chunk_metadata: &ChunkMetadata,
+        time_chunk_metadata: Option<&ChunkMetadata>,
+        time_range: TimeRange,
+        result: &mut AggregationResult,
+    ) -> Result<(), TsFileError> {
+        let mut time_chunk = match time_chunk_metadata {
+            Some(time_chunk_metadata) => {
+                let time_header =
+                    self.read_chunk_header_at(time_chunk_metadata.offset_of_chunk_header())?;
+                Some((time_header, self.position()?))
+            }
+            None => None,
+        };
+        let header = self.read_chunk_header_at(chunk_metadata.offset_of_chunk_header())?;
+        let mut page_reader = match &time_chunk {
+            Some((time_header, _)) => PageReader::new_aligned(time_header, &header)?,
+            None => PageReader::new(&header, self.config.time_encoding)?,
+        };
+        let has_statistics = header.num_pages > 1;
+        let mut next_page = self.position()?;
+        for _ in 0..header.num_pages {
+            self.seek(next_page)?;
+            let page_header = self.read_page_header(header.data_type, has_statistics)?;
+            let data_offset = self.position()?;
+            next_page = data_offset + page_header.compressed_size as u64;
+            // Value pages of aligned devices contain the same rows as the time pages
+            let time_page_header = match time_chunk.as_mut() {
+                Some((time_header, next_time_page)) => {
+                    self.seek(*next_time_page)?;
+                    let time_page_header =
+                        self.read_page_header(time_header.data_type, has_statistics)?;
+                    let time_data_offset = self.position()?;
+                    *next_time_page = time_data_offset + time_page_header.compressed_size as u64;
+                    Some((time_page_header, time_data_offset))
+                }
+                None => None,
+            };
+            if let Some(statistics) = &page_header.statistics {
+                let covered = time_range.covers_statistics(statistics);
+                if covered {
+                    result.statistics.merge(statistics);
+                }
+                if covered || !time_range.overlaps_statistics(statistics) {
+                    continue;
+                }
+            }
+            self.seek(data_offset)?;
+            let data = self.read_page_data(&page_header)?;
+            let page = Page {
+                header: page_header,
+                data,
+            };
+            let points = match time_page_header {
+                Some((time_page_header, time_data_offset)) => {
+                    self.seek(time_data_offset)?;
+                    let time_page = Page {
+                        data: self.read_page_data(&time_page_header)?,
+                        header: time_page_header,
+                    };
+                    page_reader.read_aligned(&time_page, &page)?
+                }
+                None => page_reader.read(&page)?,
+            };
+            for (timestamp, value) in points {
+                if time_range.contains(timestamp) {
+                    result.statistics.update(timestamp, &value)?;
+                }
+            }
+            result.decoded_pages += 1;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::reader::query::TimeRange;
+    use crate::reader::TsFileSequenceReader;
+    use crate::writer::compression::CompressionType;
+    use crate::writer::encoding::TSEncoding;
+    use crate::writer::errors::TsFileError;
+    use crate::writer::schema::{AlignedDeviceBuilder, DeviceBuilder, TsFileSchemaBuilder};
+    use crate::writer::tsfile_writer::{DataPoint, TsFileWriter};
+    use crate::writer::{IoTDBValue, Schema, TSDataType};
+    use std::io::Cursor;
+
+    fn write_test_file() -> Result<Vec<u8>, TsFileError> {
+        let schema = TsFileSchemaBuilder::new()
+            .add(
+                "root.sg.d1",
+                DeviceBuilder::new()
+                    .add(
+                        "s1",
+                        TSDataType::INT32,
+                        TSEncoding::PLAIN,
+                        CompressionType::UNCOMPRESSED,
+                    )
+                    .add(
+                        "s2",
+                        TSDataType::FLOAT,
+                        TSEncoding::PLAIN,
+                        CompressionType::SNAPPY,
+                    )
+                    .build(),
+            )
+            .build();
+
+        let mut writer = TsFileWriter::new_from_writer(schema, Vec::new(), Default::default())?;
+        for i in 0..100000 {
+            writer.write("root.sg.d1", "s1", i, IoTDBValue::INT(i as i32))?;
+            writer.write("root.sg.d1", "s2", i, IoTDBValue::FLOAT(1.0))?;
+        }
+        writer.close()?;
+
+        Ok(writer.file_io_writer.out)
+    }
+
+    #[test]
+    fn aggregate_whole_series() -> Result<(), TsFileError> {
+        let mut reader =
+            TsFileSequenceReader::new(Cursor::new(write_test_file()?), Default::default())?;
+
+        let result = reader
+            .aggregate("root.sg.d1", "s1", TimeRange::all())?
+            .unwrap();
+
+        assert_eq!(100000, result.count());
+        assert_eq!(Some(IoTDBValue::INT(0)), result.min_value());
+        assert_eq!(Some(IoTDBValue::INT(99999)), result.max_value());
+        assert_eq!(Some(IoTDBValue::INT(0)), result.first_value());
+        assert_eq!(Some(IoTDBValue::INT(99999)), result.last_value());
+        assert_eq!(Some(0), result.first_time());
+        assert_eq!(Some(99999), result.last_time());
+        assert_eq!(Some(4999950000.0), result.sum());
+        assert_eq!(Some(49999.5), result.avg());
+        assert_eq!(0, result.decoded_pages);
+
+        Ok(())
+    }
+
+    #[test]
+    fn aggregate_time_range() -> Result<(), TsFileError> {
+        let mut reader =
+            TsFileSequenceReader::new(Cursor::new(write_test_file()?), Default::default())?;
+
+        let result = reader
+            .aggregate("root.sg.d1", "s1", TimeRange::new(1000, 80999))?
+            .unwrap();
+
+        assert_eq!(80000, result.count());
+        assert_eq!(Some(IoTDBValue::INT(1000)), result.min_value());
+        assert_eq!(Some(IoTDBValue::INT(80999)), result.max_value());
+        assert_eq!(Some(IoTDBValue::INT(1000)), result.first_value());
+        assert_eq!(Some(IoTDBValue::INT(80999)), result.last_value());
+        assert_eq!(Some((1000..81000).sum::<i64>() as f64), result.sum());
+        // Only the pages containing the boundaries are decoded
+        assert_eq!(2, result.decoded_pages);
+
+        let result = reader
+            .aggregate("root.sg.d1", "s2", TimeRange::new(-5, 9))?
+            .unwrap();
+        assert_eq!(10, result.count());
+        assert_eq!(Some(1.0), result.avg());
+        assert_eq!(1, result.decoded_pages);
+
+        Ok(())
+    }
+
+    #[test]
+    fn aggregate_boolean() -> Result<(), TsFileError> {
+        let schema = Schema::simple(
+            "d1",
+            "s1",
+            TSDataType::BOOLEAN,
+            TSEncoding::PLAIN,
+            CompressionType::UNCOMPRESSED,
+        );
+        let mut writer = TsFileWriter::new_from_writer(schema, Vec::new(), Default::default())?;
+        for i in 0..100000 {
+            writer.write("d1", "s1", i, IoTDBValue::BOOLEAN(i % 4 == 1))?;
+        }
+        writer.close()?;
+        let mut reader =
+            TsFileSequenceReader::new(Cursor::new(writer.file_io_writer.out), Default::default())?;
+
+        let result = reader.aggregate("d1", "s1", TimeRange::all())?.unwrap();
+        assert_eq!(100000, result.count());
+        assert_eq!(Some(25000.0), result.sum());
+        assert_eq!(None, result.min_value());
+        assert_eq!(None, result.max_value());
+        assert_eq!(Some(IoTDBValue::BOOLEAN(false)), result.first_value());
+        assert_eq!(Some(IoTDBValue::BOOLEAN(false)), result.last_value());
+
+        let result = reader
+            .aggregate("d1", "s1", TimeRange::new(1, 4001))?
+            .unwrap();
+        assert_eq!(4001, result.count());
+        assert_eq!(Some(1001.0), result.sum());
+        assert_eq!(Some(IoTDBValue::BOOLEAN(true)), result.first_value());
+        assert_eq!(Some(IoTDBValue::BOOLEAN(true)), result.last_value());
+
+        Ok(())
+    }
+
+    #[test]
+    fn aggregate_empty() -> Result<(), TsFileError> {
+        let mut reader =
+            TsFileSequenceReader::new(Cursor::new(write_test_file()?), Default::default())?;
+
+        assert!(reader
+            .aggregate("root.sg.d1", "s3", TimeRange::all())?
+            .is_none());
+
+        let result = reader
+            .aggregate("root.sg.d1", "s1", TimeRange::new(100000, 200000))?
+            .unwrap();
+        assert_eq!(0, result.count());
+        assert_eq!(None, result.min_value());
+        assert_eq!(None, result.first_time());
+        assert_eq!(Some(0.0), result.sum());
+        assert_eq!(None, result.avg());
+
+        Ok(())
+    }
+
+    #[test]
+    fn aggregate_aligned() -> Result<(), TsFileError> {
+        let schema = TsFileSchemaBuilder::new()
+            .add(
+                "d1",
+                AlignedDeviceBuilder::new()
+                    .add(
+                        "s1",
+                        TSDataType::INT64,
+                        TSEncoding::PLAIN,
+                        CompressionType::UNCOMPRESSED,
+                    )
+                    .add(
+                        "s2",
+                        TSDataType::DOUBLE,
+                        TSEncoding::PLAIN,
+                        CompressionType::SNAPPY,
+                    )
+                    .build(),
+            )
+            .build();
+        let mut writer = TsFileWriter::new_from_writer(schema, Vec::new(), Default::default())?;
+        for i in 0..100000 {
+            let mut values = vec![DataPoint::new("s1", IoTDBValue::LONG(i))];
+            // s2 only has values in the first half
+            if i < 50000 && i % 2 == 1 {
+                values.push(DataPoint::new("s2", IoTDBValue::DOUBLE(2.0)));
+            }
+            writer.write_many("d1", i, values)?;
+        }
+        writer.close()?;
+        let mut reader =
+            TsFileSequenceReader::new(Cursor::new(writer.file_io_writer.out), Default::default())?;
+
+        let result = reader
+            .aggregate("d1", "s1", TimeRange::new(1000, 80999))?
+            .unwrap();
+        assert_eq!(80000, result.count());
+        assert_eq!(Some((1000..81000).sum::<i64>() as f64), result.sum());
+        assert_eq!(2, result.decoded_pages);
+
+        let result = reader
+            .aggregate("d1", "s2", TimeRange::new(40000, 60000))?
+            .unwrap();
+        assert_eq!(5000, result.count());
+        assert_eq!(Some(2.0), result.avg());
+        assert_eq!(Some(40001), result.first_time());
+        assert_eq!(Some(49999), result.last_time());
+
+        Ok(())
+    }
+}
@@ -0,0 +1,1240 @@
+//! Contains the TsFileSequenceReader to read TsFiles (Version 3), e.g. the ones written
+//! by the TsFileWriter of this crate.
+//!
+//! The reader gives access to the footer (TsFileMetadata, metadata index and bloom filter)
+//! and allows to walk through the chunk groups, chunks and pages of the file.
+//!
+//! ```
+//! use tsfile_writer::reader::TsFileSequenceReader;
+//! use tsfile_writer::writer::compression::CompressionType;
+//! use tsfile_writer::writer::encoding::TSEncoding;
+//! use tsfile_writer::writer::tsfile_writer::TsFileWriter;
+//! use tsfile_writer::writer::{IoTDBValue, Schema, TSDataType};
+//!
+//! let schema = Schema::simple(
+//!     "d1",
+//!     "s1",
+//!     TSDataType::INT64,
+//!     TSEncoding::PLAIN,
+//!     CompressionType::UNCOMPRESSED,
+//! );
+//! let mut writer = TsFileWriter::new("target/reader_doc.tsfile", schema, Default::default()).unwrap();
+//! writer.write("d1", "s1", 1, IoTDBValue::LONG(13)).unwrap();
+//! writer.close().unwrap();
+//!
+//! let mut reader = TsFileSequenceReader::open("target/reader_doc.tsfile", Default::default()).unwrap();
+//! for chunk_group in reader.chunk_groups().unwrap() {
+//!     let chunk_group = chunk_group.unwrap();
+//!     for chunk in chunk_group.chunks {
+//!         println!("{}.{}: {} pages", chunk_group.device_id, chunk.header.measurement_id, chunk.pages.len());
+//!     }
+//! }
+//! ```
+use crate::writer::encoding::TSEncoding;
+use crate::writer::errors::TsFileError;
+use crate::writer::statistics::Statistics;
+use crate::writer::ts_file_config::TsFileConfig;
+use crate::writer::utils::{read_byte, read_i32, read_var_u32};
+use crate::writer::{
+    read_str, ChunkHeader, ChunkMetadata, MetadataIndexNode, MetadataIndexNodeType, TSDataType,
+    TimeSeriesMetadata, TsFileMetadata, CHUNK_GROUP_HEADER, CHUNK_HEADER,
+    ONLY_ONE_PAGE_CHUNK_HEADER, OPERATION_INDEX_RANGE, SEPARATOR, VALUE_COLUMN_MASK,
+};
+use std::fs::File;
+use std::io::{BufReader, Read, Seek, SeekFrom};
+
+pub mod aggregation;
+pub mod page_reader;
+pub mod query;
+
+pub(crate) const MAGIC_STRING: &[u8] = b"TsFile";
+pub(crate) const VERSION_NUMBER: u8 = 0x03;
+/// Size of magic string and version at the beginning of the file, the first chunk group follows
+pub(crate) const HEADER_SIZE: u64 = MAGIC_STRING.len() as u64 + 1;
+
+/// Header of a page, the statistics are only present if the chunk has more than one page
+#[derive(Clone, Debug)]
+pub struct PageHeader {
+    pub uncompressed_size: u32,
+    pub compressed_size: u32,
+    pub statistics: Option<Statistics>,
+}
+
+/// A page with its raw (possibly compressed) content
+pub struct Page {
+    pub header: PageHeader,
+    pub data: Vec<u8>,
+}
+
+pub struct Chunk {
+    /// Position of the chunk header in the file
+    pub offset: u64,
+    pub header: ChunkHeader,
+    pub pages: Vec<Page>,
+    /// Encoding of the timestamps of non aligned chunks, taken from the config of the reader
+    pub time_encoding: TSEncoding,
+}
+
+pub struct ChunkGroup {
+    pub device_id: String,
+    pub chunks: Vec<Chunk>,
+}
+
+/// Iterator over the chunk groups of a file in the order they are stored, each chunk group
+/// is read when it is requested. See `TsFileSequenceReader::chunk_groups`.
+pub struct ChunkGroups<'a, R: Read + Seek> {
+    reader: &'a mut TsFileSequenceReader<R>,
+    /// Device of the chunk group header that ended the previous chunk group
+    next_device_id: Option<String>,
+    finished: bool,
+}
+
+impl<'a, R: Read + Seek> ChunkGroups<'a, R> {
+    /// Reads the chunks up to the next chunk group header or the separator of the index area
+    fn read_chunk_group(&mut self) -> Result<Option<ChunkGroup>, TsFileError> {
+        let mut chunk_group = self.next_device_id.take().map(|device_id| ChunkGroup {
+            device_id,
+            chunks: vec![],
+        });
+        loop {
+            let offset = self.reader.position()?;
+            let marker = self.reader.read_marker()?;
+            match marker {
+                CHUNK_GROUP_HEADER => {
+                    let device_id = self.reader.read_chunk_group_header()?;
+                    if chunk_group.is_some() {
+                        self.next_device_id = Some(device_id);
+                        return Ok(chunk_group);
+                    }
+                    chunk_group = Some(ChunkGroup {
+                        device_id,
+                        chunks: vec![],
+                    });
+                }
+                SEPARATOR => {
+                    self.finished = true;
+                    return Ok(chunk_group);
+                }
+                OPERATION_INDEX_RANGE => {
+                    // min and max plan index, not relevant here
+                    self.reader.input.seek(SeekFrom::Current(16))?;
+                }
+                m if m & 0x3F == CHUNK_HEADER || m & 0x3F == ONLY_ONE_PAGE_CHUNK_HEADER => {
+                    let header = self.reader.read_chunk_header(marker)?;
+                    let pages = self.reader.read_pages(&header)?;
+                    match chunk_group.as_mut() {
+                        Some(group) => group.chunks.push(Chunk {
+                            offset,
+                            header,
+                            pages,
+                            time_encoding: self.reader.config.time_encoding,
+                        }),
+                        None => {
+                            return Err(TsFileError::ReadError {
+                                source: Some(format!(
+                                    "Chunk at offset {} does not belong to a chunk group",
+                                    offset
+                                )),
+                            });
+                        }
+                    }
+                }
+                _ => {
+                    return Err(TsFileError::ReadError {
+                        source: Some(format!("Unknown marker {} at offset {}", marker, offset)),
+                    });
+                }
+            }
+        }
+    }
+}
+
+impl<'a, R: Read + Seek> Iterator for ChunkGroups<'a, R> {
+    type Item = Result<ChunkGroup, TsFileError>;
+
+    /// The iteration ends after the first error
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.finished {
+            return None;
+        }
+        match self.read_chunk_group() {
+            Ok(Some(chunk_group)) => Some(Ok(chunk_group)),
+            Ok(None) => {
+                self.finished = true;
+                None
+            }
+            Err(e) => {
+                self.finished = true;
+                Some(Err(e))
+            }
+        }
+    }
+}
+
+/// Reads a TsFile from any source that implements `Read` and `Seek`
+pub struct TsFileSequenceReader<R: Read + Seek> {
+    input: R,
+    file_size: u64,
+    config: TsFileConfig,
+    /// Footer of the file, read once by `new`, None for incomplete files
+    metadata: Option<TsFileMetadata>,
+}
+
+impl TsFileSequenceReader<BufReader<File>> {
+    // "Default" constructor to use... reads from a file
+    pub fn open(
+        filename: &str,
+        config: TsFileConfig,
+    ) -> Result<TsFileSequenceReader<BufReader<File>>, TsFileError> {
+        let file = File::open(filename)?;
+        TsFileSequenceReader::new(BufReader::new(file), config)
+    }
+}
+
+impl<R: Read + Seek> TsFileSequenceReader<R> {
+    /// Creates the reader, checks magic string and version at the beginning
+    /// and the magic string at the end of the file and reads the footer
+    pub fn new(input: R, config: TsFileConfig) -> Result<TsFileSequenceReader<R>, TsFileError> {
+        let mut reader = TsFileSequenceReader::new_incomplete(input, config)?;
+        if reader.file_size < (2 * MAGIC_STRING.len() + 1) as u64 {
+            return Err(TsFileError::ReadError {
+                source: Some("File is too small to be a TsFile".to_owned()),
+            });
+        }
+        reader.seek(reader.file_size - MAGIC_STRING.len() as u64)?;
+        reader.check_magic_string("tail")?;
+        reader.metadata = Some(reader.read_footer()?);
+
+        Ok(reader)
+    }
+
+    /// Creates the reader for a file that may lack its footer, e.g. one whose writer was not
+    /// closed. Only magic string and version at the beginning are checked, so the footer must
+    /// not be read, but the chunk groups can be walked through.
+    pub fn new_incomplete(
+        mut input: R,
+        config: TsFileConfig,
+    ) -> Result<TsFileSequenceReader<R>, TsFileError> {
+        let file_size = input.seek(SeekFrom::End(0))?;
+        if file_size < HEADER_SIZE {
+            return Err(TsFileError::ReadError {
+                source: Some("File is too small to be a TsFile".to_owned()),
+            });
+        }
+        let mut reader = TsFileSequenceReader {
+            input,
+            file_size,
+            config,
+            metadata: None,
+        };
+
+        reader.seek(0)?;
+        reader.check_magic_string("head")?;
+        let version = read_byte(&mut reader.input)?;
+        if version != VERSION_NUMBER {
+            return Err(TsFileError::ReadError {
+                source: Some(format!("Unsupported TsFile version {}", version)),
+            });
+        }
+
+        Ok(reader)
+    }
+
+    fn check_magic_string(&mut self, location: &str) -> Result<(), TsFileError> {
+        let mut magic = [0_u8; 6];
+        self.input.read_exact(&mut magic)?;
+        if magic != MAGIC_STRING {
+            return Err(TsFileError::ReadError {
+                source: Some(format!(
+                    "No TsFile magic string at the {} of the file",
+                    location
+                )),
+            });
+        }
+        Ok(())
+    }
+
+    pub fn file_size(&self) -> u64 {
+        self.file_size
+    }
+
+    pub fn position(&mut self) -> Result<u64, TsFileError> {
+        Ok(self.input.stream_position()?)
+    }
+
+    pub fn seek(&mut self, position: u64) -> Result<(), TsFileError> {
+        self.input.seek(SeekFrom::Start(position))?;
+        Ok(())
+    }
+
+    fn read_bytes(&mut self, start: u64, end: u64) -> Result<Vec<u8>, TsFileError> {
+        if end < start || end > self.file_size {
+            return Err(TsFileError::ReadError {
+                source: Some(format!("Invalid range [{}, {}) in file", start, end)),
+            });
+        }
+        self.seek(start)?;
+        let mut buffer = vec![0_u8; (end - start) as usize];
+        self.input.read_exact(&mut buffer)?;
+        Ok(buffer)
+    }
+
+    /// The footer of the file, i.e. the root of the metadata index, the meta offset
+    /// and the bloom filter. Readers of incomplete files have no footer.
+    pub fn file_metadata(&self) -> Result<&TsFileMetadata, TsFileError> {
+        self.metadata
+            .as_ref()
+            .ok_or_else(|| TsFileError::ReadError {
+                source: Some("The footer of an incomplete file is not read".to_owned()),
+            })
+    }
+
+    fn read_footer(&mut self) -> Result<TsFileMetadata, TsFileError> {
+        let size_position = self.file_size - MAGIC_STRING.len() as u64 - 4;
+        self.seek(size_position)?;
+        let footer_size = read_i32(&mut self.input)?;
+        if footer_size < 0 || footer_size as u64 > size_position {
+            return Err(TsFileError::ReadError {
+                source: Some(format!("Invalid footer size {}", footer_size)),
+            });
+        }
+        let buffer = self.read_bytes(size_position - footer_size as u64, size_position)?;
+        TsFileMetadata::deserialize(buffer.as_slice(), &self.config)
+    }
+
+    /// Checks the bloom filter of the file if the path (`device.measurement`) may be contained.
+    /// A return value of false guarantees that the series is not in the file.
+    /// Files without bloom filter may contain every path. The bloom filter is deserialized
+    /// with the footer by `new`, so no data is read from the file.
+    pub fn might_contain(&self, path: &str) -> Result<bool, TsFileError> {
+        Ok(match self.file_metadata()?.bloom_filter() {
+            Some(filter) => filter.might_contain(path),
+            None => true,
+        })
+    }
+
+    /// Reads the metadata index node that is stored in the range [start, end)
+    pub fn read_metadata_index_node(
+        &mut self,
+        start: u64,
+        end: u64,
+    ) -> Result<MetadataIndexNode, TsFileError> {
+        let buffer = self.read_bytes(start, end)?;
+        MetadataIndexNode::deserialize(&mut buffer.as_slice())
+    }
+
+    /// Reads all timeseries metadata that are stored in the range [start, end),
+    /// i.e. the range a child of a LeafMeasurement node points to
+    pub fn read_timeseries_metadata(
+        &mut self,
+        start: u64,
+        end: u64,
+    ) -> Result<Vec<TimeSeriesMetadata>, TsFileError> {
+        let buffer = self.read_bytes(start, end)?;
+        let mut reader = buffer.as_slice();
+        let mut result = vec![];
+        while !reader.is_empty() {
+            result.push(TimeSeriesMetadata::deserialize(&mut reader)?);
+        }
+        Ok(result)
+    }
+
+    /// Range [start, end) of the data the child with the given index points to
+    fn child_range(node: &MetadataIndexNode, index: usize) -> (u64, u64) {
+        let children = node.children();
+        let end = match children.get(index + 1) {
+            Some(next) => next.offset(),
+            None => node.end_offset(),
+        };
+        (children[index].offset(), end)
+    }
+
+    /// Index of the last child with a name less or equal to the key
+    /// (children are sorted by name)
+    fn find_child(node: &MetadataIndexNode, key: &str) -> Option<usize> {
+        match node.children().partition_point(|c| c.name() <= key) {
+            0 => None,
+            i => Some(i - 1),
+        }
+    }
+
+    /// Searches the device in the metadata index and returns the root node
+    /// of its measurement index
+    pub fn read_device_index_node(
+        &mut self,
+        device: &str,
+    ) -> Result<Option<MetadataIndexNode>, TsFileError> {
+        let mut node = match self.file_metadata()?.metadata_index() {
+            Some(root) => root.clone(),
+            None => return Ok(None),
+        };
+        loop {
+            let index = match Self::find_child(&node, device) {
+                Some(index) => index,
+                None => return Ok(None),
+            };
+            let (start, end) = Self::child_range(&node, index);
+            match node.node_type() {
+                MetadataIndexNodeType::InternalDevice => {
+                    node = self.read_metadata_index_node(start, end)?;
+                }
+                MetadataIndexNodeType::LeafDevice => {
+                    if node.children()[index].name() != device {
+                        return Ok(None);
+                    }
+                    return Ok(Some(self.read_metadata_index_node(start, end)?));
+                }
+                _ => {
+                    return Err(TsFileError::ReadError {
+                        source: Some(format!("Expected a device node at offset {}", start)),
+                    });
+                }
+            }
+        }
+    }
+
+    /// Searches the timeseries metadata of a single series in the metadata index
+    pub fn read_timeseries_metadata_of(
+        &mut self,
+        device: &str,
+        measurement: &str,
+    ) -> Result<Option<TimeSeriesMetadata>, TsFileError> {
+        let mut node = match self.read_device_index_node(device)? {
+            Some(node) => node,
+            None => return Ok(None),
+        };
+        loop {
+            let index = match Self::find_child(&node, measurement) {
+                Some(index) => index,
+                None => return Ok(None),
+            };
+            let (start, end) = Self::child_range(&node, index);
+            match node.node_type() {
+                MetadataIndexNodeType::InternalMeasurement => {
+                    node = self.read_metadata_index_node(start, end)?;
+                }
+                MetadataIndexNodeType::LeafMeasurement => {
+                    return Ok(self
+                        .read_timeseries_metadata(start, end)?
+                        .into_iter()
+                        .find(|t| t.measurement_id() == measurement));
+                }
+                _ => {
+                    return Err(TsFileError::ReadError {
+                        source: Some(format!("Expected a measurement node at offset {}", start)),
+                    });
+                }
+            }
+        }
+    }
+
+    /// Returns the chunk metadata of a series, each together with the metadata of its time
+    /// chunk if the series is a value column of an aligned device
+    pub(crate) fn chunk_metadata_with_time_chunks(
+        &mut self,
+        device: &str,
+        timeseries_metadata: &TimeSeriesMetadata,
+    ) -> Result<Vec<(ChunkMetadata, Option<ChunkMetadata>)>, TsFileError> {
+        let chunks = timeseries_metadata.chunk_metadata_list()?;
+        if timeseries_metadata.mask() != VALUE_COLUMN_MASK {
+            return Ok(chunks.into_iter().map(|chunk| (chunk, None)).collect());
+        }
+        let time_chunks = match self.read_timeseries_metadata_of(device, "")? {
+            Some(time_metadata) => time_metadata.chunk_metadata_list()?,
+            None => {
+                return Err(TsFileError::ReadError {
+                    source: Some(format!("No time column for aligned device {}", device)),
+                });
+            }
+        };
+        chunks
+            .into_iter()
+            .map(|chunk| {
+                // The time chunk is the last one before the value chunk, i.e. the first
+                // chunk of the chunk group
+                let offset = chunk.offset_of_chunk_header();
+                match time_chunks
+                    .iter()
+                    .filter(|time_chunk| time_chunk.offset_of_chunk_header() < offset)
+                    .max_by_key(|time_chunk| time_chunk.offset_of_chunk_header())
+                {
+                    Some(time_chunk) => Ok((chunk, Some(time_chunk.clone()))),
+                    None => Err(TsFileError::ReadError {
+                        source: Some(format!("No time chunk for value chunk at {}", offset)),
+                    }),
+                }
+            })
+            .collect()
+    }
+
+    /// Returns the timeseries metadata of all series of the given device
+    pub fn read_device_timeseries_metadata(
+        &mut self,
+        device: &str,
+    ) -> Result<Vec<TimeSeriesMetadata>, TsFileError> {
+        let mut result = vec![];
+        if let Some(node) = self.read_device_index_node(device)? {
+            self.collect_timeseries_metadata(&node, &mut result)?;
+        }
+        Ok(result)
+    }
+
+    fn collect_timeseries_metadata(
+        &mut self,
+        node: &MetadataIndexNode,
+        result: &mut Vec<TimeSeriesMetadata>,
+    ) -> Result<(), TsFileError> {
+        for index in 0..node.children().len() {
+            let (start, end) = Self::child_range(node, index);
+            match node.node_type() {
+                MetadataIndexNodeType::LeafMeasurement => {
+                    result.append(&mut self.read_timeseries_metadata(start, end)?);
+                }
+                _ => {
+                    let child = self.read_metadata_index_node(start, end)?;
+                    self.collect_timeseries_metadata(&child, result)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    pub fn read_marker(&mut self) -> Result<u8, TsFileError> {
+        read_byte(&mut self.input)
+    }
+
+    /// Reads the device id of a chunk group header, the marker has to be read before
+    pub fn read_chunk_group_header(&mut self) -> Result<String, TsFileError> {
+        read_str(&mut self.input)
+    }
+
+    /// Reads a chunk header, the marker has to be read before.
+    /// Afterwards the reader is positioned at the first page header of the chunk.
+    pub fn read_chunk_header(&mut self, marker: u8) -> Result<ChunkHeader, TsFileError> {
+        let mut header = ChunkHeader::deserialize(marker, &mut self.input)?;
+        if header.num_pages == 0 {
+            // Count the pages by skipping over them
+            let data_start = self.position()?;
+            let data_end = data_start + header.data_size as u64;
+            while self.position()? < data_end {
+                let page_header = self.read_page_header(header.data_type, true)?;
+                self.input
+                    .seek(SeekFrom::Current(page_header.compressed_size as i64))?;
+                header.num_pages += 1;
+            }
+            self.seek(data_start)?;
+        }
+        Ok(header)
+    }
+
+    pub fn read_page_header(
+        &mut self,
+        data_type: TSDataType,
+        has_statistics: bool,
+    ) -> Result<PageHeader, TsFileError> {
+        let uncompressed_size = read_var_u32(&mut self.input)?;
+        let compressed_size = read_var_u32(&mut self.input)?;
+        let statistics = match has_statistics {
+            true => Some(Statistics::deserialize(data_type, &mut self.input)?),
+            false => None,
+        };
+        Ok(PageHeader {
+            uncompressed_size,
+            compressed_size,
+            statistics,
+        })
+    }
+
+    /// Reads the raw (possibly compressed) content of the page following the given header
+    pub fn read_page_data(&mut self, header: &PageHeader) -> Result<Vec<u8>, TsFileError> {
+        let position = self.position()?;
+        if header.compressed_size as u64 > self.file_size.saturating_sub(position) {
+            return Err(TsFileError::ReadError {
+                source: Some(format!(
+                    "Page of {} bytes at offset {} exceeds the file",
+                    header.compressed_size, position
+                )),
+            });
+        }
+        let mut data = vec![0_u8; header.compressed_size as usize];
+        self.input.read_exact(&mut data)?;
+        Ok(data)
+    }
+
+    fn read_pages(&mut self, header: &ChunkHeader) -> Result<Vec<Page>, TsFileError> {
+        let has_statistics = header.num_pages > 1;
+        let mut pages = Vec::with_capacity(header.num_pages as usize);
+        for _ in 0..header.num_pages {
+            let page_header = self.read_page_header(header.data_type, has_statistics)?;
+            let data = self.read_page_data(&page_header)?;
+            pages.push(Page {
+                header: page_header,
+                data,
+            });
+        }
+        Ok(pages)
+    }
+
+    /// Reads the chunk header (including its marker) at the given offset,
+    /// e.g. the one of a ChunkMetadata
+    pub fn read_chunk_header_at(&mut self, offset: u64) -> Result<ChunkHeader, TsFileError> {
+        self.seek(offset)?;
+        let marker = self.read_marker()?;
+        match marker & 0x3F {
+            CHUNK_HEADER | ONLY_ONE_PAGE_CHUNK_HEADER => self.read_chunk_header(marker),
+            _ => Err(TsFileError::ReadError {
+                source: Some(format!("No chunk header at offset {}", offset)),
+            }),
+        }
+    }
+
+    /// Reads the chunk with its header at the given offset,
+    /// e.g. the one of a ChunkMetadata
+    pub fn read_chunk(&mut self, offset: u64) -> Result<Chunk, TsFileError> {
+        let header = self.read_chunk_header_at(offset)?;
+        let pages = self.read_pages(&header)?;
+        Ok(Chunk {
+            offset,
+            header,
+            pages,
+            time_encoding: self.config.time_encoding,
+        })
+    }
+
+    /// Iterates over the chunk groups of the file sequentially
+    pub fn chunk_groups(&mut self) -> Result<ChunkGroups<'_, R>, TsFileError> {
+        self.seek(HEADER_SIZE)?;
+        Ok(ChunkGroups {
+            reader: self,
+            next_device_id: None,
+            finished: false,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::reader::{Chunk, PageHeader, TsFileSequenceReader, HEADER_SIZE};
+    use crate::writer::compression::CompressionType;
+    use crate::writer::encoding::TSEncoding;
+    use crate::writer::errors::TsFileError;
+    use crate::writer::schema::{AlignedDeviceBuilder, DeviceBuilder, TsFileSchemaBuilder};
+    use crate::writer::tsfile_writer::{DataPoint, TsFileWriter};
+    use crate::writer::{
+        IoTDBValue, MetadataIndexNodeType, Schema, TSDataType, TIME_COLUMN_MASK, VALUE_COLUMN_MASK,
+    };
+    use std::io::Cursor;
+
+    fn write_test_file() -> Result<Vec<u8>, TsFileError> {
+        let schema = TsFileSchemaBuilder::new()
+            .add(
+                "d1",
+                DeviceBuilder::new()
+                    .add(
+                        "s1",
+                        TSDataType::INT64,
+                        TSEncoding::PLAIN,
+                        CompressionType::UNCOMPRESSED,
+                    )
+                    .add(
+                        "s2",
+                        TSDataType::FLOAT,
+                        TSEncoding::PLAIN,
+                        CompressionType::SNAPPY,
+                    )
+                    .build(),
+            )
+            .add(
+                "d2",
+                DeviceBuilder::new()
+                    .add(
+                        "s1",
+                        TSDataType::INT32,
+                        TSEncoding::TS2DIFF,
+                        CompressionType::UNCOMPRESSED,
+                    )
+                    .build(),
+            )
+            .build();
+
+        let mut writer = TsFileWriter::new_from_writer(schema, Vec::new(), Default::default())?;
+
+        // Enough points to get multiple pages for d1.s1
+        for i in 0..10000 {
+            writer.write("d1", "s1", i, IoTDBValue::LONG(i))?;
+        }
+        writer.write("d1", "s2", 1, IoTDBValue::FLOAT(1.0))?;
+        writer.write("d2", "s1", 1, IoTDBValue::INT(1))?;
+        writer.close()?;
+
+        Ok(writer.file_io_writer.out)
+    }
+
+    #[test]
+    fn read_chunk_groups() -> Result<(), TsFileError> {
+        let buffer = write_test_file()?;
+        let mut reader = TsFileSequenceReader::new(Cursor::new(buffer), Default::default())?;
+
+        let mut chunk_groups = reader.chunk_groups()?;
+        let d1 = chunk_groups.next().unwrap()?;
+        let d2 = chunk_groups.next().unwrap()?;
+        assert!(chunk_groups.next().is_none());
+
+        assert_eq!("d1", d1.device_id);
+        assert_eq!("d2", d2.device_id);
+
+        assert_eq!(2, d1.chunks.len());
+        let s1 = &d1.chunks[0];
+        assert_eq!("s1", s1.header.measurement_id);
+        assert_eq!(TSDataType::INT64, s1.header.data_type);
+        assert!(s1.header.num_pages > 1);
+        assert_eq!(s1.header.num_pages as usize, s1.pages.len());
+        let count: u32 = s1
+            .pages
+            .iter()
+            .map(|p| p.header.statistics.as_ref().unwrap().count())
+            .sum();
+        assert_eq!(10000, count);
+
+        let s2 = &d1.chunks[1];
+        assert_eq!("s2", s2.header.measurement_id);
+        assert_eq!(CompressionType::SNAPPY, s2.header.compression);
+        assert_eq!(1, s2.pages.len());
+        assert!(s2.pages[0].header.statistics.is_none());
+
+        assert_eq!(TSEncoding::TS2DIFF, d2.chunks[0].header.encoding);
+
+        Ok(())
+    }
+
+    #[test]
+    fn read_chunk_groups_incomplete_file() -> Result<(), TsFileError> {
+        let mut buffer = write_test_file()?;
+        let d2_offset = TsFileSequenceReader::new(Cursor::new(buffer.clone()), Default::default())?
+            .chunk_groups()?
+            .nth(1)
+            .unwrap()?
+            .chunks[0]
+            .offset;
+        buffer.truncate(d2_offset as usize + 5);
+        let mut reader =
+            TsFileSequenceReader::new_incomplete(Cursor::new(buffer), Default::default())?;
+        assert!(reader.file_metadata().is_err());
+
+        let mut chunk_groups = reader.chunk_groups()?;
+        assert_eq!("d1", chunk_groups.next().unwrap()?.device_id);
+        assert!(chunk_groups.next().unwrap().is_err());
+        assert!(chunk_groups.next().is_none());
+        Ok(())
+    }
+
+    #[test]
+    fn bloom_filter() -> Result<(), TsFileError> {
+        let buffer = write_test_file()?;
+        // The bloom filter is checked without reading from the input
+        let reader = TsFileSequenceReader::new(Cursor::new(buffer), Default::default())?;
+
+        assert!(reader.might_contain("d1.s1")?);
+        assert!(reader.might_contain("d1.s2")?);
+        assert!(reader.might_contain("d2.s1")?);
+
+        let false_positives = (0..1000)
+            .filter(|i| reader.might_contain(&format!("d{}.s1", i + 3)).unwrap())
+            .count();
+        // Error rate is 5 percent by default
+        assert!(false_positives < 100, "{} false positives", false_positives);
+
+        Ok(())
+    }
+
+    #[test]
+    fn read_metadata() -> Result<(), TsFileError> {
+        let buffer = write_test_file()?;
+        let mut reader = TsFileSequenceReader::new(Cursor::new(buffer), Default::default())?;
+
+        let metadata = reader.file_metadata()?;
+        assert!(metadata.bloom_filter().is_some());
+        let root = metadata.metadata_index().unwrap().clone();
+        assert_eq!(MetadataIndexNodeType::LeafDevice, root.node_type());
+
+        let mut devices: Vec<&str> = root.children().iter().map(|c| c.name()).collect();
+        devices.sort_unstable();
+        assert_eq!(vec!["d1", "d2"], devices);
+
+        let children = root.children();
+        for (i, child) in children.iter().enumerate() {
+            let end = match children.get(i + 1) {
+                Some(next) => next.offset(),
+                None => root.end_offset(),
+            };
+            let device_node = reader.read_metadata_index_node(child.offset(), end)?;
+            assert_eq!(
+                MetadataIndexNodeType::LeafMeasurement,
+                device_node.node_type()
+            );
+            let start = device_node.children()[0].offset();
+            let timeseries = reader.read_timeseries_metadata(start, device_node.end_offset())?;
+
+            if child.name() == "d1" {
+                assert_eq!(2, timeseries.len());
+                assert_eq!("s1", timeseries[0].measurement_id());
+                assert_eq!(10000, timeseries[0].statistics().count());
+                assert_eq!(0, timeseries[0].statistics().start_time());
+                assert_eq!(9999, timeseries[0].statistics().end_time());
+
+                let chunk_metadata = timeseries[0].chunk_metadata_list()?;
+                assert_eq!(1, chunk_metadata.len());
+                let chunk = reader.read_chunk(chunk_metadata[0].offset_of_chunk_header())?;
+                assert_eq!("s1", chunk.header.measurement_id);
+            } else {
+                assert_eq!(1, timeseries.len());
+                assert_eq!(TSDataType::INT32, timeseries[0].data_type());
+            }
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn read_points() -> Result<(), TsFileError> {
+        let buffer = write_test_file()?;
+        let mut reader = TsFileSequenceReader::new(Cursor::new(buffer), Default::default())?;
+
+        let chunk_groups = reader.chunk_groups()?.collect::<Result<Vec<_>, _>>()?;
+
+        let points = chunk_groups[0].chunks[0].read_points()?;
+        assert_eq!(10000, points.len());
+        for (i, (timestamp, value)) in points.into_iter().enumerate() {
+            assert_eq!(i as i64, timestamp);
+            assert_eq!(IoTDBValue::LONG(i as i64), value);
+        }
+        assert_eq!(
+            vec![(1, IoTDBValue::FLOAT(1.0))],
+            chunk_groups[0].chunks[1].read_points()?
+        );
+        assert_eq!(
+            vec![(1, IoTDBValue::INT(1))],
+            chunk_groups[1].chunks[0].read_points()?
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn read_points_all_encodings() -> Result<(), TsFileError> {
+        for (data_type, encoding, compression) in [
+            (
+                TSDataType::INT32,
+                TSEncoding::PLAIN,
+                CompressionType::SNAPPY,
+            ),
+            (
+                TSDataType::INT32,
+                TSEncoding::TS2DIFF,
+                CompressionType::SNAPPY,
+            ),
+            (
+                TSDataType::INT64,
+                TSEncoding::PLAIN,
+                CompressionType::SNAPPY,
+            ),
+            (
+                TSDataType::INT64,
+                TSEncoding::TS2DIFF,
+                CompressionType::UNCOMPRESSED,
+            ),
+            (
+                TSDataType::FLOAT,
+                TSEncoding::PLAIN,
+                CompressionType::UNCOMPRESSED,
+            ),
+            (
+                TSDataType::DOUBLE,
+                TSEncoding::PLAIN,
+                CompressionType::SNAPPY,
+            ),
+            (
+                TSDataType::BOOLEAN,
+                TSEncoding::PLAIN,
+                CompressionType::UNCOMPRESSED,
+            ),
+            (TSDataType::TEXT, TSEncoding::PLAIN, CompressionType::SNAPPY),
+            (TSDataType::INT32, TSEncoding::RLE, CompressionType::SNAPPY),
+            (
+                TSDataType::INT64,
+                TSEncoding::RLE,
+                CompressionType::UNCOMPRESSED,
+            ),
+            (
+                TSDataType::BOOLEAN,
+                TSEncoding::RLE,
+                CompressionType::UNCOMPRESSED,
+            ),
+            (
+                TSDataType::FLOAT,
+                TSEncoding::GORILLA,
+                CompressionType::UNCOMPRESSED,
+            ),
+            (
+                TSDataType::DOUBLE,
+                TSEncoding::GORILLA,
+                CompressionType::SNAPPY,
+            ),
+            (
+                TSDataType::TEXT,
+                TSEncoding::DICTIONARY,
+                CompressionType::UNCOMPRESSED,
+            ),
+            (
+                TSDataType::INT32,
+                TSEncoding::ZIGZAG,
+                CompressionType::SNAPPY,
+            ),
+            (
+                TSDataType::INT64,
+                TSEncoding::ZIGZAG,
+                CompressionType::UNCOMPRESSED,
+            ),
+            (
+                TSDataType::FLOAT,
+                TSEncoding::CHIMP,
+                CompressionType::UNCOMPRESSED,
+            ),
+            (
+                TSDataType::DOUBLE,
+                TSEncoding::CHIMP,
+                CompressionType::SNAPPY,
+            ),
+            (
+                TSDataType::INT64,
+                TSEncoding::SPRINTZ,
+                CompressionType::UNCOMPRESSED,
+            ),
+            (
+                TSDataType::FLOAT,
+                TSEncoding::SPRINTZ,
+                CompressionType::SNAPPY,
+            ),
+            (
+                TSDataType::DOUBLE,
+                TSEncoding::SPRINTZ,
+                CompressionType::UNCOMPRESSED,
+            ),
+        ] {
+            let schema = Schema::simple("d1", "s1", data_type, encoding, compression);
+            let mut writer = TsFileWriter::new_from_writer(schema, Vec::new(), Default::default())?;
+            let expected: Vec<(i64, IoTDBValue)> = (0..50000_i64)
+                .map(|i| {
+                    let value = match data_type {
+                        TSDataType::INT32 => IoTDBValue::INT((i % 1000 - 500) as i32),
+                        TSDataType::INT64 => IoTDBValue::LONG(i * 1000 - 3),
+                        TSDataType::FLOAT => IoTDBValue::FLOAT(i as f32 / 3.0),
+                        TSDataType::DOUBLE => IoTDBValue::DOUBLE(i as f64 / 3.0),
+                        TSDataType::BOOLEAN => IoTDBValue::BOOLEAN(i % 3 == 0),
+                        TSDataType::TEXT => IoTDBValue::from("x".repeat((i % 20) as usize)),
+                        TSDataType::VECTOR => unreachable!(),
+                    };
+                    (3 * i + 1, value)
+                })
+                .collect();
+            for (timestamp, value) in expected.iter() {
+                writer.write("d1", "s1", *timestamp, value.clone())?;
+            }
+            writer.close()?;
+
+            let mut reader = TsFileSequenceReader::new(
+                Cursor::new(writer.file_io_writer.out),
+                Default::default(),
+            )?;
+            let points: Vec<(i64, IoTDBValue)> = reader
+                .chunk_groups()?
+                .collect::<Result<Vec<_>, _>>()?
+                .iter()
+                .flat_map(|group| group.chunks.iter())
+                .map(|chunk| chunk.read_points())
+                .collect::<Result<Vec<_>, TsFileError>>()?
+                .into_iter()
+                .flatten()
+                .collect();
+
+            assert_eq!(expected, points);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn read_points_all_compressions() -> Result<(), TsFileError> {
+        for compression in [
+            CompressionType::UNCOMPRESSED,
+            CompressionType::SNAPPY,
+            #[cfg(feature = "gzip")]
+            CompressionType::GZIP,
+            #[cfg(feature = "lz4")]
+            CompressionType::LZ4,
+            #[cfg(feature = "zstd")]
+            CompressionType::ZSTD,
+            #[cfg(feature = "lzma2")]
+            CompressionType::LZMA2,
+        ] {
+            let schema = Schema::simple(
+                "d1",
+                "s1",
+                TSDataType::INT64,
+                TSEncoding::PLAIN,
+                compression,
+            );
+            let mut writer = TsFileWriter::new_from_writer(schema, Vec::new(), Default::default())?;
+            let expected: Vec<(i64, IoTDBValue)> = (0..50000_i64)
+                .map(|i| (i, IoTDBValue::LONG(i % 100)))
+                .collect();
+            for (timestamp, value) in expected.iter() {
+                writer.write("d1", "s1", *timestamp, value.clone())?;
+            }
+            writer.close()?;
+
+            let mut reader = TsFileSequenceReader::new(
+                Cursor::new(writer.file_io_writer.out),
+                Default::default(),
+            )?;
+            let chunks: Vec<Chunk> = reader
+                .chunk_groups()?
+                .collect::<Result<Vec<_>, _>>()?
+                .into_iter()
+                .flat_map(|group| group.chunks)
+                .collect();
+            assert!(chunks.iter().all(|c| c.header.compression == compression));
+            let points: Vec<(i64, IoTDBValue)> = chunks
+                .iter()
+                .map(|chunk| chunk.read_points())
+                .collect::<Result<Vec<_>, TsFileError>>()?
+                .into_iter()
+                .flatten()
+                .collect();
+            assert_eq!(expected, points);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn read_points_ts2diff_floats() -> Result<(), TsFileError> {
+        let schema = TsFileSchemaBuilder::new()
+            .add(
+                "d1",
+                DeviceBuilder::new()
+                    .add(
+                        "s1",
+                        TSDataType::FLOAT,
+                        TSEncoding::TS2DIFF,
+                        CompressionType::SNAPPY,
+                    )
+                    .add_with_max_point_number(
+                        "s2",
+                        TSDataType::DOUBLE,
+                        TSEncoding::TS2DIFF,
+                        CompressionType::UNCOMPRESSED,
+                        3,
+                    )
+                    .build(),
+            )
+            .build();
+        let mut writer = TsFileWriter::new_from_writer(schema, Vec::new(), Default::default())?;
+        for i in 0..50000_i64 {
+            writer.write_many(
+                "d1",
+                i,
+                vec![
+                    DataPoint::new("s1", IoTDBValue::FLOAT((i % 1000 - 500) as f32 / 100.0)),
+                    DataPoint::new("s2", IoTDBValue::DOUBLE((i * 7) as f64 / 1000.0)),
+                ],
+            )?;
+        }
+        writer.close()?;
+
+        let mut reader =
+            TsFileSequenceReader::new(Cursor::new(writer.file_io_writer.out), Default::default())?;
+        for chunk in reader
+            .chunk_groups()?
+            .collect::<Result<Vec<_>, _>>()?
+            .iter()
+            .flat_map(|group| group.chunks.iter())
+        {
+            let points = chunk.read_points()?;
+            assert_eq!(50000, points.len());
+            for (i, (timestamp, value)) in points.into_iter().enumerate() {
+                let i = i as i64;
+                assert_eq!(i, timestamp);
+                let expected = match chunk.header.measurement_id.as_str() {
+                    "s1" => IoTDBValue::FLOAT((i % 1000 - 500) as f32 / 100.0),
+                    _ => IoTDBValue::DOUBLE((i * 7) as f64 / 1000.0),
+                };
+                assert_eq!(expected, value);
+            }
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn read_large_text_values() -> Result<(), TsFileError> {
+        let schema = Schema::simple(
+            "d1",
+            "s1",
+            TSDataType::TEXT,
+            TSEncoding::PLAIN,
+            CompressionType::UNCOMPRESSED,
+        );
+        let mut writer = TsFileWriter::new_from_writer(schema, Vec::new(), Default::default())?;
+        let expected: Vec<(i64, IoTDBValue)> = (0..2000_i64)
+            .map(|i| (i, IoTDBValue::from(format!("{:05}", i).repeat(2000))))
+            .collect();
+        for (timestamp, value) in expected.iter() {
+            writer.write("d1", "s1", *timestamp, value.clone())?;
+        }
+        writer.close()?;
+
+        let mut reader =
+            TsFileSequenceReader::new(Cursor::new(writer.file_io_writer.out), Default::default())?;
+        let metadata = reader.read_timeseries_metadata_of("d1", "s1")?.unwrap();
+        assert_eq!(2000, metadata.statistics().count());
+        assert_eq!(
+            Some(expected[0].1.clone()),
+            metadata.statistics().first_value()
+        );
+        assert_eq!(
+            Some(expected[1999].1.clone()),
+            metadata.statistics().last_value()
+        );
+        assert_eq!(None, metadata.statistics().min_value());
+
+        let chunks: Vec<Chunk> = reader
+            .chunk_groups()?
+            .collect::<Result<Vec<_>, _>>()?
+            .into_iter()
+            .flat_map(|group| group.chunks)
+            .collect();
+        // 20 MB of text have to be split into several pages
+        assert!(chunks.iter().map(|c| c.pages.len()).sum::<usize>() > 1);
+        let points: Vec<(i64, IoTDBValue)> = chunks
+            .iter()
+            .map(|chunk| chunk.read_points())
+            .collect::<Result<Vec<_>, TsFileError>>()?
+            .into_iter()
+            .flatten()
+            .collect();
+        assert_eq!(expected, points);
+
+        Ok(())
+    }
+
+    #[test]
+    fn read_aligned_chunks() -> Result<(), TsFileError> {
+        let schema = TsFileSchemaBuilder::new()
+            .add(
+                "d1",
+                AlignedDeviceBuilder::new()
+                    .add(
+                        "s1",
+                        TSDataType::INT64,
+                        TSEncoding::TS2DIFF,
+                        CompressionType::UNCOMPRESSED,
+                    )
+                    .add(
+                        "s2",
+                        TSDataType::TEXT,
+                        TSEncoding::PLAIN,
+                        CompressionType::SNAPPY,
+                    )
+                    .time_compression(CompressionType::SNAPPY)
+                    .build(),
+            )
+            .build();
+        let mut writer = TsFileWriter::new_from_writer(schema, Vec::new(), Default::default())?;
+        for i in 0..100000 {
+            let mut values = vec![DataPoint::new("s1", IoTDBValue::LONG(i))];
+            if i % 3 == 0 {
+                values.push(DataPoint::new("s2", IoTDBValue::from(format!("v{}", i))));
+            }
+            writer.write_many("d1", i, values)?;
+        }
+        // Single measurements are written as rows with nulls for the other measurements
+        writer.write("d1", "s2", 100000, IoTDBValue::from("last"))?;
+        assert_eq!(
+            Err(TsFileError::OutOfOrderData),
+            writer.write("d1", "s1", 100000, IoTDBValue::LONG(0))
+        );
+        assert_eq!(
+            Err(TsFileError::WrongTypeForSeries),
+            writer.write("d1", "s1", 100001, IoTDBValue::INT(0))
+        );
+        writer.close()?;
+
+        let mut reader =
+            TsFileSequenceReader::new(Cursor::new(writer.file_io_writer.out), Default::default())?;
+        let chunk_groups = reader.chunk_groups()?.collect::<Result<Vec<_>, _>>()?;
+        assert_eq!(1, chunk_groups.len());
+        let chunks = &chunk_groups[0].chunks;
+        assert_eq!(3, chunks.len());
+        let time_chunk = &chunks[0];
+        assert_eq!("", time_chunk.header.measurement_id);
+        assert_eq!(TIME_COLUMN_MASK, time_chunk.header.mask);
+        assert_eq!(TSDataType::VECTOR, time_chunk.header.data_type);
+        assert_eq!(CompressionType::SNAPPY, time_chunk.header.compression);
+        assert!(time_chunk.header.num_pages > 1);
+        for value_chunk in &chunks[1..] {
+            assert_eq!(VALUE_COLUMN_MASK, value_chunk.header.mask);
+            assert_eq!(time_chunk.header.num_pages, value_chunk.header.num_pages);
+        }
+
+        let s1 = chunks[1].read_aligned_points(time_chunk)?;
+        assert_eq!(100000, s1.len());
+        assert_eq!((99999, IoTDBValue::LONG(99999)), s1[99999]);
+        let s2 = chunks[2].read_aligned_points(time_chunk)?;
+        assert_eq!(33335, s2.len());
+        assert_eq!((3, IoTDBValue::from("v3")), s2[1]);
+        assert_eq!((100000, IoTDBValue::from("last")), s2[33334]);
+
+        let time_metadata = reader.read_timeseries_metadata_of("d1", "")?.unwrap();
+        assert_eq!(TIME_COLUMN_MASK, time_metadata.mask());
+        assert_eq!(100001, time_metadata.statistics().count());
+        let s2_metadata = reader.read_timeseries_metadata_of("d1", "s2")?.unwrap();
+        assert_eq!(VALUE_COLUMN_MASK, s2_metadata.mask());
+        assert_eq!(33335, s2_metadata.statistics().count());
+        assert!(reader.might_contain("d1.")?);
+
+        Ok(())
+    }
+
+    #[test]
+    fn reject_no_tsfile() {
+        let result = TsFileSequenceReader::new(
+            Cursor::new(b"NoTsFile\x03NoTsFile".to_vec()),
+            Default::default(),
+        );
+        assert!(matches!(result, Err(TsFileError::ReadError { .. })));
+    }
+
+    #[test]
+    fn reject_incomplete_file() -> Result<(), TsFileError> {
+        let mut buffer = write_test_file()?;
+        buffer.truncate(buffer.len() - 100);
+        let result = TsFileSequenceReader::new(Cursor::new(buffer), Default::default());
+        assert!(matches!(result, Err(TsFileError::ReadError { .. })));
+        Ok(())
+    }
+
+    #[test]
+    fn reject_page_beyond_file() -> Result<(), TsFileError> {
+        let buffer = write_test_file()?;
+        let mut reader = TsFileSequenceReader::new(Cursor::new(buffer), Default::default())?;
+        let header = PageHeader {
+            uncompressed_size: 0,
+            compressed_size: u32::MAX,
+            statistics: None,
+        };
+        reader.seek(HEADER_SIZE)?;
+        assert!(matches!(
+            reader.read_page_data(&header),
+            Err(TsFileError::ReadError { .. })
+        ));
+        Ok(())
+    }
+}
@@ -0,0 +1,191 @@
+//! Turns the content of pages back into timestamp / value pairs
+use crate::reader::{Chunk, Page};
+use crate::writer::compression::Compressor;
+use crate::writer::encoding::{Decoder, TSEncoding};
+use crate::writer::errors::TsFileError;
+use crate::writer::utils::{bytes_for_bits, read_i32, read_var_u32};
+use crate::writer::{ChunkHeader, IoTDBValue, TSDataType};
+
+pub struct PageReader {
+    compressor: Box<dyn Compressor>,
+    /// Compressor of the time chunk for value chunks of aligned devices
+    time_compressor: Box<dyn Compressor>,
+    time_decoder: Box<dyn Decoder>,
+    value_decoder: Box<dyn Decoder>,
+}
+
+impl PageReader {
+    /// Creates a page reader for the pages of the chunk with the given header,
+    /// the encoding of the timestamps is not part of the file but of the config
+    pub fn new(header: &ChunkHeader, time_encoding: TSEncoding) -> Result<PageReader, TsFileError> {
+        Ok(PageReader {
+            compressor: <dyn Compressor>::new(header.compression),
+            time_compressor: <dyn Compressor>::new(header.compression),
+            time_decoder: <dyn Decoder>::new(TSDataType::INT64, time_encoding)?,
+            value_decoder: <dyn Decoder>::new(header.data_type, header.encoding)?,
+        })
+    }
+
+    /// Creates a page reader for the pages of a value chunk of an aligned device together
+    /// with the pages of its time chunk, whose header contains the encoding of the timestamps
+    pub fn new_aligned(
+        time_header: &ChunkHeader,
+        value_header: &ChunkHeader,
+    ) -> Result<PageReader, TsFileError> {
+        Ok(PageReader {
+            compressor: <dyn Compressor>::new(value_header.compression),
+            time_compressor: <dyn Compressor>::new(time_header.compression),
+            time_decoder: <dyn Decoder>::new(TSDataType::INT64, time_header.encoding)?,
+            value_decoder: <dyn Decoder>::new(value_header.data_type, value_header.encoding)?,
+        })
+    }
+
+    fn uncompress(compressor: &dyn Compressor, page: &Page) -> Result<Vec<u8>, TsFileError> {
+        let data = compressor.uncompress(&page.data, page.header.uncompressed_size as usize)?;
+        if data.len() != page.header.uncompressed_size as usize {
+            return Err(TsFileError::ReadError {
+                source: Some(format!(
+                    "Expected {} bytes in page but found {}",
+                    page.header.uncompressed_size,
+                    data.len()
+                )),
+            });
+        }
+        Ok(data)
+    }
+
+    /// Reads a page of a value chunk of an aligned device, the time page has to be the
+    /// page at the same position in the time chunk. Null values are skipped.
+    pub fn read_aligned(
+        &mut self,
+        time_page: &Page,
+        value_page: &Page,
+    ) -> Result<Vec<(i64, IoTDBValue)>, TsFileError> {
+        let time_data = Self::uncompress(self.time_compressor.as_ref(), time_page)?;
+        let timestamps = self.time_decoder.decode(&time_data)?;
+
+        // The values are prefixed by the row count and the bitmap of non null rows
+        let data = Self::uncompress(self.compressor.as_ref(), value_page)?;
+        let mut reader = data.as_slice();
+        let row_count = read_i32(&mut reader)?;
+        if row_count < 0 || row_count as usize != timestamps.len() {
+            return Err(TsFileError::ReadError {
+                source: Some(format!(
+                    "Page contains {} timestamps but {} rows",
+                    timestamps.len(),
+                    row_count
+                )),
+            });
+        }
+        let bitmap_size = bytes_for_bits(row_count as usize);
+        if bitmap_size > reader.len() {
+            return Err(TsFileError::ReadError {
+                source: Some("Bitmap exceeds the page".to_owned()),
+            });
+        }
+        let (bitmap, value_buffer) = reader.split_at(bitmap_size);
+        let mut values = self.value_decoder.decode(value_buffer)?.into_iter();
+
+        let mut result = Vec::with_capacity(values.len());
+        for (row, timestamp) in timestamps.iter().enumerate() {
+            if bitmap[row / 8] & (0x80 >> (row % 8)) == 0 {
+                continue;
+            }
+            match values.next() {
+                Some(value) => result.push((i64::try_from(timestamp)?, value)),
+                None => {
+                    return Err(TsFileError::ReadError {
+                        source: Some("Page contains less values than its bitmap".to_owned()),
+                    });
+                }
+            }
+        }
+        if values.next().is_some() {
+            return Err(TsFileError::ReadError {
+                source: Some("Page contains more values than its bitmap".to_owned()),
+            });
+        }
+        Ok(result)
+    }
+
+    pub fn read(&mut self, page: &Page) -> Result<Vec<(i64, IoTDBValue)>, TsFileError> {
+        let data = Self::uncompress(self.compressor.as_ref(), page)?;
+
+        // The time column is prefixed by its length
+        let mut reader = data.as_slice();
+        let time_length = read_var_u32(&mut reader)? as usize;
+        if time_length > reader.len() {
+            return Err(TsFileError::ReadError {
+                source: Some("Time column exceeds the page".to_owned()),
+            });
+        }
+        let (time_buffer, value_buffer) = reader.split_at(time_length);
+
+        let timestamps = self.time_decoder.decode(time_buffer)?;
+        let values = self.value_decoder.decode(value_buffer)?;
+        if timestamps.len() != values.len() {
+            return Err(TsFileError::ReadError {
+                source: Some(format!(
+                    "Page contains {} timestamps but {} values",
+                    timestamps.len(),
+                    values.len()
+                )),
+            });
+        }
+
+        timestamps
+            .iter()
+            .zip(values)
+            .map(|(timestamp, value)| Ok((i64::try_from(timestamp)?, value)))
+            .collect()
+    }
+}
+
+impl Chunk {
+    /// Decodes all points of all pages of this chunk
+    pub fn read_points(&self) -> Result<Vec<(i64, IoTDBValue)>, TsFileError> {
+        let mut page_reader = PageReader::new(&self.header, self.time_encoding)?;
+        let mut result = vec![];
+        for page in self.pages.iter() {
+            result.append(&mut page_reader.read(page)?);
+        }
+        Ok(result)
+    }
+
+    /// Decodes all timestamps of this time chunk of an aligned device
+    pub fn read_timestamps(&self) -> Result<Vec<i64>, TsFileError> {
+        let compressor = <dyn Compressor>::new(self.header.compression);
+        let mut decoder = <dyn Decoder>::new(TSDataType::INT64, self.header.encoding)?;
+        let mut result = vec![];
+        for page in self.pages.iter() {
+            let data = PageReader::uncompress(compressor.as_ref(), page)?;
+            for timestamp in decoder.decode(&data)?.iter() {
+                result.push(i64::try_from(timestamp)?);
+            }
+        }
+        Ok(result)
+    }
+
+    /// Decodes all non null points of this value chunk of an aligned device,
+    /// the time chunk has to be the one of its chunk group
+    pub fn read_aligned_points(
+        &self,
+        time_chunk: &Chunk,
+    ) -> Result<Vec<(i64, IoTDBValue)>, TsFileError> {
+        if time_chunk.pages.len() != self.pages.len() {
+            return Err(TsFileError::ReadError {
+                source: Some(format!(
+                    "Time chunk has {} pages but value chunk {}",
+                    time_chunk.pages.len(),
+                    self.pages.len()
+                )),
+            });
+        }
+        let mut page_reader = PageReader::new_aligned(&time_chunk.header, &self.header)?;
+        let mut result = vec![];
+        for (time_page, value_page) in time_chunk.pages.iter().zip(self.pages.iter()) {
+            result.append(&mut page_reader.read_aligned(time_page, value_page)?);
+        }
+        Ok(result)
+    }
+}
@@ -0,0 +1,565 @@
+//! Queries for a device, a set of its measurements and a time range.
+//!
+//! The series are located via the metadata index. Chunks and pages whose statistics
+//! lie outside of the time range are skipped without being decoded.
+//!
+//! ```
+//! use tsfile_writer::reader::query::TimeRange;
+//! use tsfile_writer::reader::TsFileSequenceReader;
+//! use tsfile_writer::writer::compression::CompressionType;
+//! use tsfile_writer::writer::encoding::TSEncoding;
+//! use tsfile_writer::writer::tsfile_writer::TsFileWriter;
+//! use tsfile_writer::writer::{IoTDBValue, Schema, TSDataType};
+//!
+//! let schema = Schema::simple(
+//!     "d1",
+//!     "s1",
+//!     TSDataType::INT64,
+//!     TSEncoding::PLAIN,
+//!     CompressionType::UNCOMPRESSED,
+//! );
+//! let mut writer = TsFileWriter::new("target/query_doc.tsfile", schema, Default::default()).unwrap();
+//! for i in 0..100 {
+//!     writer.write("d1", "s1", i, IoTDBValue::LONG(i)).unwrap();
+//! }
+//! writer.close().unwrap();
+//!
+//! let mut reader = TsFileSequenceReader::open("target/query_doc.tsfile", Default::default()).unwrap();
+//! let rows = reader.query("d1", &["s1"], TimeRange::new(10, 19)).unwrap();
+//! assert_eq!(10, rows.count());
+//! ```
+use crate::reader::page_reader::PageReader;
+use crate::reader::{Page, TsFileSequenceReader};
+use crate::writer::errors::TsFileError;
+use crate::writer::statistics::Statistics;
+use crate::writer::{ChunkHeader, ChunkMetadata, IoTDBValue};
+use std::collections::VecDeque;
+use std::io::{Read, Seek};
+
+/// Closed interval [start, end] of timestamps
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct TimeRange {
+    pub start: i64,
+    pub end: i64,
+}
+
+impl TimeRange {
+    pub fn new(start: i64, end: i64) -> TimeRange {
+        TimeRange { start, end }
+    }
+
+    /// Range that contains every timestamp
+    pub fn all() -> TimeRange {
+        TimeRange::new(i64::MIN, i64::MAX)
+    }
+
+    pub fn contains(&self, timestamp: i64) -> bool {
+        self.start <= timestamp && timestamp <= self.end
+    }
+
+    /// Checks if this range overlaps with the range [start, end]
+    pub fn overlaps(&self, start: i64, end: i64) -> bool {
+        self.start <= end && start <= self.end
+    }
+
+    /// Checks if the range [start, end] lies completely inside this range
+    pub fn covers(&self, start: i64, end: i64) -> bool {
+        self.start <= start && end <= self.end
+    }
+
+    pub(crate) fn overlaps_statistics(&self, statistics: &Statistics) -> bool {
+        self.overlaps(statistics.start_time(), statistics.end_time())
+    }
+
+    pub(crate) fn covers_statistics(&self, statistics: &Statistics) -> bool {
+        self.covers(statistics.start_time(), statistics.end_time())
+    }
+}
+
+/// One row of a query result, values are in the order of the queried measurements.
+/// A value is `None` if the measurement has no value at this timestamp.
+#[derive(Clone, Debug, PartialEq)]
+pub struct RowRecord {
+    pub timestamp: i64,
+    pub values: Vec<Option<IoTDBValue>>,
+}
+
+/// State of the chunk a series reader currently reads pages from
+struct ChunkState {
+    header: ChunkHeader,
+    page_reader: PageReader,
+    next_page_offset: u64,
+    remaining_pages: u32,
+    /// Header and offset of the next page of the time chunk for value chunks of
+    /// aligned devices
+    time_chunk: Option<(ChunkHeader, u64)>,
+}
+
+/// Reads the points of one series in the time range, one page at a time
+pub(crate) struct SeriesReader {
+    /// Chunks together with their time chunk if the series belongs to an aligned device
+    chunks: VecDeque<(ChunkMetadata, Option<ChunkMetadata>)>,
+    current_chunk: Option<ChunkState>,
+    points: VecDeque<(i64, IoTDBValue)>,
+    time_range: TimeRange,
+}
+
+impl SeriesReader {
+    pub(crate) fn new(
+        chunks: Vec<(ChunkMetadata, Option<ChunkMetadata>)>,
+        time_range: TimeRange,
+    ) -> SeriesReader {
+        SeriesReader {
+            chunks: chunks
+                .into_iter()
+                .filter(|(c, _)| time_range.overlaps_statistics(c.statistics()))
+                .collect(),
+            current_chunk: None,
+            points: VecDeque::new(),
+            time_range,
+        }
+    }
+
+    fn empty(time_range: TimeRange) -> SeriesReader {
+        Self::new(vec![], time_range)
+    }
+
+    /// Opens the next chunk that overlaps with the time range
+    fn open_next_chunk<R: Read + Seek>(
+        &mut self,
+        reader: &mut TsFileSequenceReader<R>,
+    ) -> Result<bool, TsFileError> {
+        let (chunk_metadata, time_chunk_metadata) = match self.chunks.pop_front() {
+            Some(c) => c,
+            None => return Ok(false),
+        };
+        let time_chunk = match time_chunk_metadata {
+            Some(time_chunk_metadata) => {
+                let time_header =
+                    reader.read_chunk_header_at(time_chunk_metadata.offset_of_chunk_header())?;
+                Some((time_header, reader.position()?))
+            }
+            None => None,
+        };
+        let header = reader.read_chunk_header_at(chunk_metadata.offset_of_chunk_header())?;
+        let page_reader = match &time_chunk {
+            Some((time_header, _)) => PageReader::new_aligned(time_header, &header)?,
+            None => PageReader::new(&header, reader.config.time_encoding)?,
+        };
+        self.current_chunk = Some(ChunkState {
+            page_reader,
+            next_page_offset: reader.position()?,
+            remaining_pages: header.num_pages,
+            header,
+            time_chunk,
+        });
+        Ok(true)
+    }
+
+    /// Decodes the next page that overlaps with the time range.
+    /// Returns false if there are no more pages.
+    pub(crate) fn load_next_page<R: Read + Seek>(
+        &mut self,
+        reader: &mut TsFileSequenceReader<R>,
+    ) -> Result<bool, TsFileError> {
+        loop {
+            let chunk = match self.current_chunk.as_mut() {
+                Some(chunk) if chunk.remaining_pages > 0 => chunk,
+                _ => {
+                    if !self.open_next_chunk(reader)? {
+                        return Ok(false);
+                    }
+                    continue;
+                }
+            };
+            reader.seek(chunk.next_page_offset)?;
+            let has_statistics = chunk.header.num_pages > 1;
+            let page_header = reader.read_page_header(chunk.header.data_type, has_statistics)?;
+            let data_offset = reader.position()?;
+            chunk.next_page_offset = data_offset + page_header.compressed_size as u64;
+            chunk.remaining_pages -= 1;
+            // Value pages of aligned devices contain the same rows as the time pages
+            let time_page_header = match chunk.time_chunk.as_mut() {
+                Some((time_header, next_time_page_offset)) => {
+                    reader.seek(*next_time_page_offset)?;
+                    let time_page_header =
+                        reader.read_page_header(time_header.data_type, has_statistics)?;
+                    let time_data_offset = reader.position()?;
+                    *next_time_page_offset =
+                        time_data_offset + time_page_header.compressed_size as u64;
+                    Some((time_page_header, time_data_offset))
+                }
+                None => None,
+            };
+
+            if let Some(statistics) = &page_header.statistics {
+                if !self.time_range.overlaps_statistics(statistics) {
+                    // Skip the page without reading it
+                    continue;
+                }
+            }
+            reader.seek(data_offset)?;
+            let data = reader.read_page_data(&page_header)?;
+            let page = Page {
+                header: page_header,
+                data,
+            };
+            let points = match time_page_header {
+                Some((time_page_header, time_data_offset)) => {
+                    reader.seek(time_data_offset)?;
+                    let time_page = Page {
+                        data: reader.read_page_data(&time_page_header)?,
+                        header: time_page_header,
+                    };
+                    chunk.page_reader.read_aligned(&time_page, &page)?
+                }
+                None => chunk.page_reader.read(&page)?,
+            };
+            let time_range = self.time_range;
+            self.points.extend(
+                points
+                    .into_iter()
+                    .filter(|(timestamp, _)| time_range.contains(*timestamp)),
+            );
+            return Ok(true);
+        }
+    }
+
+    /// Timestamp of the next point, loads pages if necessary
+    pub(crate) fn peek_time<R: Read + Seek>(
+        &mut self,
+        reader: &mut TsFileSequenceReader<R>,
+    ) -> Result<Option<i64>, TsFileError> {
+        while self.points.is_empty() {
+            if !self.load_next_page(reader)? {
+                return Ok(None);
+            }
+        }
+        Ok(self.points.front().map(|(timestamp, _)| *timestamp))
+    }
+
+    pub(crate) fn next_point(&mut self) -> Option<(i64, IoTDBValue)> {
+        self.points.pop_front()
+    }
+}
+
+/// Iterator over the rows of a query, the points are read lazily page by page
+pub struct QueryDataSet<'a, R: Read + Seek> {
+    reader: &'a mut TsFileSequenceReader<R>,
+    series_readers: Vec<SeriesReader>,
+    failed: bool,
+}
+
+impl<'a, R: Read + Seek> QueryDataSet<'a, R> {
+    fn next_row(&mut self) -> Result<Option<RowRecord>, TsFileError> {
+        let mut min_time: Option<i64> = None;
+        for series_reader in self.series_readers.iter_mut() {
+            if let Some(timestamp) = series_reader.peek_time(self.reader)? {
+                min_time = Some(match min_time {
+                    Some(t) if t <= timestamp => t,
+                    _ => timestamp,
+                });
+            }
+        }
+        let timestamp = match min_time {
+            Some(t) => t,
+            None => return Ok(None),
+        };
+        let mut values = Vec::with_capacity(self.series_readers.len());
+        for series_reader in self.series_readers.iter_mut() {
+            if series_reader.peek_time(self.reader)? == Some(timestamp) {
+                values.push(series_reader.next_point().map(|(_, value)| value));
+            } else {
+                values.push(None);
+            }
+        }
+        Ok(Some(RowRecord { timestamp, values }))
+    }
+}
+
+impl<'a, R: Read + Seek> Iterator for QueryDataSet<'a, R> {
+    type Item = Result<RowRecord, TsFileError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.failed {
+            return None;
+        }
+        match self.next_row() {
+            Ok(row) => row.map(Ok),
+            Err(e) => {
+                self.failed = true;
+                Some(Err(e))
+            }
+        }
+    }
+}
+
+impl<R: Read + Seek> TsFileSequenceReader<R> {
+    /// Queries the given measurements of a device in the time range.
+    /// The rows are returned ordered by time, measurements that do not exist in the file
+    /// have no values.
+    pub fn query(
+        &mut self,
+        device: &str,
+        measurements: &[&str],
+        time_range: TimeRange,
+    ) -> Result<QueryDataSet<'_, R>, TsFileError> {
+        let mut series_readers = vec![];
+        for measurement in measurements {
+            let series_reader = match self.read_timeseries_metadata_of(device, measurement)? {
+                Some(timeseries_metadata)
+                    if time_range.overlaps_statistics(timeseries_metadata.statistics()) =>
+                {
+                    SeriesReader::new(
+                        self.chunk_metadata_with_time_chunks(device, &timeseries_metadata)?,
+                        time_range,
+                    )
+                }
+                _ => SeriesReader::empty(time_range),
+            };
+            series_readers.push(series_reader);
+        }
+        Ok(QueryDataSet {
+            reader: self,
+            series_readers,
+            failed: false,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::reader::query::{RowRecord, TimeRange};
+    use crate::reader::TsFileSequenceReader;
+    use crate::writer::compression::CompressionType;
+    use crate::writer::encoding::TSEncoding;
+    use crate::writer::errors::TsFileError;
+    use crate::writer::schema::{AlignedDeviceBuilder, DeviceBuilder, TsFileSchemaBuilder};
+    use crate::writer::tsfile_writer::{DataPoint, TsFileWriter};
+    use crate::writer::{IoTDBValue, TSDataType};
+    use std::io::Cursor;
+
+    fn write_test_file() -> Result<Vec<u8>, TsFileError> {
+        let schema = TsFileSchemaBuilder::new()
+            .add(
+                "root.sg.d1",
+                DeviceBuilder::new()
+                    .add(
+                        "s1",
+                        TSDataType::INT64,
+                        TSEncoding::TS2DIFF,
+                        CompressionType::UNCOMPRESSED,
+                    )
+                    .add(
+                        "s2",
+                        TSDataType::INT32,
+                        TSEncoding::PLAIN,
+                        CompressionType::SNAPPY,
+                    )
+                    .build(),
+            )
+            .add(
+                "root.sg.d2",
+                DeviceBuilder::new()
+                    .add(
+                        "s1",
+                        TSDataType::FLOAT,
+                        TSEncoding::PLAIN,
+                        CompressionType::UNCOMPRESSED,
+                    )
+                    .build(),
+            )
+            .build();
+
+        let mut writer = TsFileWriter::new_from_writer(schema, Vec::new(), Default::default())?;
+        for i in 0..100000 {
+            writer.write("root.sg.d1", "s1", i, IoTDBValue::LONG(i))?;
+            if i % 2 == 0 {
+                writer.write("root.sg.d1", "s2", i, IoTDBValue::INT(i as i32))?;
+            }
+        }
+        writer.write_many(
+            "root.sg.d2",
+            7,
+            vec![DataPoint::new("s1", IoTDBValue::FLOAT(7.0))],
+        )?;
+        writer.close()?;
+
+        Ok(writer.file_io_writer.out)
+    }
+
+    #[test]
+    fn query_time_range() -> Result<(), TsFileError> {
+        let mut reader =
+            TsFileSequenceReader::new(Cursor::new(write_test_file()?), Default::default())?;
+
+        let rows = reader
+            .query("root.sg.d1", &["s1", "s2"], TimeRange::new(50000, 50003))?
+            .collect::<Result<Vec<RowRecord>, TsFileError>>()?;
+
+        assert_eq!(
+            vec![
+                RowRecord {
+                    timestamp: 50000,
+                    values: vec![Some(IoTDBValue::LONG(50000)), Some(IoTDBValue::INT(50000))]
+                },
+                RowRecord {
+                    timestamp: 50001,
+                    values: vec![Some(IoTDBValue::LONG(50001)), None]
+                },
+                RowRecord {
+                    timestamp: 50002,
+                    values: vec![Some(IoTDBValue::LONG(50002)), Some(IoTDBValue::INT(50002))]
+                },
+                RowRecord {
+                    timestamp: 50003,
+                    values: vec![Some(IoTDBValue::LONG(50003)), None]
+                },
+            ],
+            rows
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn query_whole_series() -> Result<(), TsFileError> {
+        let mut reader =
+            TsFileSequenceReader::new(Cursor::new(write_test_file()?), Default::default())?;
+
+        let mut count = 0;
+        for (i, row) in reader
+            .query("root.sg.d1", &["s2"], TimeRange::all())?
+            .enumerate()
+        {
+            let row = row?;
+            assert_eq!(2 * i as i64, row.timestamp);
+            count += 1;
+        }
+        assert_eq!(50000, count);
+
+        let rows = reader
+            .query("root.sg.d2", &["s1"], TimeRange::all())?
+            .collect::<Result<Vec<RowRecord>, TsFileError>>()?;
+        assert_eq!(
+            vec![RowRecord {
+                timestamp: 7,
+                values: vec![Some(IoTDBValue::FLOAT(7.0))]
+            }],
+            rows
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn query_missing_series() -> Result<(), TsFileError> {
+        let mut reader =
+            TsFileSequenceReader::new(Cursor::new(write_test_file()?), Default::default())?;
+
+        assert_eq!(
+            0,
+            reader
+                .query("root.sg.d3", &["s1"], TimeRange::all())?
+                .count()
+        );
+        assert_eq!(
+            0,
+            reader
+                .query("root.sg.d1", &["s3"], TimeRange::all())?
+                .count()
+        );
+        assert_eq!(
+            0,
+            reader
+                .query("root.sg.d1", &["s1"], TimeRange::new(100000, 200000))?
+                .count()
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn read_timeseries_metadata_of() -> Result<(), TsFileError> {
+        let mut reader =
+            TsFileSequenceReader::new(Cursor::new(write_test_file()?), Default::default())?;
+
+        let metadata = reader
+            .read_timeseries_metadata_of("root.sg.d1", "s2")?
+            .unwrap();
+        assert_eq!("s2", metadata.measurement_id());
+        assert_eq!(50000, metadata.statistics().count());
+        assert!(reader
+            .read_timeseries_metadata_of("root.sg.d0", "s1")?
+            .is_none());
+        assert_eq!(
+            2,
+            reader.read_device_timeseries_metadata("root.sg.d1")?.len()
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn query_aligned() -> Result<(), TsFileError> {
+        let schema = TsFileSchemaBuilder::new()
+            .add(
+                "d1",
+                AlignedDeviceBuilder::new()
+                    .add(
+                        "s1",
+                        TSDataType::INT32,
+                        TSEncoding::TS2DIFF,
+                        CompressionType::SNAPPY,
+                    )
+                    .add(
+                        "s2",
+                        TSDataType::FLOAT,
+                        TSEncoding::PLAIN,
+                        CompressionType::UNCOMPRESSED,
+                    )
+                    .build(),
+            )
+            .build();
+        let mut writer = TsFileWriter::new_from_writer(schema, Vec::new(), Default::default())?;
+        for i in 0..100000 {
+            let mut values = vec![];
+            if i % 2 == 0 {
+                values.push(DataPoint::new("s1", IoTDBValue::INT(i as i32)));
+            }
+            if i % 5 == 0 {
+                values.push(DataPoint::new("s2", IoTDBValue::FLOAT(i as f32)));
+            }
+            writer.write_many("d1", i, values)?;
+        }
+        writer.close()?;
+        let mut reader =
+            TsFileSequenceReader::new(Cursor::new(writer.file_io_writer.out), Default::default())?;
+
+        let rows = reader
+            .query("d1", &["s1", "s2"], TimeRange::new(70000, 70003))?
+            .collect::<Result<Vec<RowRecord>, TsFileError>>()?;
+        assert_eq!(
+            vec![
+                RowRecord {
+                    timestamp: 70000,
+                    values: vec![
+                        Some(IoTDBValue::INT(70000)),
+                        Some(IoTDBValue::FLOAT(70000.0))
+                    ]
+                },
+                RowRecord {
+                    timestamp: 70002,
+                    values: vec![Some(IoTDBValue::INT(70002)), None]
+                },
+            ],
+            rows
+        );
+        assert_eq!(
+            20000,
+            reader.query("d1", &["s2"], TimeRange::all())?.count()
+        );
+
+        Ok(())
+    }
+}
@@ -1,78 +1,173 @@
-use crate::writer::encoding::time_encoder::LongTs2DiffEncoder;
+use crate::writer::compression::Compressor;
+use crate::writer::encoding::float::DEFAULT_MAX_POINT_NUMBER;
+#[cfg(test)]
+use crate::writer::encoding::Decoder;
 use crate::writer::encoding::Encoder;
 use crate::writer::statistics::Statistics;
+use crate::writer::ts_file_config::TsFileConfig;
 use crate::writer::tsfile_io_writer::TsFileIoWriter;
+#[cfg(test)]
+use crate::writer::utils::{bytes_for_bits, read_i32, read_var_u32};
 use crate::writer::utils::{size_var_i32, size_var_u32};
 use crate::writer::{
-    utils, write_str, CompressionType, IoTDBValue, PositionedWrite, Serializable, TSDataType,
-    TSEncoding, TsFileError, CHUNK_HEADER, ONLY_ONE_PAGE_CHUNK_HEADER,
+    read_data_type, read_str, utils, write_str, CompressionType, IoTDBValue, PositionedWrite,
+    Serializable, TSDataType, TSEncoding, TsFileError, CHUNK_HEADER, ONLY_ONE_PAGE_CHUNK_HEADER,
+    TIME_COLUMN_MASK, VALUE_COLUMN_MASK,
 };
-use snap::raw::max_compress_len;
 use std::fmt::{Display, Formatter};
 use std::io;
-use std::io::Write;
+use std::io::{Read, Write};
 
-const MAX_NUMBER_OF_POINTS_IN_PAGE: u32 = 1048576;
-const VALUE_COUNT_IN_ONE_PAGE_FOR_NEXT_CHECK: u32 = 7989;
-const PAGE_SIZE_THRESHOLD: u32 = 65536;
+pub(crate) const MAX_NUMBER_OF_POINTS_IN_PAGE: u32 = 1048576;
+pub(crate) const VALUE_COUNT_IN_ONE_PAGE_FOR_NEXT_CHECK: u32 = 7989;
+pub(crate) const PAGE_SIZE_THRESHOLD: u32 = 65536;
 const MINIMUM_RECORD_COUNT_FOR_CHECK: u32 = 1500;
 
 struct PageWriter {
-    time_encoder: LongTs2DiffEncoder,
-    value_encoder: Box<dyn Encoder>,
+    time_encoder: Box<dyn Encoder>,
+    /// None for the time column of aligned devices
+    value_encoder: Option<Box<dyn Encoder>>,
     data_type: TSDataType,
     statistics: Statistics,
     point_number: u32,
+    /// 0 for non aligned series, TIME_COLUMN_MASK or VALUE_COLUMN_MASK for aligned devices
+    mask: u8,
+    /// Marks the non null rows of value columns of aligned devices, most significant bit first
+    bitmap: Vec<u8>,
     // Necessary for writing
     buffer: Vec<u8>,
 }
 
 impl PageWriter {
-    fn new(data_type: TSDataType, encoding: TSEncoding) -> Result<PageWriter, TsFileError> {
+    fn new(
+        data_type: TSDataType,
+        encoding: TSEncoding,
+        max_point_number: u32,
+        time_encoding: TSEncoding,
+        mask: u8,
+    ) -> Result<PageWriter, TsFileError> {
         Ok(PageWriter {
-            time_encoder: LongTs2DiffEncoder::new(),
-            value_encoder: <dyn Encoder>::new(data_type, encoding)?,
+            time_encoder: <dyn Encoder>::new(TSDataType::INT64, time_encoding)?,
+            value_encoder: match mask {
+                TIME_COLUMN_MASK => None,
+                _ => Some(<dyn Encoder>::new_with_max_point_number(
+                    data_type,
+                    encoding,
+                    max_point_number,
+                )?),
+            },
             data_type,
             statistics: Statistics::new(data_type),
             buffer: Vec::with_capacity(65536),
             point_number: 0,
+            mask,
+            bitmap: vec![],
         })
     }
 
     pub(crate) fn reset(&mut self) {
         self.statistics = Statistics::new(self.data_type);
         self.time_encoder.reset();
-        self.value_encoder.reset();
+        if let Some(value_encoder) = self.value_encoder.as_mut() {
+            value_encoder.reset();
+        }
+        self.bitmap.clear();
         self.point_number = 0;
     }
 
     pub(crate) fn estimate_max_mem_size(&mut self) -> u32 {
         let time_encoder_size = self.time_encoder.size();
-        let value_encoder_size = self.value_encoder.size();
         let time_encoder_max_size = self.time_encoder.get_max_byte_size();
-        let value_encoder_max_size = self.value_encoder.get_max_byte_size();
-        let max_size =
-            time_encoder_size + value_encoder_size + time_encoder_max_size + value_encoder_max_size;
+        let (value_encoder_size, value_encoder_max_size) = match self.value_encoder.as_mut() {
+            Some(value_encoder) => (value_encoder.size(), value_encoder.get_max_byte_size()),
+            None => (0, 0),
+        };
+        let bitmap_size = match self.mask {
+            VALUE_COLUMN_MASK => 4 + self.bitmap.len() as u32,
+            _ => 0,
+        };
+        let max_size = time_encoder_size
+            + value_encoder_size
+            + time_encoder_max_size
+            + value_encoder_max_size
+            + bitmap_size;
         log::trace!("Max size estimated for page writer: {}", max_size);
         max_size
     }
 
-    fn write(&mut self, timestamp: i64, value: &mut IoTDBValue) -> Result<u32, &str> {
+    fn write(&mut self, timestamp: i64, value: &mut IoTDBValue) -> Result<u32, TsFileError> {
+        let value_encoder = match self.value_encoder.as_mut() {
+            Some(value_encoder) => value_encoder,
+            None => return Err(TsFileError::WrongTypeForSeries),
+        };
+        // Update the statistics first as they reject values of the wrong type
+        self.statistics.update(timestamp, value)?;
+        // Value columns of aligned devices share the timestamps of the time column
+        if self.mask == 0 {
+            self.time_encoder.write(&timestamp.into());
+        }
+        value_encoder.write(value);
+        self.mark_row(true);
+        self.point_number += 1;
+        Ok(1)
+    }
+
+    /// Writes a timestamp of the time column of an aligned device
+    fn write_time(&mut self, timestamp: i64) -> Result<u32, TsFileError> {
+        self.statistics.update_time(timestamp)?;
         self.time_encoder.write(&timestamp.into());
-        self.value_encoder.write(value);
-        self.statistics.update(timestamp, value);
         self.point_number += 1;
         Ok(1)
     }
 
+    /// Writes a row without value to a value column of an aligned device
+    fn write_null(&mut self) -> Result<u32, TsFileError> {
+        if self.mask != VALUE_COLUMN_MASK {
+            return Err(TsFileError::IllegalState {
+                source: Some("Only aligned series can contain null values".to_owned()),
+            });
+        }
+        self.mark_row(false);
+        self.point_number += 1;
+        Ok(0)
+    }
+
+    /// Adds the current row to the bitmap of value columns
+    fn mark_row(&mut self, not_null: bool) {
+        if self.mask != VALUE_COLUMN_MASK {
+            return;
+        }
+        let index = self.point_number as usize / 8;
+        if index == self.bitmap.len() {
+            self.bitmap.push(0);
+        }
+        if not_null {
+            self.bitmap[index] |= 0x80 >> (self.point_number % 8);
+        }
+    }
+
     pub(crate) fn prepare_buffer(&mut self) {
         // serialize time_encoder and value encoder
         self.buffer.clear();
-        let mut time_buffer = vec![];
-        self.time_encoder.serialize(&mut time_buffer);
-        crate::writer::write_var_u32(time_buffer.len() as u32, &mut self.buffer);
-        self.buffer.write_all(time_buffer.as_slice());
-        self.value_encoder.serialize(&mut self.buffer);
+        match self.mask {
+            // The time column of aligned devices only contains the timestamps
+            TIME_COLUMN_MASK => self.time_encoder.serialize(&mut self.buffer),
+            // Value columns contain the row count, the bitmap and the non null values
+            VALUE_COLUMN_MASK => {
+                self.buffer
+                    .extend_from_slice(&(self.point_number as i32).to_be_bytes());
+                self.buffer.extend_from_slice(&self.bitmap);
+            }
+            _ => {
+                let mut time_buffer = vec![];
+                self.time_encoder.serialize(&mut time_buffer);
+                crate::writer::write_var_u32(time_buffer.len() as u32, &mut self.buffer);
+                self.buffer.write_all(time_buffer.as_slice());
+            }
+        }
+        if let Some(value_encoder) = self.value_encoder.as_mut() {
+            value_encoder.serialize(&mut self.buffer);
+        }
     }
 }
 
@@ -80,8 +175,13 @@ pub struct ChunkWriter {
     pub(crate) measurement_id: String,
     pub(crate) data_type: TSDataType,
     pub compression_type: CompressionType,
+    compressor: Box<dyn Compressor>,
     pub encoding: TSEncoding,
-    #[allow(dead_code)]
+    /// Decimal places kept by TS2DIFF for FLOAT and DOUBLE
+    max_point_number: u32,
+    /// Encoding of the timestamps, for the time column of aligned devices the same as `encoding`
+    time_encoding: TSEncoding,
+    /// 0 for non aligned series, TIME_COLUMN_MASK or VALUE_COLUMN_MASK for aligned devices
     pub(crate) mask: u8,
     #[allow(dead_code)]
     offset_of_chunk_header: Option<u64>,
@@ -92,6 +192,8 @@ pub struct ChunkWriter {
     first_page_statistics: Option<Statistics>,
     value_count_in_one_page_for_next_check: u32,
     size_without_statistics: usize,
+    page_size_threshold: u32,
+    max_number_of_points_in_page: u32,
 }
 
 impl ChunkWriter {
@@ -100,21 +202,108 @@ impl ChunkWriter {
         data_type: TSDataType,
         compression_type: CompressionType,
         encoding: TSEncoding,
+        time_encoding: TSEncoding,
+    ) -> ChunkWriter {
+        Self::new_with_mask(
+            measurement_id,
+            data_type,
+            compression_type,
+            encoding,
+            time_encoding,
+            0,
+        )
+    }
+
+    /// Creates the writer of the time column of an aligned device
+    pub(crate) fn new_time(
+        compression_type: CompressionType,
+        time_encoding: TSEncoding,
+    ) -> ChunkWriter {
+        Self::new_with_mask(
+            "",
+            TSDataType::VECTOR,
+            compression_type,
+            time_encoding,
+            time_encoding,
+            TIME_COLUMN_MASK,
+        )
+    }
+
+    /// Creates the writer of a value column of an aligned device
+    pub(crate) fn new_value(
+        measurement_id: &str,
+        data_type: TSDataType,
+        compression_type: CompressionType,
+        encoding: TSEncoding,
+    ) -> ChunkWriter {
+        // The timestamps are written by the time column only
+        Self::new_with_mask(
+            measurement_id,
+            data_type,
+            compression_type,
+            encoding,
+            TSEncoding::TS2DIFF,
+            VALUE_COLUMN_MASK,
+        )
+    }
+
+    /// Decimal places kept by TS2DIFF for FLOAT and DOUBLE, default is 2
+    pub(crate) fn with_max_point_number(mut self, max_point_number: u32) -> ChunkWriter {
+        self.max_point_number = max_point_number;
+        self
+    }
+
+    /// Page size and point thresholds of the config
+    pub(crate) fn with_page_thresholds(mut self, config: &TsFileConfig) -> ChunkWriter {
+        self.page_size_threshold = config.page_size_in_byte;
+        self.max_number_of_points_in_page = config.max_number_of_points_in_page;
+        if self.data_type != TSDataType::TEXT {
+            self.value_count_in_one_page_for_next_check =
+                config.value_count_in_one_page_for_next_check;
+        }
+        self
+    }
+
+    fn new_with_mask(
+        measurement_id: &str,
+        data_type: TSDataType,
+        compression_type: CompressionType,
+        encoding: TSEncoding,
+        time_encoding: TSEncoding,
+        mask: u8,
     ) -> ChunkWriter {
         ChunkWriter {
             measurement_id: measurement_id.to_owned(),
             data_type,
             compression_type,
+            compressor: <dyn Compressor>::new(compression_type),
             encoding,
-            mask: 0,
+            max_point_number: DEFAULT_MAX_POINT_NUMBER,
+            time_encoding,
+            mask,
             offset_of_chunk_header: None,
             statistics: Statistics::new(data_type),
             current_page_writer: None,
             page_buffer: vec![],
             num_pages: 0,
             first_page_statistics: None,
-            value_count_in_one_page_for_next_check: VALUE_COUNT_IN_ONE_PAGE_FOR_NEXT_CHECK,
+            value_count_in_one_page_for_next_check: match data_type {
+                TSDataType::TEXT => 1,
+                _ => VALUE_COUNT_IN_ONE_PAGE_FOR_NEXT_CHECK,
+            },
             size_without_statistics: 0,
+            page_size_threshold: PAGE_SIZE_THRESHOLD,
+            max_number_of_points_in_page: MAX_NUMBER_OF_POINTS_IN_PAGE,
+        }
+    }
+
+    /// Number of points after which the size of a new page is checked first.
+    /// TEXT values have variable length, so their pages are checked right away and
+    /// the next check is extrapolated from the actual size.
+    fn minimum_record_count_for_check(&self) -> u32 {
+        match self.data_type {
+            TSDataType::TEXT => 1,
+            _ => MINIMUM_RECORD_COUNT_FOR_CHECK,
         }
     }
 
@@ -165,8 +354,7 @@ impl ChunkWriter {
         ChunkMetadata {
             measurement_id: self.measurement_id.clone(),
             data_type: self.data_type,
-            // FIXME add this
-            mask: 0,
+            mask: self.mask,
             offset_of_chunk_header: match self.offset_of_chunk_header {
                 None => {
                     panic!("get_metadata called before offset is defined");
@@ -177,19 +365,21 @@ impl ChunkWriter {
         }
     }
 
-    pub(crate) fn seal_current_page(&mut self) {
+    pub(crate) fn seal_current_page(&mut self) -> Result<(), TsFileError> {
         match &self.current_page_writer {
             None => {}
             Some(pw) => {
                 if pw.point_number > 0 {
-                    self.write_page_to_buffer();
+                    self.write_page_to_buffer()?;
                 }
             }
         }
+        Ok(())
     }
 
     pub(crate) fn get_serialized_chunk_size(&self) -> u64 {
-        if self.page_buffer.is_empty() {
+        // Chunks without values, e.g. value columns with only nulls, are not written
+        if self.page_buffer.is_empty() || self.statistics.count() == 0 {
             0
         } else {
             let measurement_length = self.measurement_id.len() as i32;
@@ -208,8 +398,8 @@ impl ChunkWriter {
     pub(crate) fn write_to_file_writer<T: PositionedWrite>(
         &mut self,
         file_writer: &mut TsFileIoWriter<T>,
-    ) {
-        self.seal_current_page();
+    ) -> Result<(), TsFileError> {
+        self.seal_current_page()?;
         self.write_all_pages_of_chunk_to_ts_file(file_writer, &self.statistics);
 
         // re-init this chunk writer
@@ -217,6 +407,7 @@ impl ChunkWriter {
         self.num_pages = 0;
         self.first_page_statistics = None;
         self.statistics = Statistics::new(self.data_type);
+        Ok(())
     }
 
     fn write_all_pages_of_chunk_to_ts_file<T: PositionedWrite>(
@@ -235,7 +426,7 @@ impl ChunkWriter {
             statistics.clone(),
             self.page_buffer.len() as u32,
             self.num_pages,
-            0,
+            self.mask,
         );
 
         let data_offset = file_writer.out.get_position();
@@ -280,54 +471,129 @@ impl ChunkWriter {
         }
     }
 
+    fn current_page_writer(&mut self) -> Result<&mut PageWriter, TsFileError> {
+        if self.current_page_writer.is_none() {
+            // Create a page
+            self.current_page_writer = Some(PageWriter::new(
+                self.data_type,
+                self.encoding,
+                self.max_point_number,
+                self.time_encoding,
+                self.mask,
+            )?);
+        }
+        match self.current_page_writer.as_mut() {
+            Some(page_writer) => Ok(page_writer),
+            None => panic!("Something bad happened!"),
+        }
+    }
+
     pub fn write(&mut self, timestamp: i64, mut value: IoTDBValue) -> Result<u32, TsFileError> {
-        // self.statistics.update(timestamp, &value);
-        match &mut self.current_page_writer {
-            None => {
-                // Create a page
-                self.current_page_writer = Some(PageWriter::new(self.data_type, self.encoding)?)
-            }
-            Some(_) => {
-                // do nothing
-            }
+        let records_written = self.current_page_writer()?.write(timestamp, &mut value)?;
+        // Pages of aligned devices are sealed by the group writer as they have to contain the
+        // same rows
+        if self.mask == 0 {
+            self.check_page_size_and_may_open_new_page()?;
         }
-        let records_written = match &mut self.current_page_writer {
-            None => {
-                panic!("Something bad happened!");
+        Ok(records_written)
+    }
+
+    /// Writes a timestamp to the time column of an aligned device
+    pub(crate) fn write_time(&mut self, timestamp: i64) -> Result<u32, TsFileError> {
+        self.current_page_writer()?.write_time(timestamp)
+    }
+
+    /// Writes a null to a value column of an aligned device
+    pub(crate) fn write_null(&mut self) -> Result<u32, TsFileError> {
+        self.current_page_writer()?.write_null()
+    }
+
+    /// Checks the size of the current pages of an aligned device, i.e. of this time column
+    /// and the given value columns, and seals all of them together if it exceeds the
+    /// threshold
+    pub(crate) fn check_aligned_page_size_and_may_open_new_page<'b>(
+        &mut self,
+        value_chunk_writers: impl IntoIterator<Item = &'b mut ChunkWriter>,
+    ) -> Result<(), TsFileError> {
+        let point_number = match &self.current_page_writer {
+            Some(page_writer) => page_writer.point_number,
+            None => return Ok(()),
+        };
+        let mut value_chunk_writers: Vec<&mut ChunkWriter> =
+            value_chunk_writers.into_iter().collect();
+        // Variable length values in any column require a check with the first value
+        let minimum_record_count_for_check = value_chunk_writers
+            .iter()
+            .map(|w| w.minimum_record_count_for_check())
+            .fold(self.minimum_record_count_for_check(), u32::min);
+        if point_number == 1 {
+            self.value_count_in_one_page_for_next_check = self
+                .value_count_in_one_page_for_next_check
+                .min(minimum_record_count_for_check);
+        }
+        let seal = if point_number > self.max_number_of_points_in_page {
+            true
+        } else if point_number >= self.value_count_in_one_page_for_next_check {
+            let mut current_page_size = self.estimate_current_page_size();
+            for value_chunk_writer in value_chunk_writers.iter_mut() {
+                current_page_size += value_chunk_writer.estimate_current_page_size();
             }
-            Some(page_writer) => page_writer.write(timestamp, &mut value).unwrap(),
+            if current_page_size > self.page_size_threshold {
+                self.value_count_in_one_page_for_next_check = minimum_record_count_for_check;
+                true
+            } else {
+                self.value_count_in_one_page_for_next_check =
+                    ((self.page_size_threshold as f32) / (current_page_size as f32)
+                        * (point_number as f32)) as u32;
+                false
+            }
+        } else {
+            false
         };
-        self.check_page_size_and_may_open_new_page();
-        Ok(records_written)
+        if seal {
+            self.write_page_to_buffer()?;
+            for value_chunk_writer in value_chunk_writers {
+                value_chunk_writer.write_page_to_buffer()?;
+            }
+        }
+        Ok(())
+    }
+
+    fn estimate_current_page_size(&mut self) -> u32 {
+        match self.current_page_writer.as_mut() {
+            Some(page_writer) => page_writer.estimate_max_mem_size(),
+            None => 0,
+        }
     }
 
-    fn check_page_size_and_may_open_new_page(&mut self) {
+    fn check_page_size_and_may_open_new_page(&mut self) -> Result<(), TsFileError> {
         if self.current_page_writer.is_none() {
-            return;
+            return Ok(());
         }
         let page_writer = self.current_page_writer.as_mut().unwrap();
-        if page_writer.point_number > MAX_NUMBER_OF_POINTS_IN_PAGE {
-            self.write_page_to_buffer();
+        if page_writer.point_number > self.max_number_of_points_in_page {
+            self.write_page_to_buffer()?;
         } else if page_writer.point_number >= self.value_count_in_one_page_for_next_check {
             let current_page_size = page_writer.estimate_max_mem_size();
 
-            if current_page_size > PAGE_SIZE_THRESHOLD {
+            if current_page_size > self.page_size_threshold {
                 log::trace!(
             "enough size, write page {}, pageSizeThreshold:{}, currentPateSize:{}, valueCountInOnePage:{}",
             self.measurement_id.clone(),
-            PAGE_SIZE_THRESHOLD,
+            self.page_size_threshold,
             current_page_size,
             page_writer.point_number);
-                self.write_page_to_buffer();
-                self.value_count_in_one_page_for_next_check = MINIMUM_RECORD_COUNT_FOR_CHECK;
+                self.write_page_to_buffer()?;
+                self.value_count_in_one_page_for_next_check = self.minimum_record_count_for_check();
             } else {
                 // valueCountInOnePageForNextCheck =
                 //     (int) (((float) pageSizeThreshold / currentPageSize) * pageWriter.getPointNumber());
                 self.value_count_in_one_page_for_next_check =
-                    ((PAGE_SIZE_THRESHOLD as f32) / (current_page_size as f32)
+                    ((self.page_size_threshold as f32) / (current_page_size as f32)
                         * (page_writer.point_number as f32)) as u32;
             }
         }
+        Ok(())
     }
 
     //   private void checkPageSizeAndMayOpenANewPage() {
@@ -359,134 +625,44 @@ impl ChunkWriter {
         if let Some(page_writer) = self.current_page_writer.as_mut() {
             page_writer.prepare_buffer();
 
-            let buffer_size: u32 = page_writer.buffer.len() as u32;
-
-            let uncompressed_bytes = buffer_size;
-            let mut compressed_buffer: Vec<u8> = Vec::new();
-
-            let compressed_bytes = match self.compression_type {
-                CompressionType::UNCOMPRESSED => uncompressed_bytes,
-                CompressionType::SNAPPY => {
-                    let mut encoder1 = snap::raw::Encoder::new();
-                    let out_buffer_len = max_compress_len(page_writer.buffer.len());
-                    let mut out = vec![0; out_buffer_len];
-                    match encoder1.compress(&page_writer.buffer, out.as_mut_slice()) {
-                        Ok(size) => {
-                            let mut reader = &out.as_mut_slice()[..size];
-                            io::copy(&mut reader, &mut compressed_buffer);
-                            size as u32
-                        }
-                        Err(_) => {
-                            return Err(TsFileError::Compression);
-                        }
-                    }
-                }
-            };
-
-            // TODO we need a change here if multiple pages exist
-            if self.num_pages == 0 {
-                // Uncompressed size
-                self.size_without_statistics +=
-                    utils::write_var_u32(uncompressed_bytes as u32, &mut self.page_buffer)?
-                        as usize;
-                // Compressed size
-                self.size_without_statistics +=
-                    utils::write_var_u32(compressed_bytes as u32, &mut self.page_buffer)? as usize;
-
-                // Write page content
-                match self.compression_type {
-                    CompressionType::UNCOMPRESSED => {
-                        self.page_buffer.write_all(&page_writer.buffer);
-                    }
-                    _ => {
-                        self.page_buffer.write_all(&compressed_buffer);
-                    }
-                }
-
-                page_writer.buffer.clear();
-
-                self.first_page_statistics = Some(page_writer.statistics.clone())
-            } else if self.num_pages == 1 {
-                let temp = self.page_buffer.clone();
-                self.page_buffer.clear();
-
-                log::trace!("Page Buffer offset: {}", self.page_buffer.get_position());
-                let header_bytes = &temp[0..self.size_without_statistics];
-                self.page_buffer.write_all(header_bytes);
-                log::trace!("Page Buffer offset: {}", self.page_buffer.get_position());
-                match &self.first_page_statistics {
-                    Some(stat) => stat.serialize(&mut self.page_buffer),
-                    _ => panic!("This should not happen!"),
+            let uncompressed_bytes = page_writer.buffer.len() as u32;
+            let compressed_buffer = self.compressor.compress(&page_writer.buffer)?;
+            let compressed_bytes = compressed_buffer.len() as u32;
+
+            if self.num_pages == 1 {
+                // The chunk gets a second page, so the first page needs its statistics, too
+                let temp = std::mem::take(&mut self.page_buffer);
+                let (header_bytes, remainder_bytes) = temp.split_at(self.size_without_statistics);
+                self.page_buffer.extend_from_slice(header_bytes);
+                match self.first_page_statistics.take() {
+                    Some(stat) => stat.serialize(&mut self.page_buffer)?,
+                    None => panic!("This should not happen!"),
                 };
-                log::trace!("Page Buffer offset: {}", self.page_buffer.get_position());
-                let remainder_bytes = &temp[self.size_without_statistics..];
-                self.page_buffer.write_all(remainder_bytes);
-                log::trace!("Page Buffer offset: {}", self.page_buffer.get_position());
-                // Uncompressed size
-                utils::write_var_u32(uncompressed_bytes as u32, &mut self.page_buffer);
-                // Compressed size
-                utils::write_var_u32(compressed_bytes as u32, &mut self.page_buffer);
-                log::trace!("Page Buffer offset: {}", self.page_buffer.get_position());
-                // Write page content
-                log::trace!("Statistics: {:?}", &page_writer.statistics);
-                page_writer.statistics.serialize(&mut self.page_buffer);
-
-                log::trace!(
-                    "Flushing page at page buffer offset {}",
-                    self.page_buffer.get_position()
-                );
-
-                let pos_before_flush = self.page_buffer.get_position();
-
-                // Write page content
-                match self.compression_type {
-                    CompressionType::UNCOMPRESSED => {
-                        self.page_buffer.write_all(&page_writer.buffer);
-                    }
-                    _ => {
-                        self.page_buffer.write_all(&compressed_buffer);
-                    }
-                }
-
-                let pos_after_flush = self.page_buffer.get_position();
-
-                log::trace!(
-                    "Wrote {} bytes to page buffer",
-                    pos_after_flush - pos_before_flush
-                );
-
-                log::trace!("Page Buffer offset: {}", self.page_buffer.get_position());
+                self.page_buffer.extend_from_slice(remainder_bytes);
+            }
 
-                page_writer.buffer.clear();
-                self.first_page_statistics = None;
+            // Uncompressed size
+            let mut header_size = utils::write_var_u32(uncompressed_bytes, &mut self.page_buffer)?;
+            // Compressed size
+            header_size += utils::write_var_u32(compressed_bytes, &mut self.page_buffer)?;
+            if self.num_pages == 0 {
+                // The statistics of a chunk with a single page are only stored in the chunk
+                self.size_without_statistics = header_size as usize;
+                self.first_page_statistics = Some(page_writer.statistics.clone());
             } else {
-                // Uncompressed size
-                utils::write_var_u32(uncompressed_bytes as u32, &mut self.page_buffer);
-                // Compressed size
-                utils::write_var_u32(compressed_bytes as u32, &mut self.page_buffer);
-                // Write page content
-                page_writer.statistics.serialize(&mut self.page_buffer);
-
-                let pos_before_flush = self.page_buffer.get_position();
-
-                // Write page content
-                match self.compression_type {
-                    CompressionType::UNCOMPRESSED => {
-                        self.page_buffer.write_all(&page_writer.buffer);
-                    }
-                    _ => {
-                        self.page_buffer.write_all(&compressed_buffer);
-                    }
-                }
+                log::trace!("Statistics: {:?}", &page_writer.statistics);
+                page_writer.statistics.serialize(&mut self.page_buffer)?;
+            }
 
-                let pos_after_flush = self.page_buffer.get_position();
+            // Write page content
+            self.page_buffer.extend_from_slice(&compressed_buffer);
+            log::trace!(
+                "Wrote {} bytes to page buffer, offset is {}",
+                compressed_bytes,
+                self.page_buffer.get_position()
+            );
 
-                log::trace!(
-                    "Wrote {} bytes to page buffer",
-                    pos_after_flush - pos_before_flush
-                );
-                page_writer.buffer.clear();
-            }
+            page_writer.buffer.clear();
             self.num_pages += 1;
             self.statistics.merge(&page_writer.statistics);
             page_writer.reset();
@@ -534,6 +710,44 @@ impl ChunkHeader {
         Ok(())
     }
 
+    /// Reads the header after its marker.
+    /// The number of pages is not part of the serialized header, so it is only known here
+    /// for chunks with a single page. Otherwise it is left at 0 and has to be determined
+    /// by reading the pages.
+    pub(crate) fn deserialize(
+        marker: u8,
+        reader: &mut dyn Read,
+    ) -> Result<ChunkHeader, TsFileError> {
+        let measurement_id = read_str(reader)?;
+        let data_size = utils::read_var_u32(reader)?;
+        let data_type = read_data_type(reader)?;
+        let compression_byte = utils::read_byte(reader)?;
+        let compression = compression_byte
+            .try_into()
+            .map_err(|_| TsFileError::ReadError {
+                source: Some(format!("Unknown compression type {}", compression_byte)),
+            })?;
+        let encoding_byte = utils::read_byte(reader)?;
+        let encoding = encoding_byte
+            .try_into()
+            .map_err(|_| TsFileError::ReadError {
+                source: Some(format!("Unknown encoding {}", encoding_byte)),
+            })?;
+        let num_pages = match marker & 0x3F {
+            ONLY_ONE_PAGE_CHUNK_HEADER => 1,
+            _ => 0,
+        };
+        Ok(ChunkHeader {
+            measurement_id,
+            data_size,
+            data_type,
+            compression,
+            encoding,
+            num_pages,
+            mask: marker & 0xC0,
+        })
+    }
+
     pub(crate) fn new(
         measurement_id: String,
         data_size: u32,
@@ -565,6 +779,27 @@ pub struct ChunkMetadata {
 }
 
 impl ChunkMetadata {
+    pub fn measurement_id(&self) -> &str {
+        self.measurement_id.as_str()
+    }
+
+    pub fn data_type(&self) -> TSDataType {
+        self.data_type
+    }
+
+    pub fn mask(&self) -> u8 {
+        self.mask
+    }
+
+    /// Position of the chunk header in the file
+    pub fn offset_of_chunk_header(&self) -> u64 {
+        self.offset_of_chunk_header as u64
+    }
+
+    pub fn statistics(&self) -> &Statistics {
+        &self.statistics
+    }
+
     pub(crate) fn new(
         measurement_id: String,
         data_type: TSDataType,
@@ -599,3 +834,140 @@ impl Display for ChunkMetadata {
         write!(f, "{} (...)", self.measurement_id)
     }
 }
+
+#[cfg(test)]
+impl ChunkWriter {
+    /// Decodes the timestamps and the non null values of the current page, the page can not
+    /// be continued afterwards
+    pub(crate) fn decode_current_page(
+        &mut self,
+    ) -> Result<(Vec<i64>, Vec<IoTDBValue>), TsFileError> {
+        let page_writer = self.current_page_writer()?;
+        page_writer.prepare_buffer();
+        let data = page_writer.buffer.clone();
+        let mut buffer = data.as_slice();
+        let (time_buffer, value_buffer) = match self.mask {
+            TIME_COLUMN_MASK => (buffer, None),
+            // Value columns only contain the values of the non null rows
+            VALUE_COLUMN_MASK => {
+                let row_count = read_i32(&mut buffer)? as usize;
+                (&[][..], Some(&buffer[bytes_for_bits(row_count)..]))
+            }
+            _ => {
+                let time_length = read_var_u32(&mut buffer)? as usize;
+                let (time_buffer, value_buffer) = buffer.split_at(time_length);
+                (time_buffer, Some(value_buffer))
+            }
+        };
+        let timestamps = <dyn Decoder>::new(TSDataType::INT64, self.time_encoding)?
+            .decode(time_buffer)?
+            .iter()
+            .map(i64::try_from)
+            .collect::<Result<Vec<_>, _>>()?;
+        let values = match value_buffer {
+            Some(value_buffer) => {
+                <dyn Decoder>::new(self.data_type, self.encoding)?.decode(value_buffer)?
+            }
+            None => vec![],
+        };
+        Ok((timestamps, values))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::writer::chunk_writer::ChunkWriter;
+    use crate::writer::ts_file_config::TsFileConfigBuilder;
+    use crate::writer::{CompressionType, IoTDBValue, TSDataType, TSEncoding, TsFileError};
+
+    fn chunk_writer() -> ChunkWriter {
+        ChunkWriter::new(
+            "s1",
+            TSDataType::INT64,
+            CompressionType::UNCOMPRESSED,
+            TSEncoding::PLAIN,
+            TSEncoding::TS2DIFF,
+        )
+    }
+
+    /// Writes the points and returns the number of pages of the chunk
+    fn write_pages(chunk_writer: &mut ChunkWriter, points: i64) -> Result<u32, TsFileError> {
+        for timestamp in 0..points {
+            chunk_writer.write(timestamp, IoTDBValue::LONG(timestamp))?;
+        }
+        chunk_writer.seal_current_page()?;
+        assert_eq!(points as u32, chunk_writer.statistics.count());
+        Ok(chunk_writer.num_pages)
+    }
+
+    #[test]
+    fn page_thresholds() -> Result<(), TsFileError> {
+        // The page size is not checked before 7989 points by default
+        assert_eq!(1, write_pages(&mut chunk_writer(), 2000)?);
+
+        // A page is sealed once it exceeds the maximal number of points
+        let config = TsFileConfigBuilder::new()
+            .max_number_of_points_in_page(50)
+            .build()?;
+        let mut limited = chunk_writer().with_page_thresholds(&config);
+        assert_eq!(40, write_pages(&mut limited, 2000)?);
+
+        // The first page is checked after 10 points and sealed at 1024 bytes, the next
+        // check follows after 1500 points like in IoTDB
+        let config = TsFileConfigBuilder::new()
+            .page_size_in_byte(1024)
+            .value_count_in_one_page_for_next_check(10)
+            .build()?;
+        let mut small = chunk_writer().with_page_thresholds(&config);
+        assert_eq!(3, write_pages(&mut small, 2000)?);
+        Ok(())
+    }
+
+    #[test]
+    fn time_encodings() -> Result<(), TsFileError> {
+        // Jittery timestamps
+        let timestamps: Vec<i64> = (0..1000_i64).map(|i| i * 1000 + (i * 7919) % 13).collect();
+        let values: Vec<IoTDBValue> = (0..1000).map(IoTDBValue::INT).collect();
+        for time_encoding in [
+            TSEncoding::TS2DIFF,
+            TSEncoding::PLAIN,
+            TSEncoding::RLE,
+            TSEncoding::GORILLA,
+            TSEncoding::ZIGZAG,
+            TSEncoding::CHIMP,
+            TSEncoding::SPRINTZ,
+        ] {
+            let mut chunk_writer = ChunkWriter::new(
+                "s1",
+                TSDataType::INT32,
+                CompressionType::UNCOMPRESSED,
+                TSEncoding::PLAIN,
+                time_encoding,
+            );
+            for (timestamp, value) in timestamps.iter().zip(values.iter()) {
+                chunk_writer.write(*timestamp, value.clone())?;
+            }
+            assert_eq!(
+                (timestamps.clone(), values.clone()),
+                chunk_writer.decode_current_page()?,
+                "{:?}",
+                time_encoding
+            );
+
+            // The time column of aligned devices stores the encoding in its chunk header
+            let mut time_chunk_writer =
+                ChunkWriter::new_time(CompressionType::UNCOMPRESSED, time_encoding);
+            for timestamp in timestamps.iter() {
+                time_chunk_writer.write_time(*timestamp)?;
+            }
+            assert_eq!(time_encoding, time_chunk_writer.encoding);
+            assert_eq!(
+                (timestamps.clone(), vec![]),
+                time_chunk_writer.decode_current_page()?,
+                "{:?}",
+                time_encoding
+            );
+        }
+        Ok(())
+    }
+}
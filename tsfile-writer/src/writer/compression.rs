@@ -1,10 +1,29 @@
 //! Contains the compression algorithms
+//!
+//! UNCOMPRESSED and SNAPPY are always available, the other types are enabled by the
+//! cargo features `gzip`, `lz4`, `zstd` and `lzma2`. Each type is implemented by a
+//! `Compressor`, which is used by the `ChunkWriter` and the `PageReader`.
+use crate::writer::errors::TsFileError;
 use crate::writer::CompressionType::{SNAPPY, UNCOMPRESSED};
+#[cfg(any(feature = "gzip", feature = "lzma2"))]
+use std::io::{Read, Write};
 
 #[derive(PartialEq, Copy, Clone, Debug)]
 pub enum CompressionType {
     UNCOMPRESSED,
     SNAPPY,
+    /// Gzip format, like the `GZIPOutputStream` used by IoTDB
+    #[cfg(feature = "gzip")]
+    GZIP,
+    /// LZ4 block format without size prefix
+    #[cfg(feature = "lz4")]
+    LZ4,
+    /// Zstandard frame with the default level 3
+    #[cfg(feature = "zstd")]
+    ZSTD,
+    /// XZ format with LZMA2 and the default preset 6, like the `XZOutputStream` used by IoTDB
+    #[cfg(feature = "lzma2")]
+    LZMA2,
 }
 
 impl TryFrom<u8> for CompressionType {
@@ -14,6 +33,14 @@ impl TryFrom<u8> for CompressionType {
         match value {
             0x00 => Ok(UNCOMPRESSED),
             0x01 => Ok(SNAPPY),
+            #[cfg(feature = "gzip")]
+            0x02 => Ok(CompressionType::GZIP),
+            #[cfg(feature = "lz4")]
+            0x07 => Ok(CompressionType::LZ4),
+            #[cfg(feature = "zstd")]
+            0x08 => Ok(CompressionType::ZSTD),
+            #[cfg(feature = "lzma2")]
+            0x09 => Ok(CompressionType::LZMA2),
             _ => Err(()),
         }
     }
@@ -24,6 +51,239 @@ impl CompressionType {
         match self {
             CompressionType::UNCOMPRESSED => 0x00,
             CompressionType::SNAPPY => 0x01,
+            #[cfg(feature = "gzip")]
+            CompressionType::GZIP => 0x02,
+            #[cfg(feature = "lz4")]
+            CompressionType::LZ4 => 0x07,
+            #[cfg(feature = "zstd")]
+            CompressionType::ZSTD => 0x08,
+            #[cfg(feature = "lzma2")]
+            CompressionType::LZMA2 => 0x09,
+        }
+    }
+}
+
+/// Compresses and uncompresses the content of pages, like `ICompressor` and `IUnCompressor`
+/// of IoTDB. `<dyn Compressor>::new` selects the implementation of a `CompressionType`.
+/// Compressors are `Send`, so that writers can be moved to other threads.
+pub trait Compressor: Send {
+    fn compress(&self, data: &[u8]) -> Result<Vec<u8>, TsFileError>;
+    /// `uncompressed_size` is taken from the page header
+    fn uncompress(&self, data: &[u8], uncompressed_size: usize) -> Result<Vec<u8>, TsFileError>;
+    /// Upper bound of the compressed size of `uncompressed_len` bytes
+    fn max_compressed_len(&self, uncompressed_len: usize) -> usize;
+}
+
+impl dyn Compressor {
+    pub fn new(compression: CompressionType) -> Box<dyn Compressor> {
+        match compression {
+            CompressionType::UNCOMPRESSED => Box::new(NoCompressor {}),
+            CompressionType::SNAPPY => Box::new(SnappyCompressor {}),
+            #[cfg(feature = "gzip")]
+            CompressionType::GZIP => Box::new(GzipCompressor {}),
+            #[cfg(feature = "lz4")]
+            CompressionType::LZ4 => Box::new(Lz4Compressor {}),
+            #[cfg(feature = "zstd")]
+            CompressionType::ZSTD => Box::new(ZstdCompressor {}),
+            #[cfg(feature = "lzma2")]
+            CompressionType::LZMA2 => Box::new(Lzma2Compressor {}),
+        }
+    }
+}
+
+pub struct NoCompressor {}
+
+impl Compressor for NoCompressor {
+    fn compress(&self, data: &[u8]) -> Result<Vec<u8>, TsFileError> {
+        Ok(data.to_vec())
+    }
+
+    fn uncompress(&self, data: &[u8], _uncompressed_size: usize) -> Result<Vec<u8>, TsFileError> {
+        Ok(data.to_vec())
+    }
+
+    fn max_compressed_len(&self, uncompressed_len: usize) -> usize {
+        uncompressed_len
+    }
+}
+
+pub struct SnappyCompressor {}
+
+impl Compressor for SnappyCompressor {
+    fn compress(&self, data: &[u8]) -> Result<Vec<u8>, TsFileError> {
+        let mut out = vec![0; self.max_compressed_len(data.len())];
+        let size = snap::raw::Encoder::new()
+            .compress(data, out.as_mut_slice())
+            .map_err(|_| TsFileError::Compression)?;
+        out.truncate(size);
+        Ok(out)
+    }
+
+    fn uncompress(&self, data: &[u8], _uncompressed_size: usize) -> Result<Vec<u8>, TsFileError> {
+        snap::raw::Decoder::new()
+            .decompress_vec(data)
+            .map_err(|_| TsFileError::Compression)
+    }
+
+    fn max_compressed_len(&self, uncompressed_len: usize) -> usize {
+        snap::raw::max_compress_len(uncompressed_len)
+    }
+}
+
+#[cfg(feature = "gzip")]
+pub struct GzipCompressor {}
+
+#[cfg(feature = "gzip")]
+impl Compressor for GzipCompressor {
+    fn compress(&self, data: &[u8]) -> Result<Vec<u8>, TsFileError> {
+        let out = Vec::with_capacity(self.max_compressed_len(data.len()));
+        let mut encoder = flate2::write::GzEncoder::new(out, flate2::Compression::default());
+        encoder
+            .write_all(data)
+            .map_err(|_| TsFileError::Compression)?;
+        encoder.finish().map_err(|_| TsFileError::Compression)
+    }
+
+    fn uncompress(&self, data: &[u8], uncompressed_size: usize) -> Result<Vec<u8>, TsFileError> {
+        let mut result = Vec::with_capacity(uncompressed_size);
+        flate2::read::GzDecoder::new(data)
+            .read_to_end(&mut result)
+            .map_err(|_| TsFileError::Compression)?;
+        Ok(result)
+    }
+
+    fn max_compressed_len(&self, uncompressed_len: usize) -> usize {
+        // Stored blocks in the worst case, plus the gzip header and trailer
+        uncompressed_len + 5 * (uncompressed_len / 16383 + 1) + 18
+    }
+}
+
+#[cfg(feature = "lz4")]
+pub struct Lz4Compressor {}
+
+#[cfg(feature = "lz4")]
+impl Compressor for Lz4Compressor {
+    fn compress(&self, data: &[u8]) -> Result<Vec<u8>, TsFileError> {
+        Ok(lz4_flex::block::compress(data))
+    }
+
+    fn uncompress(&self, data: &[u8], uncompressed_size: usize) -> Result<Vec<u8>, TsFileError> {
+        lz4_flex::block::decompress(data, uncompressed_size).map_err(|_| TsFileError::Compression)
+    }
+
+    fn max_compressed_len(&self, uncompressed_len: usize) -> usize {
+        lz4_flex::block::get_maximum_output_size(uncompressed_len)
+    }
+}
+
+#[cfg(feature = "zstd")]
+pub struct ZstdCompressor {}
+
+#[cfg(feature = "zstd")]
+impl Compressor for ZstdCompressor {
+    fn compress(&self, data: &[u8]) -> Result<Vec<u8>, TsFileError> {
+        zstd::bulk::compress(data, 3).map_err(|_| TsFileError::Compression)
+    }
+
+    fn uncompress(&self, data: &[u8], uncompressed_size: usize) -> Result<Vec<u8>, TsFileError> {
+        zstd::bulk::decompress(data, uncompressed_size).map_err(|_| TsFileError::Compression)
+    }
+
+    fn max_compressed_len(&self, uncompressed_len: usize) -> usize {
+        zstd::zstd_safe::compress_bound(uncompressed_len)
+    }
+}
+
+#[cfg(feature = "lzma2")]
+pub struct Lzma2Compressor {}
+
+#[cfg(feature = "lzma2")]
+impl Compressor for Lzma2Compressor {
+    fn compress(&self, data: &[u8]) -> Result<Vec<u8>, TsFileError> {
+        let out = Vec::with_capacity(self.max_compressed_len(data.len()));
+        let mut encoder = xz2::write::XzEncoder::new(out, 6);
+        encoder
+            .write_all(data)
+            .map_err(|_| TsFileError::Compression)?;
+        encoder.finish().map_err(|_| TsFileError::Compression)
+    }
+
+    fn uncompress(&self, data: &[u8], uncompressed_size: usize) -> Result<Vec<u8>, TsFileError> {
+        let mut result = Vec::with_capacity(uncompressed_size);
+        xz2::read::XzDecoder::new(data)
+            .read_to_end(&mut result)
+            .map_err(|_| TsFileError::Compression)?;
+        Ok(result)
+    }
+
+    fn max_compressed_len(&self, uncompressed_len: usize) -> usize {
+        // Uncompressed LZMA2 chunks of 64 KiB with a header of 3 bytes in the worst case,
+        // plus the headers, index and footer of the xz stream
+        uncompressed_len + 3 * (uncompressed_len / 65536 + 1) + 1 + 1024 + 64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::writer::compression::{CompressionType, Compressor};
+
+    fn all_types() -> Vec<CompressionType> {
+        vec![
+            CompressionType::UNCOMPRESSED,
+            CompressionType::SNAPPY,
+            #[cfg(feature = "gzip")]
+            CompressionType::GZIP,
+            #[cfg(feature = "lz4")]
+            CompressionType::LZ4,
+            #[cfg(feature = "zstd")]
+            CompressionType::ZSTD,
+            #[cfg(feature = "lzma2")]
+            CompressionType::LZMA2,
+        ]
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let data: Vec<u8> = (0..10000_u32).flat_map(|i| (i / 7).to_be_bytes()).collect();
+        for compression in all_types() {
+            let compressor = <dyn Compressor>::new(compression);
+            let compressed = compressor.compress(&data).unwrap();
+            if compression != CompressionType::UNCOMPRESSED {
+                assert!(compressed.len() < data.len() / 2, "{:?}", compression);
+            }
+            assert_eq!(
+                data,
+                compressor.uncompress(&compressed, data.len()).unwrap()
+            );
+            assert_eq!(
+                Ok(compression),
+                CompressionType::try_from(compression.serialize())
+            );
+        }
+    }
+
+    #[test]
+    fn test_max_compressed_len() {
+        // Pseudo random bytes can not be compressed
+        let mut state = 0x2545_F491_u32;
+        let data: Vec<u8> = (0..200_000)
+            .map(|_| {
+                state ^= state << 13;
+                state ^= state >> 17;
+                state ^= state << 5;
+                state as u8
+            })
+            .collect();
+        for compression in all_types() {
+            let compressor = <dyn Compressor>::new(compression);
+            for len in [0, 1, 100, data.len()] {
+                let compressed = compressor.compress(&data[..len]).unwrap();
+                assert!(
+                    compressed.len() <= compressor.max_compressed_len(len),
+                    "{:?}",
+                    compression
+                );
+            }
         }
     }
 }
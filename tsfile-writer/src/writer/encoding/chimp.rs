@@ -0,0 +1,337 @@
+//! CHIMP encoding for INT32, INT64, FLOAT and DOUBLE, compatible with the
+//! `IntChimpEncoder`, `LongChimpEncoder`, `SinglePrecisionChimpEncoder` and
+//! `DoublePrecisionChimpEncoder` of IoTDB.
+//!
+//! Like GORILLA, values are stored as XOR, but with one of the previous 64 (32 bit values)
+//! or 128 (64 bit values) values that shares the most trailing bits. Each value starts
+//! with a 2 bit flag:
+//! * `00` repeats a previous value, followed by its index
+//! * `01` XOR with many trailing zeros, followed by index, leading zeros (3 bits, rounded),
+//!   number of significant bits and the significant bits
+//! * `10` XOR with the last value, with the same leading zeros as the last `11`
+//! * `11` XOR with the last value, followed by the leading zeros (3 bits, rounded)
+//!
+//! The stream of a page is terminated like the one of GORILLA.
+use crate::writer::encoding::gorilla::{from_bits, to_bits, BitReader, BitWriter, GorillaFormat};
+use crate::writer::encoding::{Decoder, Encoder};
+use crate::writer::{IoTDBValue, TSDataType, TsFileError};
+
+/// Leading zeros that can be represented with 3 bits
+const LEADING_ZEROS: [u32; 8] = [0, 8, 12, 16, 18, 20, 22, 24];
+
+/// Rounds the leading zeros down to the next value of `LEADING_ZEROS`, returns its index
+fn leading_representation(leading_zeros: u32) -> u32 {
+    match leading_zeros {
+        0..=7 => 0,
+        8..=11 => 1,
+        12..=15 => 2,
+        16..=17 => 3,
+        18..=19 => 4,
+        20..=21 => 5,
+        22..=23 => 6,
+        _ => 7,
+    }
+}
+
+/// Bit lengths of the stream of a data type
+#[derive(Copy, Clone)]
+struct ChimpFormat {
+    value_bits: u32,
+    ending: u64,
+    previous_values_log2: u32,
+    /// Minimal trailing zeros of a XOR with an older value
+    threshold: u32,
+    significant_bits_length: u32,
+}
+
+impl ChimpFormat {
+    fn of(data_type: TSDataType) -> ChimpFormat {
+        let gorilla = GorillaFormat::of(data_type);
+        match gorilla.value_bits {
+            64 => ChimpFormat {
+                value_bits: 64,
+                ending: gorilla.ending,
+                previous_values_log2: 7,
+                threshold: 13,
+                significant_bits_length: 6,
+            },
+            _ => ChimpFormat {
+                value_bits: 32,
+                ending: gorilla.ending,
+                previous_values_log2: 6,
+                threshold: 11,
+                significant_bits_length: 5,
+            },
+        }
+    }
+
+    fn previous_values(&self) -> usize {
+        1 << self.previous_values_log2
+    }
+
+    /// Mask of the lower bits that are used to find previous values with many trailing zeros
+    fn set_lsb(&self) -> usize {
+        (1 << (self.threshold + 1)) - 1
+    }
+
+    fn flag_zero_size(&self) -> u32 {
+        self.previous_values_log2 + 2
+    }
+
+    fn flag_one_size(&self) -> u32 {
+        self.previous_values_log2 + 2 + 3 + self.significant_bits_length
+    }
+}
+
+pub struct ChimpEncoder {
+    data_type: TSDataType,
+    format: ChimpFormat,
+    first_value_was_written: bool,
+    stored_leading_zeros: u32,
+    /// Ring buffer of the previous values
+    stored_values: Vec<u64>,
+    current: usize,
+    /// Latest index of a value by its lower bits
+    indices: Vec<usize>,
+    index: usize,
+    out: BitWriter,
+}
+
+impl ChimpEncoder {
+    pub(crate) fn new(data_type: TSDataType) -> ChimpEncoder {
+        let format = ChimpFormat::of(data_type);
+        Self {
+            data_type,
+            format,
+            first_value_was_written: false,
+            stored_leading_zeros: u32::MAX,
+            stored_values: vec![0; format.previous_values()],
+            current: 0,
+            indices: vec![0; format.set_lsb() + 1],
+            index: 0,
+            out: BitWriter::new(),
+        }
+    }
+
+    fn encode(&mut self, value: u64) {
+        let format = self.format;
+        let key = value as usize & format.set_lsb();
+        if !self.first_value_was_written {
+            self.stored_values[self.current] = value;
+            self.out.write_bits(value, format.value_bits);
+            self.indices[key] = self.index;
+            self.first_value_was_written = true;
+            return;
+        }
+        let mut trailing_zeros = 0;
+        let mut previous_index = self.index % format.previous_values();
+        let mut xor = self.stored_values[previous_index] ^ value;
+        let candidate = self.indices[key];
+        if self.index - candidate < format.previous_values() {
+            let candidate_xor = value ^ self.stored_values[candidate % format.previous_values()];
+            trailing_zeros = candidate_xor.trailing_zeros().min(format.value_bits);
+            if trailing_zeros > format.threshold {
+                previous_index = candidate % format.previous_values();
+                xor = candidate_xor;
+            }
+        }
+
+        if xor == 0 {
+            self.out
+                .write_bits(previous_index as u64, format.flag_zero_size());
+            self.stored_leading_zeros = format.value_bits + 1;
+        } else {
+            let representation =
+                leading_representation(xor.leading_zeros() - (64 - format.value_bits));
+            let leading_zeros = LEADING_ZEROS[representation as usize];
+            if trailing_zeros > format.threshold {
+                let significant_bits = format.value_bits - leading_zeros - trailing_zeros;
+                let flag = (((format.previous_values() + previous_index) as u64)
+                    << (3 + format.significant_bits_length))
+                    + ((representation as u64) << format.significant_bits_length)
+                    + significant_bits as u64;
+                self.out.write_bits(flag, format.flag_one_size());
+                self.out.write_bits(xor >> trailing_zeros, significant_bits);
+                self.stored_leading_zeros = format.value_bits + 1;
+            } else if leading_zeros == self.stored_leading_zeros {
+                self.out.write_bits(2, 2);
+                self.out.write_bits(xor, format.value_bits - leading_zeros);
+            } else {
+                self.stored_leading_zeros = leading_zeros;
+                self.out.write_bits(24 + representation as u64, 5);
+                self.out.write_bits(xor, format.value_bits - leading_zeros);
+            }
+        }
+        self.current = (self.current + 1) % format.previous_values();
+        self.stored_values[self.current] = value;
+        self.index += 1;
+        self.indices[key] = self.index;
+    }
+}
+
+impl Encoder for ChimpEncoder {
+    fn write(&mut self, value: &IoTDBValue) -> Result<(), TsFileError> {
+        let bits = to_bits(self.data_type, value)?;
+        self.encode(bits);
+        Ok(())
+    }
+
+    fn size(&mut self) -> u32 {
+        self.out.buffer.len() as u32
+    }
+
+    fn get_max_byte_size(&self) -> u32 {
+        // The current byte and the ending value
+        2 + (self.format.flag_one_size() + self.format.value_bits) / 8
+    }
+
+    fn serialize(&mut self, buffer: &mut Vec<u8>) {
+        self.encode(self.format.ending);
+        self.out.finish(buffer);
+        self.reset();
+    }
+
+    fn reset(&mut self) {
+        *self = ChimpEncoder::new(self.data_type);
+    }
+}
+
+pub struct ChimpDecoder {
+    data_type: TSDataType,
+    format: ChimpFormat,
+}
+
+impl ChimpDecoder {
+    pub(crate) fn new(data_type: TSDataType) -> ChimpDecoder {
+        Self {
+            data_type,
+            format: ChimpFormat::of(data_type),
+        }
+    }
+}
+
+impl Decoder for ChimpDecoder {
+    fn decode(&mut self, buffer: &[u8]) -> Result<Vec<IoTDBValue>, TsFileError> {
+        let format = self.format;
+        let mut reader = BitReader::new(buffer);
+        let mut result = vec![];
+        if buffer.is_empty() {
+            return Ok(result);
+        }
+        let mut stored_values = vec![0_u64; format.previous_values()];
+        let mut current = 0;
+        let mut stored_value = reader.read(format.value_bits)?;
+        stored_values[current] = stored_value;
+        let mut stored_leading_zeros = 0;
+        while stored_value != format.ending {
+            result.push(from_bits(self.data_type, stored_value));
+            match reader.read(2)? {
+                3 => {
+                    stored_leading_zeros = LEADING_ZEROS[reader.read(3)? as usize];
+                    stored_value ^= reader.read(format.value_bits - stored_leading_zeros)?;
+                }
+                2 => {
+                    stored_value ^= reader.read(format.value_bits - stored_leading_zeros)?;
+                }
+                1 => {
+                    let metadata = reader.read(format.flag_one_size() - 2)?;
+                    let index = (metadata >> (3 + format.significant_bits_length)) as usize
+                        & (format.previous_values() - 1);
+                    stored_leading_zeros = LEADING_ZEROS
+                        [((metadata >> format.significant_bits_length) & 0x07) as usize];
+                    let mut significant_bits =
+                        (metadata & ((1 << format.significant_bits_length) - 1)) as u32;
+                    if significant_bits == 0 {
+                        significant_bits = format.value_bits;
+                    }
+                    if stored_leading_zeros + significant_bits > format.value_bits {
+                        return Err(TsFileError::Encoding);
+                    }
+                    let trailing_zeros =
+                        format.value_bits - significant_bits - stored_leading_zeros;
+                    stored_value =
+                        stored_values[index] ^ (reader.read(significant_bits)? << trailing_zeros);
+                }
+                _ => {
+                    let index = reader.read(format.previous_values_log2)? as usize;
+                    stored_value = stored_values[index];
+                }
+            }
+            current = (current + 1) % format.previous_values();
+            stored_values[current] = stored_value;
+        }
+        Ok(result)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::writer::encoding::chimp::{ChimpDecoder, ChimpEncoder};
+    use crate::writer::encoding::{Decoder, Encoder};
+    use crate::writer::{IoTDBValue, TSDataType};
+
+    fn encode(data_type: TSDataType, values: &[IoTDBValue]) -> Vec<u8> {
+        let mut encoder = ChimpEncoder::new(data_type);
+        for v in values {
+            encoder.write(v).unwrap();
+        }
+        let mut buffer = vec![];
+        encoder.serialize(&mut buffer);
+        buffer
+    }
+
+    #[test]
+    fn test_float_bytes() {
+        let buffer = encode(
+            TSDataType::FLOAT,
+            &[IoTDBValue::FLOAT(1.0), IoTDBValue::FLOAT(1.0)],
+        );
+
+        // 1.0, the repeated value as flag 00 with index 0 (6 bits), then the XOR with NaN:
+        // flag 01, index 1, 0 leading zeros (rounded), 10 significant bits and 0100000001
+        assert_eq!(
+            vec![0x3F, 0x80, 0x00, 0x00, 0x00, 0x41, 0x0A, 0x40, 0x40],
+            buffer
+        );
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let floats: Vec<IoTDBValue> = (0..10000)
+            .map(|i| IoTDBValue::FLOAT(20.0 + ((i / 10) as f32 * 0.1).sin()))
+            .collect();
+        let doubles: Vec<IoTDBValue> = (0..10000)
+            .map(|i| match i % 7 {
+                0 => IoTDBValue::DOUBLE(-0.0),
+                1 => IoTDBValue::DOUBLE(f64::MAX),
+                2 => IoTDBValue::DOUBLE((i % 300) as f64 * 0.5),
+                _ => IoTDBValue::DOUBLE(1013.25 + i as f64 / 1000.0),
+            })
+            .collect();
+        let ints: Vec<IoTDBValue> = (0..10000)
+            .map(|i| IoTDBValue::INT(if i % 100 == 0 { i32::MAX } else { -i * 3 }))
+            .collect();
+        let longs: Vec<IoTDBValue> = (0..10000)
+            .map(|i| IoTDBValue::LONG(1_600_000_000_000 + (i % 250) * 4096))
+            .collect();
+
+        for (data_type, values) in [
+            (TSDataType::INT32, ints),
+            (TSDataType::INT64, longs),
+            (TSDataType::FLOAT, floats),
+            (TSDataType::DOUBLE, doubles),
+        ] {
+            let mut encoder = ChimpEncoder::new(data_type);
+            // The encoder is reused for every page
+            for _ in 0..2 {
+                for v in values.iter() {
+                    encoder.write(v).unwrap();
+                }
+                let mut buffer = vec![];
+                encoder.serialize(&mut buffer);
+                let decoded = ChimpDecoder::new(data_type).decode(&buffer).unwrap();
+                assert_eq!(values, decoded);
+            }
+        }
+    }
+}
@@ -0,0 +1,165 @@
+//! DICTIONARY encoding for TEXT, compatible with the `DictionaryEncoder` of IoTDB.
+//!
+//! A page starts with the map of distinct values (their number and each value with its
+//! length, as zigzag var ints) in order of appearance, followed by the RLE encoded
+//! indexes of the values in this map.
+use crate::writer::encoding::rle::{RleDecoder, RleEncoder};
+use crate::writer::encoding::{Decoder, Encoder};
+use crate::writer::utils::{read_var_i32, size_var_i32, write_var_i32};
+use crate::writer::{IoTDBValue, TSDataType, TsFileError};
+use std::collections::HashMap;
+use std::io::Read;
+
+pub struct DictionaryEncoder {
+    entry_index: HashMap<Vec<u8>, i32>,
+    index_entry: Vec<Vec<u8>>,
+    /// Serialized size of the map
+    map_size: u32,
+    values_encoder: RleEncoder,
+}
+
+impl DictionaryEncoder {
+    pub(crate) fn new() -> DictionaryEncoder {
+        Self {
+            entry_index: HashMap::new(),
+            index_entry: vec![],
+            map_size: 0,
+            values_encoder: RleEncoder::new(TSDataType::INT32),
+        }
+    }
+}
+
+impl Encoder for DictionaryEncoder {
+    fn write(&mut self, value: &IoTDBValue) -> Result<(), TsFileError> {
+        let value = match value {
+            IoTDBValue::TEXT(v) => v,
+            _ => return Err(TsFileError::WrongTypeForSeries),
+        };
+        let index = match self.entry_index.get(value) {
+            Some(index) => *index,
+            None => {
+                let index = self.index_entry.len() as i32;
+                self.map_size += size_var_i32(value.len() as i32) as u32 + value.len() as u32;
+                self.entry_index.insert(value.clone(), index);
+                self.index_entry.push(value.clone());
+                index
+            }
+        };
+        self.values_encoder.write(&IoTDBValue::INT(index))
+    }
+
+    fn size(&mut self) -> u32 {
+        // Nothing is encoded before serialize
+        0
+    }
+
+    fn get_max_byte_size(&self) -> u32 {
+        // Number of entries, the entries and the indexes
+        5 + self.map_size + self.values_encoder.get_max_byte_size()
+    }
+
+    fn serialize(&mut self, buffer: &mut Vec<u8>) {
+        write_var_i32(self.index_entry.len() as i32, buffer).expect("Writing to a Vec never fails");
+        for value in self.index_entry.iter() {
+            write_var_i32(value.len() as i32, buffer).expect("Writing to a Vec never fails");
+            buffer.extend_from_slice(value);
+        }
+        self.values_encoder.serialize(buffer);
+        self.reset();
+    }
+
+    fn reset(&mut self) {
+        self.entry_index.clear();
+        self.index_entry.clear();
+        self.map_size = 0;
+        self.values_encoder.reset();
+    }
+}
+
+pub struct DictionaryDecoder {}
+
+impl DictionaryDecoder {
+    pub(crate) fn new() -> DictionaryDecoder {
+        Self {}
+    }
+}
+
+impl Decoder for DictionaryDecoder {
+    fn decode(&mut self, buffer: &[u8]) -> Result<Vec<IoTDBValue>, TsFileError> {
+        if buffer.is_empty() {
+            return Ok(vec![]);
+        }
+        let mut reader = buffer;
+        let map_size = read_var_i32(&mut reader)?;
+        if map_size < 0 {
+            return Err(TsFileError::Encoding);
+        }
+        let mut entries = Vec::with_capacity(map_size as usize);
+        for _ in 0..map_size {
+            let length = read_var_i32(&mut reader)?;
+            if length < 0 || length as usize > reader.len() {
+                return Err(TsFileError::Encoding);
+            }
+            let mut entry = vec![0_u8; length as usize];
+            reader.read_exact(&mut entry)?;
+            entries.push(entry);
+        }
+        RleDecoder::new(TSDataType::INT32)
+            .decode(reader)?
+            .into_iter()
+            .map(|index| match index {
+                IoTDBValue::INT(i) if i >= 0 && (i as usize) < entries.len() => {
+                    Ok(IoTDBValue::TEXT(entries[i as usize].clone()))
+                }
+                _ => Err(TsFileError::Encoding),
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::writer::encoding::dictionary::{DictionaryDecoder, DictionaryEncoder};
+    use crate::writer::encoding::{Decoder, Encoder};
+    use crate::writer::IoTDBValue;
+
+    #[test]
+    fn test_bytes() {
+        let mut encoder = DictionaryEncoder::new();
+        for v in ["a", "bc", "a"] {
+            encoder.write(&IoTDBValue::from(v)).unwrap();
+        }
+        let mut buffer = vec![];
+        encoder.serialize(&mut buffer);
+
+        // Map with 2 entries (zigzag), then the RLE block of the indexes 0, 1, 0
+        assert_eq!(
+            vec![0x04, 0x02, b'a', 0x04, b'b', b'c', 0x04, 0x01, 0x03, 0x03, 0x40],
+            buffer
+        );
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let labels = ["IDLE", "RUNNING", "", "STOPPED", "ERROR ü"];
+        let values: Vec<IoTDBValue> = (0..10000)
+            .map(|i| IoTDBValue::from(labels[(i * i) % labels.len()]))
+            .collect();
+        let mut encoder = DictionaryEncoder::new();
+        // The encoder is reused for every page
+        for _ in 0..2 {
+            for v in values.iter() {
+                encoder.write(v).unwrap();
+            }
+            let mut buffer = vec![];
+            encoder.serialize(&mut buffer);
+            assert!(buffer.len() < 5000);
+            assert_eq!(values, DictionaryDecoder::new().decode(&buffer).unwrap());
+        }
+    }
+
+    #[test]
+    fn test_wrong_type() {
+        assert!(DictionaryEncoder::new().write(&IoTDBValue::INT(1)).is_err());
+    }
+}
@@ -0,0 +1,176 @@
+//! TS2DIFF for FLOAT and DOUBLE, compatible with the `FloatEncoder` of IoTDB.
+//!
+//! Values are multiplied by `10^max_point_number`, rounded and encoded with the TS2DIFF
+//! encoder for INT32 (FLOAT) or INT64 (DOUBLE), so only `max_point_number` decimal places
+//! are kept. A page starts with `max_point_number` as var u32.
+use crate::writer::encoding::time_encoder::{
+    IntTs2DiffDecoder, IntTs2DiffEncoder, LongTs2DiffDecoder, LongTs2DiffEncoder,
+};
+use crate::writer::encoding::{Decoder, Encoder};
+use crate::writer::utils::{read_var_u32, write_var_u32};
+use crate::writer::{IoTDBValue, TSDataType, TsFileError};
+
+/// Default number of decimal places, like `float_precision` of IoTDB
+pub const DEFAULT_MAX_POINT_NUMBER: u32 = 2;
+
+pub struct FloatEncoder {
+    data_type: TSDataType,
+    max_point_number: u32,
+    max_point_value: f64,
+    encoder: Box<dyn Encoder>,
+    /// The header is only written for pages with values
+    has_values: bool,
+}
+
+impl FloatEncoder {
+    pub(crate) fn new(data_type: TSDataType, max_point_number: u32) -> FloatEncoder {
+        Self {
+            data_type,
+            max_point_number,
+            max_point_value: 10_f64.powi(max_point_number as i32),
+            encoder: match data_type {
+                TSDataType::DOUBLE => Box::new(LongTs2DiffEncoder::new()),
+                _ => Box::new(IntTs2DiffEncoder::new()),
+            },
+            has_values: false,
+        }
+    }
+
+    /// Rounds like `Math.round` in Java, i.e. half up and saturating
+    fn round(&self, value: f64) -> i64 {
+        (value * self.max_point_value + 0.5).floor() as i64
+    }
+}
+
+impl Encoder for FloatEncoder {
+    fn write(&mut self, value: &IoTDBValue) -> Result<(), TsFileError> {
+        let value = match (self.data_type, value) {
+            (TSDataType::FLOAT, IoTDBValue::FLOAT(v)) => {
+                IoTDBValue::INT(self.round(*v as f64) as i32)
+            }
+            (TSDataType::DOUBLE, IoTDBValue::DOUBLE(v)) => IoTDBValue::LONG(self.round(*v)),
+            _ => return Err(TsFileError::WrongTypeForSeries),
+        };
+        self.has_values = true;
+        self.encoder.write(&value)
+    }
+
+    fn size(&mut self) -> u32 {
+        self.encoder.size()
+    }
+
+    fn get_max_byte_size(&self) -> u32 {
+        5 + self.encoder.get_max_byte_size()
+    }
+
+    fn serialize(&mut self, buffer: &mut Vec<u8>) {
+        if self.has_values {
+            write_var_u32(self.max_point_number, buffer).expect("Writing to a Vec never fails");
+            self.encoder.serialize(buffer);
+        }
+        self.reset();
+    }
+
+    fn reset(&mut self) {
+        self.encoder.reset();
+        self.has_values = false;
+    }
+}
+
+pub struct FloatDecoder {
+    data_type: TSDataType,
+}
+
+impl FloatDecoder {
+    pub(crate) fn new(data_type: TSDataType) -> FloatDecoder {
+        Self { data_type }
+    }
+}
+
+impl Decoder for FloatDecoder {
+    fn decode(&mut self, buffer: &[u8]) -> Result<Vec<IoTDBValue>, TsFileError> {
+        if buffer.is_empty() {
+            return Ok(vec![]);
+        }
+        let mut reader = buffer;
+        let max_point_value = 10_f64.powi(read_var_u32(&mut reader)? as i32);
+        let values = match self.data_type {
+            TSDataType::DOUBLE => LongTs2DiffDecoder::new().decode(reader)?,
+            _ => IntTs2DiffDecoder::new().decode(reader)?,
+        };
+        values
+            .into_iter()
+            .map(|value| match value {
+                IoTDBValue::INT(v) => Ok(IoTDBValue::FLOAT((v as f64 / max_point_value) as f32)),
+                IoTDBValue::LONG(v) => Ok(IoTDBValue::DOUBLE(v as f64 / max_point_value)),
+                _ => Err(TsFileError::Encoding),
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::writer::encoding::float::{FloatDecoder, FloatEncoder};
+    use crate::writer::encoding::{Decoder, Encoder};
+    use crate::writer::{IoTDBValue, TSDataType};
+
+    fn encode(data_type: TSDataType, max_point_number: u32, values: &[IoTDBValue]) -> Vec<u8> {
+        let mut encoder = FloatEncoder::new(data_type, max_point_number);
+        for v in values {
+            encoder.write(v).unwrap();
+        }
+        let mut buffer = vec![];
+        encoder.serialize(&mut buffer);
+        buffer
+    }
+
+    #[test]
+    fn test_float_bytes() {
+        let values = [1.01_f32, 1.02].map(IoTDBValue::FLOAT).to_vec();
+
+        let buffer = encode(TSDataType::FLOAT, 2, &values);
+
+        // Precision, then TS2DIFF of 101 and 102: one delta of width 0,
+        // min delta 1 and first value 101
+        assert_eq!(
+            vec![0x02, 0, 0, 0, 1, 0, 0, 0, 0, 0, 0, 0, 1, 0, 0, 0, 101],
+            buffer
+        );
+    }
+
+    #[test]
+    fn test_empty() {
+        assert!(encode(TSDataType::DOUBLE, 2, &[]).is_empty());
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let floats: Vec<IoTDBValue> = (0..1000)
+            .map(|i| IoTDBValue::FLOAT((i - 300) as f32 / 100.0))
+            .collect();
+        let doubles: Vec<IoTDBValue> = (0..1000)
+            .map(|i| IoTDBValue::DOUBLE((1_013_000 + i) as f64 / 1000.0))
+            .collect();
+
+        for (data_type, max_point_number, values) in [
+            (TSDataType::FLOAT, 2, floats),
+            (TSDataType::DOUBLE, 3, doubles),
+        ] {
+            let buffer = encode(data_type, max_point_number, &values);
+            let decoded = FloatDecoder::new(data_type).decode(&buffer).unwrap();
+            assert_eq!(values, decoded);
+        }
+    }
+
+    #[test]
+    fn test_precision_is_limited() {
+        let buffer = encode(TSDataType::DOUBLE, 1, &[IoTDBValue::DOUBLE(2.345)]);
+
+        let decoded = FloatDecoder::new(TSDataType::DOUBLE)
+            .decode(&buffer)
+            .unwrap();
+
+        assert_eq!(vec![IoTDBValue::DOUBLE(2.3)], decoded);
+    }
+}
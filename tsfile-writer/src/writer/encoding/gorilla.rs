@@ -0,0 +1,404 @@
+//! XOR based GORILLA encoding for INT32, INT64, FLOAT and DOUBLE, compatible with the
+//! `IntGorillaEncoder`, `LongGorillaEncoder`, `SinglePrecisionEncoderV2` and
+//! `DoublePrecisionEncoderV2` of IoTDB.
+//!
+//! The first value is stored with all its bits, every following value as XOR with its
+//! predecessor. The stream of a page is terminated by NaN (or the minimal value for
+//! integers) and padded to full bytes.
+use crate::writer::encoding::time_encoder::read_bits;
+use crate::writer::encoding::{Decoder, Encoder};
+use crate::writer::{IoTDBValue, TSDataType, TsFileError};
+
+/// Bit patterns of the values that terminate the stream of a page,
+/// for floating point numbers NaN as written by Java
+const ENDING_INT: u64 = 0x8000_0000;
+const ENDING_LONG: u64 = 0x8000_0000_0000_0000;
+const ENDING_FLOAT: u64 = 0x7FC0_0000;
+const ENDING_DOUBLE: u64 = 0x7FF8_0000_0000_0000;
+
+/// Bit lengths of the stream of a data type
+#[derive(Copy, Clone)]
+pub(crate) struct GorillaFormat {
+    pub(crate) value_bits: u32,
+    leading_zero_bits: u32,
+    meaningful_xor_bits: u32,
+    pub(crate) ending: u64,
+}
+
+impl GorillaFormat {
+    pub(crate) fn of(data_type: TSDataType) -> GorillaFormat {
+        let (value_bits, ending) = match data_type {
+            TSDataType::INT32 => (32, ENDING_INT),
+            TSDataType::INT64 => (64, ENDING_LONG),
+            TSDataType::DOUBLE => (64, ENDING_DOUBLE),
+            _ => (32, ENDING_FLOAT),
+        };
+        match value_bits {
+            64 => GorillaFormat {
+                value_bits,
+                leading_zero_bits: 6,
+                meaningful_xor_bits: 6,
+                ending,
+            },
+            _ => GorillaFormat {
+                value_bits,
+                leading_zero_bits: 5,
+                meaningful_xor_bits: 5,
+                ending,
+            },
+        }
+    }
+
+    /// Maximal size of one encoded value in bytes
+    pub(crate) fn one_item_max_size(&self) -> u32 {
+        (2 + self.leading_zero_bits + self.meaningful_xor_bits + self.value_bits) / 8 + 1
+    }
+
+    pub(crate) fn leading_zeros(&self, value: u64) -> u32 {
+        value.leading_zeros() - (64 - self.value_bits)
+    }
+}
+
+/// Collects single bits or numbers, most significant bit first
+pub(crate) struct BitWriter {
+    /// Byte that is currently filled and the number of its free bits
+    current_byte: u8,
+    bits_left: u32,
+    pub(crate) buffer: Vec<u8>,
+}
+
+impl BitWriter {
+    pub(crate) fn new() -> BitWriter {
+        Self {
+            current_byte: 0,
+            bits_left: 8,
+            buffer: vec![],
+        }
+    }
+
+    fn flip_byte(&mut self) {
+        if self.bits_left == 0 {
+            self.buffer.push(self.current_byte);
+            self.current_byte = 0;
+            self.bits_left = 8;
+        }
+    }
+
+    pub(crate) fn write_bit(&mut self, bit: bool) {
+        if bit {
+            self.current_byte |= 1 << (self.bits_left - 1);
+        }
+        self.bits_left -= 1;
+        self.flip_byte();
+    }
+
+    /// Writes the lowest `bits` bits of the value
+    pub(crate) fn write_bits(&mut self, value: u64, mut bits: u32) {
+        while bits > 0 {
+            if bits >= self.bits_left {
+                let shift = bits - self.bits_left;
+                self.current_byte |= ((value >> shift) & ((1 << self.bits_left) - 1)) as u8;
+                bits -= self.bits_left;
+                self.bits_left = 0;
+            } else {
+                let shift = self.bits_left - bits;
+                self.current_byte |= ((value & ((1 << bits) - 1)) << shift) as u8;
+                self.bits_left -= bits;
+                bits = 0;
+            }
+            self.flip_byte();
+        }
+    }
+
+    /// Moves all bits to the buffer. The last byte is written even if it is empty,
+    /// as the decoders of IoTDB rely on it.
+    pub(crate) fn finish(&mut self, buffer: &mut Vec<u8>) {
+        self.bits_left = 0;
+        self.flip_byte();
+        buffer.append(&mut self.buffer);
+    }
+}
+
+pub struct GorillaEncoder {
+    data_type: TSDataType,
+    format: GorillaFormat,
+    first_value_was_written: bool,
+    stored_value: u64,
+    stored_leading_zeros: u32,
+    stored_trailing_zeros: u32,
+    out: BitWriter,
+}
+
+impl GorillaEncoder {
+    pub(crate) fn new(data_type: TSDataType) -> GorillaEncoder {
+        Self {
+            data_type,
+            format: GorillaFormat::of(data_type),
+            first_value_was_written: false,
+            stored_value: 0,
+            stored_leading_zeros: u32::MAX,
+            stored_trailing_zeros: 0,
+            out: BitWriter::new(),
+        }
+    }
+
+    fn encode(&mut self, value: u64) {
+        if !self.first_value_was_written {
+            self.stored_value = value;
+            self.out.write_bits(value, self.format.value_bits);
+            self.first_value_was_written = true;
+            return;
+        }
+        let xor = self.stored_value ^ value;
+        self.stored_value = value;
+        if xor == 0 {
+            self.out.write_bit(false);
+            return;
+        }
+        self.out.write_bit(true);
+        let leading_zeros = self.format.leading_zeros(xor);
+        let trailing_zeros = xor.trailing_zeros();
+        if leading_zeros >= self.stored_leading_zeros
+            && trailing_zeros >= self.stored_trailing_zeros
+        {
+            // The meaningful bits fit into the window of the previous value
+            self.out.write_bit(false);
+            let significant_bits =
+                self.format.value_bits - self.stored_leading_zeros - self.stored_trailing_zeros;
+            self.out
+                .write_bits(xor >> self.stored_trailing_zeros, significant_bits);
+        } else {
+            // New window, its position is stored before the meaningful bits
+            self.out.write_bit(true);
+            let significant_bits = self.format.value_bits - leading_zeros - trailing_zeros;
+            self.out
+                .write_bits(leading_zeros as u64, self.format.leading_zero_bits);
+            self.out.write_bits(
+                (significant_bits - 1) as u64,
+                self.format.meaningful_xor_bits,
+            );
+            self.out.write_bits(xor >> trailing_zeros, significant_bits);
+            self.stored_leading_zeros = leading_zeros;
+            self.stored_trailing_zeros = trailing_zeros;
+        }
+    }
+}
+
+impl Encoder for GorillaEncoder {
+    fn write(&mut self, value: &IoTDBValue) -> Result<(), TsFileError> {
+        let bits = to_bits(self.data_type, value)?;
+        self.encode(bits);
+        Ok(())
+    }
+
+    fn size(&mut self) -> u32 {
+        self.out.buffer.len() as u32
+    }
+
+    fn get_max_byte_size(&self) -> u32 {
+        // The current byte and the ending value
+        1 + self.format.one_item_max_size()
+    }
+
+    fn serialize(&mut self, buffer: &mut Vec<u8>) {
+        self.encode(self.format.ending);
+        self.out.finish(buffer);
+        self.reset();
+    }
+
+    fn reset(&mut self) {
+        self.first_value_was_written = false;
+        self.stored_value = 0;
+        self.stored_leading_zeros = u32::MAX;
+        self.stored_trailing_zeros = 0;
+        self.out = BitWriter::new();
+    }
+}
+
+pub struct GorillaDecoder {
+    data_type: TSDataType,
+    format: GorillaFormat,
+}
+
+impl GorillaDecoder {
+    pub(crate) fn new(data_type: TSDataType) -> GorillaDecoder {
+        Self {
+            data_type,
+            format: GorillaFormat::of(data_type),
+        }
+    }
+}
+
+/// Bits of a numeric value, 32 bit values occupy the lower half
+pub(crate) fn to_bits(data_type: TSDataType, value: &IoTDBValue) -> Result<u64, TsFileError> {
+    match (data_type, value) {
+        (TSDataType::INT32, IoTDBValue::INT(v)) => Ok(*v as u32 as u64),
+        (TSDataType::INT64, IoTDBValue::LONG(v)) => Ok(*v as u64),
+        (TSDataType::FLOAT, IoTDBValue::FLOAT(v)) => Ok(v.to_bits() as u64),
+        (TSDataType::DOUBLE, IoTDBValue::DOUBLE(v)) => Ok(v.to_bits()),
+        _ => Err(TsFileError::WrongTypeForSeries),
+    }
+}
+
+/// Counterpart of `to_bits`
+pub(crate) fn from_bits(data_type: TSDataType, bits: u64) -> IoTDBValue {
+    match data_type {
+        TSDataType::INT32 => IoTDBValue::INT(bits as u32 as i32),
+        TSDataType::INT64 => IoTDBValue::LONG(bits as i64),
+        TSDataType::DOUBLE => IoTDBValue::DOUBLE(f64::from_bits(bits)),
+        _ => IoTDBValue::FLOAT(f32::from_bits(bits as u32)),
+    }
+}
+
+/// Reads single bits or numbers from a buffer, most significant bit first
+pub(crate) struct BitReader<'a> {
+    buffer: &'a [u8],
+    position: usize,
+}
+
+impl<'a> BitReader<'a> {
+    pub(crate) fn new(buffer: &'a [u8]) -> BitReader<'a> {
+        Self {
+            buffer,
+            position: 0,
+        }
+    }
+
+    pub(crate) fn read(&mut self, width: u32) -> Result<u64, TsFileError> {
+        if self.position + width as usize > self.buffer.len() * 8 {
+            return Err(TsFileError::Encoding);
+        }
+        let value = read_bits(self.buffer, self.position, width);
+        self.position += width as usize;
+        Ok(value)
+    }
+
+    pub(crate) fn read_bit(&mut self) -> Result<bool, TsFileError> {
+        Ok(self.read(1)? == 1)
+    }
+}
+
+impl Decoder for GorillaDecoder {
+    fn decode(&mut self, buffer: &[u8]) -> Result<Vec<IoTDBValue>, TsFileError> {
+        let format = self.format;
+        let mut reader = BitReader::new(buffer);
+        let mut result = vec![];
+        if buffer.is_empty() {
+            return Ok(result);
+        }
+        let mut stored_value = reader.read(format.value_bits)?;
+        let mut stored_leading_zeros = 0;
+        let mut stored_trailing_zeros = 0;
+        while stored_value != format.ending {
+            result.push(from_bits(self.data_type, stored_value));
+            if reader.read_bit()? {
+                if reader.read_bit()? {
+                    stored_leading_zeros = reader.read(format.leading_zero_bits)? as u32;
+                    let significant_bits = reader.read(format.meaningful_xor_bits)? as u32 + 1;
+                    if stored_leading_zeros + significant_bits > format.value_bits {
+                        return Err(TsFileError::Encoding);
+                    }
+                    stored_trailing_zeros =
+                        format.value_bits - significant_bits - stored_leading_zeros;
+                }
+                let significant_bits =
+                    format.value_bits - stored_leading_zeros - stored_trailing_zeros;
+                stored_value ^= reader.read(significant_bits)? << stored_trailing_zeros;
+            }
+        }
+        Ok(result)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::writer::encoding::gorilla::{GorillaDecoder, GorillaEncoder};
+    use crate::writer::encoding::{Decoder, Encoder};
+    use crate::writer::{IoTDBValue, TSDataType};
+
+    fn encode(data_type: TSDataType, values: &[IoTDBValue]) -> Vec<u8> {
+        let mut encoder = GorillaEncoder::new(data_type);
+        for v in values {
+            encoder.write(v).unwrap();
+        }
+        let mut buffer = vec![];
+        encoder.serialize(&mut buffer);
+        buffer
+    }
+
+    #[test]
+    fn test_float_bytes() {
+        let buffer = encode(TSDataType::FLOAT, &[IoTDBValue::FLOAT(1.0)]);
+
+        // 1.0, then the XOR with NaN: control bits 11, 1 leading zero (5 bits),
+        // 9 meaningful bits (stored as 8 in 5 bits) and the bits 100000001
+        assert_eq!(vec![0x3F, 0x80, 0x00, 0x00, 0xC2, 0x88, 0x08], buffer);
+    }
+
+    #[test]
+    fn test_long_bytes() {
+        let buffer = encode(
+            TSDataType::INT64,
+            &[IoTDBValue::LONG(1), IoTDBValue::LONG(1)],
+        );
+
+        // 1, a repeated value as single 0 bit, then the XOR with the minimal value:
+        // control bits 11, no leading zeros (6 bits), 64 meaningful bits (stored as 63)
+        let mut expected = vec![0, 0, 0, 0, 0, 0, 0, 1];
+        expected.extend_from_slice(&[0x60, 0x7F, 0, 0, 0, 0, 0, 0, 0, 0x02]);
+        assert_eq!(expected, buffer);
+    }
+
+    #[test]
+    fn test_empty() {
+        let buffer = encode(TSDataType::DOUBLE, &[]);
+
+        // Only the ending value and an empty byte
+        assert_eq!(vec![0x7F, 0xF8, 0, 0, 0, 0, 0, 0, 0], buffer);
+        assert!(GorillaDecoder::new(TSDataType::DOUBLE)
+            .decode(&buffer)
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let floats: Vec<IoTDBValue> = (0..10000)
+            .map(|i| IoTDBValue::FLOAT(20.0 + ((i / 10) as f32 * 0.1).sin()))
+            .collect();
+        let doubles: Vec<IoTDBValue> = (0..10000)
+            .map(|i| match i % 7 {
+                0 => IoTDBValue::DOUBLE(-0.0),
+                1 => IoTDBValue::DOUBLE(f64::MAX),
+                2 => IoTDBValue::DOUBLE(f64::INFINITY),
+                _ => IoTDBValue::DOUBLE(1013.25 + i as f64 / 1000.0),
+            })
+            .collect();
+
+        let ints: Vec<IoTDBValue> = (0..10000)
+            .map(|i| IoTDBValue::INT(if i % 100 == 0 { i32::MAX } else { -i * 3 }))
+            .collect();
+        let longs: Vec<IoTDBValue> = (0..10000)
+            .map(|i| IoTDBValue::LONG(1_600_000_000_000 + i * 1000 + i % 7))
+            .collect();
+
+        for (data_type, values) in [
+            (TSDataType::INT32, ints),
+            (TSDataType::INT64, longs),
+            (TSDataType::FLOAT, floats),
+            (TSDataType::DOUBLE, doubles),
+        ] {
+            let mut encoder = GorillaEncoder::new(data_type);
+            // The encoder is reused for every page
+            for _ in 0..2 {
+                for v in values.iter() {
+                    encoder.write(v).unwrap();
+                }
+                let mut buffer = vec![];
+                encoder.serialize(&mut buffer);
+                let decoded = GorillaDecoder::new(data_type).decode(&buffer).unwrap();
+                assert_eq!(values, decoded);
+            }
+        }
+    }
+}
@@ -1,17 +1,39 @@
 //! Different Encoding Algorithms for TsFiles
 use crate::writer::{IoTDBValue, TSDataType, TsFileError};
 
+pub mod chimp;
+pub mod dictionary;
+pub mod float;
+pub mod gorilla;
 pub mod plain;
+pub mod rle;
+pub mod sprintz;
 pub mod time_encoder;
+pub mod zigzag;
 
-use crate::writer::encoding::plain::PlainEncoder;
-use crate::writer::encoding::time_encoder::{IntTs2DiffEncoder, LongTs2DiffEncoder};
-use crate::writer::TSEncoding::{PLAIN, TS2DIFF};
+use crate::writer::encoding::chimp::{ChimpDecoder, ChimpEncoder};
+use crate::writer::encoding::dictionary::{DictionaryDecoder, DictionaryEncoder};
+use crate::writer::encoding::float::{FloatDecoder, FloatEncoder, DEFAULT_MAX_POINT_NUMBER};
+use crate::writer::encoding::gorilla::{GorillaDecoder, GorillaEncoder};
+use crate::writer::encoding::plain::{PlainDecoder, PlainEncoder};
+use crate::writer::encoding::rle::{RleDecoder, RleEncoder};
+use crate::writer::encoding::sprintz::{SprintzDecoder, SprintzEncoder};
+use crate::writer::encoding::time_encoder::{
+    IntTs2DiffDecoder, IntTs2DiffEncoder, LongTs2DiffDecoder, LongTs2DiffEncoder,
+};
+use crate::writer::encoding::zigzag::{ZigzagDecoder, ZigzagEncoder};
+use crate::writer::TSEncoding::{CHIMP, DICTIONARY, GORILLA, PLAIN, RLE, SPRINTZ, TS2DIFF, ZIGZAG};
 
 #[derive(PartialEq, Copy, Clone, Debug)]
 pub enum TSEncoding {
     PLAIN,
+    RLE,
     TS2DIFF,
+    DICTIONARY,
+    GORILLA,
+    ZIGZAG,
+    CHIMP,
+    SPRINTZ,
 }
 
 impl TryFrom<u8> for TSEncoding {
@@ -20,7 +42,13 @@ impl TryFrom<u8> for TSEncoding {
     fn try_from(value: u8) -> Result<Self, Self::Error> {
         match value {
             0 => Ok(PLAIN),
+            2 => Ok(RLE),
             4 => Ok(TS2DIFF),
+            7 => Ok(DICTIONARY),
+            8 => Ok(GORILLA),
+            9 => Ok(ZIGZAG),
+            11 => Ok(CHIMP),
+            12 => Ok(SPRINTZ),
             _ => Err(()),
         }
     }
@@ -30,12 +58,18 @@ impl TSEncoding {
     pub fn serialize(&self) -> u8 {
         match self {
             TSEncoding::PLAIN => 0,
+            TSEncoding::RLE => 2,
             TSEncoding::TS2DIFF => 4,
+            TSEncoding::DICTIONARY => 7,
+            TSEncoding::GORILLA => 8,
+            TSEncoding::ZIGZAG => 9,
+            TSEncoding::CHIMP => 11,
+            TSEncoding::SPRINTZ => 12,
         }
     }
 }
 
-pub trait Encoder {
+pub trait Encoder: Send {
     fn write(&mut self, value: &IoTDBValue) -> Result<(), TsFileError>;
     fn size(&mut self) -> u32;
     fn get_max_byte_size(&self) -> u32;
@@ -47,12 +81,98 @@ impl dyn Encoder {
     pub(crate) fn new(
         data_type: TSDataType,
         encoding: TSEncoding,
+    ) -> Result<Box<dyn Encoder>, TsFileError> {
+        Self::new_with_max_point_number(data_type, encoding, DEFAULT_MAX_POINT_NUMBER)
+    }
+
+    /// `max_point_number` is the number of decimal places kept by TS2DIFF for FLOAT and DOUBLE
+    pub(crate) fn new_with_max_point_number(
+        data_type: TSDataType,
+        encoding: TSEncoding,
+        max_point_number: u32,
     ) -> Result<Box<dyn Encoder>, TsFileError> {
         match (data_type, encoding) {
+            (TSDataType::VECTOR, _) => Err(TsFileError::Encoding),
             (_, TSEncoding::PLAIN) => Ok(Box::new(PlainEncoder::new(data_type))),
             (TSDataType::INT64, TSEncoding::TS2DIFF) => Ok(Box::new(LongTs2DiffEncoder::new())),
             (TSDataType::INT32, TSEncoding::TS2DIFF) => Ok(Box::new(IntTs2DiffEncoder::new())),
+            (TSDataType::FLOAT | TSDataType::DOUBLE, TSEncoding::TS2DIFF) => {
+                Ok(Box::new(FloatEncoder::new(data_type, max_point_number)))
+            }
+            (_, TSEncoding::TS2DIFF) => Err(TsFileError::Encoding),
+            (TSDataType::INT32 | TSDataType::INT64 | TSDataType::BOOLEAN, TSEncoding::RLE) => {
+                Ok(Box::new(RleEncoder::new(data_type)))
+            }
+            (_, TSEncoding::RLE) => Err(TsFileError::Encoding),
+            (
+                TSDataType::INT32 | TSDataType::INT64 | TSDataType::FLOAT | TSDataType::DOUBLE,
+                TSEncoding::GORILLA,
+            ) => Ok(Box::new(GorillaEncoder::new(data_type))),
+            (_, TSEncoding::GORILLA) => Err(TsFileError::Encoding),
+            (TSDataType::TEXT, TSEncoding::DICTIONARY) => Ok(Box::new(DictionaryEncoder::new())),
+            (_, TSEncoding::DICTIONARY) => Err(TsFileError::Encoding),
+            (TSDataType::INT32 | TSDataType::INT64, TSEncoding::ZIGZAG) => {
+                Ok(Box::new(ZigzagEncoder::new(data_type)))
+            }
+            (_, TSEncoding::ZIGZAG) => Err(TsFileError::Encoding),
+            (
+                TSDataType::INT32 | TSDataType::INT64 | TSDataType::FLOAT | TSDataType::DOUBLE,
+                TSEncoding::CHIMP,
+            ) => Ok(Box::new(ChimpEncoder::new(data_type))),
+            (_, TSEncoding::CHIMP) => Err(TsFileError::Encoding),
+            (
+                TSDataType::INT32 | TSDataType::INT64 | TSDataType::FLOAT | TSDataType::DOUBLE,
+                TSEncoding::SPRINTZ,
+            ) => Ok(Box::new(SprintzEncoder::new(data_type))),
+            (_, TSEncoding::SPRINTZ) => Err(TsFileError::Encoding),
+        }
+    }
+}
+
+/// Counterpart of the Encoder, turns the encoded content of a page back into values
+pub trait Decoder {
+    fn decode(&mut self, buffer: &[u8]) -> Result<Vec<IoTDBValue>, TsFileError>;
+}
+
+impl dyn Decoder {
+    pub(crate) fn new(
+        data_type: TSDataType,
+        encoding: TSEncoding,
+    ) -> Result<Box<dyn Decoder>, TsFileError> {
+        match (data_type, encoding) {
+            (TSDataType::VECTOR, _) => Err(TsFileError::Encoding),
+            (_, TSEncoding::PLAIN) => Ok(Box::new(PlainDecoder::new(data_type))),
+            (TSDataType::INT64, TSEncoding::TS2DIFF) => Ok(Box::new(LongTs2DiffDecoder::new())),
+            (TSDataType::INT32, TSEncoding::TS2DIFF) => Ok(Box::new(IntTs2DiffDecoder::new())),
+            (TSDataType::FLOAT | TSDataType::DOUBLE, TSEncoding::TS2DIFF) => {
+                Ok(Box::new(FloatDecoder::new(data_type)))
+            }
             (_, TSEncoding::TS2DIFF) => Err(TsFileError::Encoding),
+            (TSDataType::INT32 | TSDataType::INT64 | TSDataType::BOOLEAN, TSEncoding::RLE) => {
+                Ok(Box::new(RleDecoder::new(data_type)))
+            }
+            (_, TSEncoding::RLE) => Err(TsFileError::Encoding),
+            (
+                TSDataType::INT32 | TSDataType::INT64 | TSDataType::FLOAT | TSDataType::DOUBLE,
+                TSEncoding::GORILLA,
+            ) => Ok(Box::new(GorillaDecoder::new(data_type))),
+            (_, TSEncoding::GORILLA) => Err(TsFileError::Encoding),
+            (TSDataType::TEXT, TSEncoding::DICTIONARY) => Ok(Box::new(DictionaryDecoder::new())),
+            (_, TSEncoding::DICTIONARY) => Err(TsFileError::Encoding),
+            (TSDataType::INT32 | TSDataType::INT64, TSEncoding::ZIGZAG) => {
+                Ok(Box::new(ZigzagDecoder::new(data_type)))
+            }
+            (_, TSEncoding::ZIGZAG) => Err(TsFileError::Encoding),
+            (
+                TSDataType::INT32 | TSDataType::INT64 | TSDataType::FLOAT | TSDataType::DOUBLE,
+                TSEncoding::CHIMP,
+            ) => Ok(Box::new(ChimpDecoder::new(data_type))),
+            (_, TSEncoding::CHIMP) => Err(TsFileError::Encoding),
+            (
+                TSDataType::INT32 | TSDataType::INT64 | TSDataType::FLOAT | TSDataType::DOUBLE,
+                TSEncoding::SPRINTZ,
+            ) => Ok(Box::new(SprintzDecoder::new(data_type))),
+            (_, TSEncoding::SPRINTZ) => Err(TsFileError::Encoding),
         }
     }
 }
@@ -1,12 +1,14 @@
 use std::io::Write;
 
-use crate::writer::encoding::Encoder;
+use crate::writer::encoding::{Decoder, Encoder};
 use crate::writer::{utils, IoTDBValue};
 use crate::writer::{TSDataType, TsFileError};
 
 pub struct PlainEncoder {
     data_type: TSDataType,
     pub(crate) buffer: Vec<u8>,
+    /// Length of the longest TEXT value written so far
+    max_text_length: u32,
 }
 
 impl PlainEncoder {
@@ -14,6 +16,7 @@ impl PlainEncoder {
         Self {
             data_type,
             buffer: Vec::new(),
+            max_text_length: 0,
         }
     }
 }
@@ -21,6 +24,14 @@ impl PlainEncoder {
 impl Encoder for PlainEncoder {
     fn write(&mut self, value: &IoTDBValue) -> Result<(), TsFileError> {
         match value {
+            IoTDBValue::BOOLEAN(v) => {
+                self.buffer.write_all(&[*v as u8])?;
+            }
+            IoTDBValue::TEXT(v) => {
+                utils::write_var_i32(v.len() as i32, &mut self.buffer)?;
+                self.buffer.write_all(v)?;
+                self.max_text_length = self.max_text_length.max(v.len() as u32);
+            }
             IoTDBValue::DOUBLE(v) => {
                 self.buffer.write_all(&v.to_be_bytes())?;
             }
@@ -46,6 +57,11 @@ impl Encoder for PlainEncoder {
             TSDataType::INT32 => (24 + self.buffer.len()) as u32,
             TSDataType::INT64 => 0,
             TSDataType::FLOAT => 0,
+            TSDataType::DOUBLE => 0,
+            TSDataType::BOOLEAN => 0,
+            // Values have variable length, so reserve space for one more of the longest value
+            TSDataType::TEXT => 5 + self.max_text_length,
+            TSDataType::VECTOR => 0,
         }
     }
     fn serialize(&mut self, buffer: &mut Vec<u8>) {
@@ -54,5 +70,46 @@ impl Encoder for PlainEncoder {
 
     fn reset(&mut self) {
         self.buffer.clear();
+        self.max_text_length = 0;
+    }
+}
+
+pub struct PlainDecoder {
+    data_type: TSDataType,
+}
+
+impl PlainDecoder {
+    pub(crate) fn new(data_type: TSDataType) -> PlainDecoder {
+        Self { data_type }
+    }
+}
+
+impl Decoder for PlainDecoder {
+    fn decode(&mut self, buffer: &[u8]) -> Result<Vec<IoTDBValue>, TsFileError> {
+        let mut reader = buffer;
+        let mut result = vec![];
+        while !reader.is_empty() {
+            let value = match self.data_type {
+                TSDataType::INT32 => IoTDBValue::INT(utils::read_var_i32(&mut reader)?),
+                TSDataType::INT64 => IoTDBValue::LONG(utils::read_i64(&mut reader)?),
+                TSDataType::FLOAT => IoTDBValue::FLOAT(utils::read_f32(&mut reader)?),
+                TSDataType::DOUBLE => IoTDBValue::DOUBLE(utils::read_f64(&mut reader)?),
+                TSDataType::BOOLEAN => IoTDBValue::BOOLEAN(utils::read_byte(&mut reader)? == 1),
+                TSDataType::TEXT => {
+                    let length = utils::read_var_i32(&mut reader)?;
+                    if length < 0 || length as usize > reader.len() {
+                        return Err(TsFileError::ReadError {
+                            source: Some(format!("Invalid text length {}", length)),
+                        });
+                    }
+                    let (value, rest) = reader.split_at(length as usize);
+                    reader = rest;
+                    IoTDBValue::TEXT(value.to_vec())
+                }
+                TSDataType::VECTOR => return Err(TsFileError::Encoding),
+            };
+            result.push(value);
+        }
+        Ok(result)
     }
 }
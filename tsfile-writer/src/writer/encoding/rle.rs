@@ -0,0 +1,414 @@
+//! Run length / bit packing hybrid encoding (RLE) for INT32, INT64 and BOOLEAN,
+//! compatible with the `IntRleEncoder` and `LongRleEncoder` of IoTDB.
+//!
+//! The values of a page are encoded as one block, prefixed by its length (var u32).
+//! A block starts with the bit width of the values, followed by runs that are either
+//! a repeated value (header `count << 1`) or up to 63 bit packed groups of 8 values
+//! (header `groups << 1 | 1` and the number of used values in the last group).
+use crate::writer::encoding::time_encoder::read_bits;
+use crate::writer::encoding::{Decoder, Encoder};
+use crate::writer::utils::{bytes_for_bits, read_byte, read_var_u32, write_var_u32};
+use crate::writer::{IoTDBValue, TSDataType, TsFileError};
+
+/// Minimal number of repeated values that are written as run, also the size of a bit packed group
+const RLE_MIN_REPEATED_NUM: usize = 8;
+const RLE_MAX_REPEATED_NUM: usize = 0x7FFF;
+const RLE_MAX_BIT_PACKED_NUM: usize = 63;
+
+pub struct RleEncoder {
+    data_type: TSDataType,
+    /// The bit width is derived from all values of a page, so they are only encoded on serialize
+    values: Vec<i64>,
+}
+
+impl RleEncoder {
+    pub(crate) fn new(data_type: TSDataType) -> RleEncoder {
+        Self {
+            data_type,
+            values: vec![],
+        }
+    }
+
+    /// Bits of the value as written, INT32 and BOOLEAN values use at most 32 bits
+    fn to_bits(&self, value: i64) -> u64 {
+        match self.data_type {
+            TSDataType::INT64 => value as u64,
+            _ => value as i32 as u32 as u64,
+        }
+    }
+}
+
+impl Encoder for RleEncoder {
+    fn write(&mut self, value: &IoTDBValue) -> Result<(), TsFileError> {
+        let value = match (self.data_type, value) {
+            (TSDataType::INT32, IoTDBValue::INT(v)) => *v as i64,
+            (TSDataType::INT64, IoTDBValue::LONG(v)) => *v,
+            (TSDataType::BOOLEAN, IoTDBValue::BOOLEAN(v)) => *v as i64,
+            _ => return Err(TsFileError::WrongTypeForSeries),
+        };
+        self.values.push(value);
+        Ok(())
+    }
+
+    fn size(&mut self) -> u32 {
+        // Nothing is encoded before serialize
+        0
+    }
+
+    fn get_max_byte_size(&self) -> u32 {
+        let value_size = match self.data_type {
+            TSDataType::INT64 => 8,
+            _ => 4,
+        };
+        let group_num = (self.values.len() / 8 + 1) / 63 + 1;
+        (8 + group_num * 5 + self.values.len() * value_size) as u32
+    }
+
+    fn serialize(&mut self, buffer: &mut Vec<u8>) {
+        let bits: Vec<u64> = self.values.iter().map(|v| self.to_bits(*v)).collect();
+        let bit_width = bits
+            .iter()
+            .map(|v| 64 - v.leading_zeros())
+            .fold(1, u32::max);
+        let mut writer = RleWriter::new(bit_width, self.data_type == TSDataType::INT64);
+        for value in bits {
+            writer.encode_value(value);
+        }
+        writer.flush(buffer);
+        self.values.clear();
+    }
+
+    fn reset(&mut self) {
+        self.values.clear();
+    }
+}
+
+/// Writes `width` bits of the value starting at bit `pos` (most significant bit first)
+fn write_bits(buffer: &mut [u8], pos: usize, width: u32, value: u64) {
+    for i in 0..width as usize {
+        if (value >> (width as usize - 1 - i)) & 0x01 == 1 {
+            buffer[(pos + i) >> 3] |= 0x80 >> ((pos + i) & 0x07);
+        }
+    }
+}
+
+/// Encodes the values of one page, follows the RleEncoder of IoTDB step by step
+struct RleWriter {
+    bit_width: u32,
+    /// Values of runs are written little endian for INT32 but big endian for INT64
+    long_values: bool,
+    out: Vec<u8>,
+    buffered_values: [u64; RLE_MIN_REPEATED_NUM],
+    num_buffered_values: usize,
+    repeat_count: usize,
+    pre_value: u64,
+    bit_packed_groups: Vec<u8>,
+    bit_packed_group_count: usize,
+    is_bit_pack_run: bool,
+}
+
+impl RleWriter {
+    fn new(bit_width: u32, long_values: bool) -> RleWriter {
+        RleWriter {
+            bit_width,
+            long_values,
+            out: vec![bit_width as u8],
+            buffered_values: [0; RLE_MIN_REPEATED_NUM],
+            num_buffered_values: 0,
+            repeat_count: 0,
+            pre_value: 0,
+            bit_packed_groups: vec![],
+            bit_packed_group_count: 0,
+            is_bit_pack_run: false,
+        }
+    }
+
+    fn encode_value(&mut self, value: u64) {
+        if value == self.pre_value {
+            self.repeat_count += 1;
+            if (RLE_MIN_REPEATED_NUM..=RLE_MAX_REPEATED_NUM).contains(&self.repeat_count) {
+                // Keep on counting, the run is written once the value changes
+                return;
+            } else if self.repeat_count == RLE_MAX_REPEATED_NUM + 1 {
+                self.repeat_count = RLE_MAX_REPEATED_NUM;
+                self.write_rle_run();
+                self.repeat_count = 1;
+                self.pre_value = value;
+            }
+        } else {
+            if self.repeat_count >= RLE_MIN_REPEATED_NUM {
+                self.write_rle_run();
+            }
+            self.repeat_count = 1;
+            self.pre_value = value;
+        }
+        self.buffered_values[self.num_buffered_values] = value;
+        self.num_buffered_values += 1;
+        if self.num_buffered_values == RLE_MIN_REPEATED_NUM {
+            self.write_or_append_bit_packed_run();
+        }
+    }
+
+    fn write_rle_run(&mut self) {
+        self.end_previous_bit_packed_run(RLE_MIN_REPEATED_NUM);
+        write_var_u32((self.repeat_count << 1) as u32, &mut self.out)
+            .expect("Writing to a Vec never fails");
+        let byte_count = bytes_for_bits(self.bit_width as usize);
+        for i in 0..byte_count {
+            let shift = match self.long_values {
+                true => 8 * (byte_count - 1 - i),
+                false => 8 * i,
+            };
+            self.out.push((self.pre_value >> shift) as u8);
+        }
+        self.repeat_count = 0;
+        self.num_buffered_values = 0;
+    }
+
+    fn write_or_append_bit_packed_run(&mut self) {
+        if self.bit_packed_group_count >= RLE_MAX_BIT_PACKED_NUM {
+            self.end_previous_bit_packed_run(RLE_MIN_REPEATED_NUM);
+        }
+        self.is_bit_pack_run = true;
+
+        let start = self.bit_packed_groups.len();
+        self.bit_packed_groups
+            .resize(start + self.bit_width as usize, 0);
+        for (i, value) in self.buffered_values.iter().enumerate() {
+            write_bits(
+                &mut self.bit_packed_groups[start..],
+                i * self.bit_width as usize,
+                self.bit_width,
+                *value,
+            );
+        }
+
+        self.num_buffered_values = 0;
+        self.repeat_count = 0;
+        self.bit_packed_group_count += 1;
+    }
+
+    fn end_previous_bit_packed_run(&mut self, last_bit_packed_num: usize) {
+        if !self.is_bit_pack_run {
+            return;
+        }
+        self.out
+            .push(((self.bit_packed_group_count << 1) | 1) as u8);
+        self.out.push(last_bit_packed_num as u8);
+        self.out.append(&mut self.bit_packed_groups);
+        self.is_bit_pack_run = false;
+        self.bit_packed_group_count = 0;
+    }
+
+    fn flush(mut self, buffer: &mut Vec<u8>) {
+        let last_bit_packed_num = self.num_buffered_values;
+        if self.repeat_count >= RLE_MIN_REPEATED_NUM {
+            self.write_rle_run();
+        } else if self.num_buffered_values > 0 {
+            for value in self.buffered_values[self.num_buffered_values..].iter_mut() {
+                *value = 0;
+            }
+            self.write_or_append_bit_packed_run();
+            self.end_previous_bit_packed_run(last_bit_packed_num);
+        } else {
+            self.end_previous_bit_packed_run(RLE_MIN_REPEATED_NUM);
+        }
+        // Without values not even the bit width is written
+        let length = match self.out.len() {
+            1 => 0,
+            l => l,
+        };
+        write_var_u32(length as u32, buffer).expect("Writing to a Vec never fails");
+        buffer.extend_from_slice(&self.out[self.out.len() - length..]);
+    }
+}
+
+pub struct RleDecoder {
+    data_type: TSDataType,
+}
+
+impl RleDecoder {
+    pub(crate) fn new(data_type: TSDataType) -> RleDecoder {
+        Self { data_type }
+    }
+
+    fn to_value(&self, bits: u64) -> IoTDBValue {
+        match self.data_type {
+            TSDataType::INT64 => IoTDBValue::LONG(bits as i64),
+            TSDataType::BOOLEAN => IoTDBValue::BOOLEAN(bits != 0),
+            _ => IoTDBValue::INT(bits as u32 as i32),
+        }
+    }
+
+    fn decode_block(&self, block: &[u8], result: &mut Vec<IoTDBValue>) -> Result<(), TsFileError> {
+        let mut reader = block;
+        let bit_width = read_byte(&mut reader)? as u32;
+        if bit_width > 64 {
+            return Err(TsFileError::Encoding);
+        }
+        while !reader.is_empty() {
+            let header = read_var_u32(&mut reader)? as usize;
+            if header & 1 == 0 {
+                // Run of a repeated value
+                let byte_count = bytes_for_bits(bit_width as usize);
+                if byte_count > reader.len() {
+                    return Err(TsFileError::Encoding);
+                }
+                let (bytes, remainder) = reader.split_at(byte_count);
+                let value = match self.data_type {
+                    TSDataType::INT64 => bytes.iter().fold(0, |v, b| (v << 8) | *b as u64),
+                    _ => bytes.iter().rev().fold(0, |v, b| (v << 8) | *b as u64),
+                };
+                for _ in 0..header >> 1 {
+                    result.push(self.to_value(value));
+                }
+                reader = remainder;
+            } else {
+                // Bit packed groups, only the given number of values of the last group is used
+                let groups = header >> 1;
+                let last_bit_packed_num =
+                    (read_byte(&mut reader)? as usize).min(RLE_MIN_REPEATED_NUM);
+                if groups == 0 {
+                    return Err(TsFileError::Encoding);
+                }
+                let size = (groups * bit_width as usize).min(reader.len());
+                let (bytes, remainder) = reader.split_at(size);
+                let count = (groups - 1) * RLE_MIN_REPEATED_NUM + last_bit_packed_num;
+                if count * bit_width as usize > size * 8 {
+                    return Err(TsFileError::Encoding);
+                }
+                for i in 0..count {
+                    let value = read_bits(bytes, i * bit_width as usize, bit_width);
+                    result.push(self.to_value(value));
+                }
+                reader = remainder;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Decoder for RleDecoder {
+    fn decode(&mut self, buffer: &[u8]) -> Result<Vec<IoTDBValue>, TsFileError> {
+        let mut reader = buffer;
+        let mut result = vec![];
+        while !reader.is_empty() {
+            let length = read_var_u32(&mut reader)? as usize;
+            if length > reader.len() {
+                return Err(TsFileError::Encoding);
+            }
+            let (block, remainder) = reader.split_at(length);
+            if !block.is_empty() {
+                self.decode_block(block, &mut result)?;
+            }
+            reader = remainder;
+        }
+        Ok(result)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::writer::encoding::rle::{RleDecoder, RleEncoder};
+    use crate::writer::encoding::{Decoder, Encoder};
+    use crate::writer::{IoTDBValue, TSDataType};
+
+    fn encode(data_type: TSDataType, values: &[IoTDBValue]) -> Vec<u8> {
+        let mut encoder = RleEncoder::new(data_type);
+        for v in values {
+            encoder.write(v).unwrap();
+        }
+        let mut buffer = vec![];
+        encoder.serialize(&mut buffer);
+        buffer
+    }
+
+    #[test]
+    fn test_int_bit_packed() {
+        let values: Vec<IoTDBValue> = (0..10).map(IoTDBValue::INT).collect();
+
+        let buffer = encode(TSDataType::INT32, &values);
+
+        // length, bit width, header (2 groups), 2 values in the last group, 2 * 4 bytes
+        assert_eq!(
+            vec![0x0B, 0x04, 0x05, 0x02, 0x01, 0x23, 0x45, 0x67, 0x89, 0x00, 0x00, 0x00],
+            buffer
+        );
+        assert_eq!(
+            values,
+            RleDecoder::new(TSDataType::INT32).decode(&buffer).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_int_run() {
+        let values: Vec<IoTDBValue> = vec![IoTDBValue::INT(300); 20];
+
+        let buffer = encode(TSDataType::INT32, &values);
+
+        // length, bit width, header (20 << 1), value little endian
+        assert_eq!(vec![0x04, 0x09, 0x28, 0x2C, 0x01], buffer);
+        assert_eq!(
+            values,
+            RleDecoder::new(TSDataType::INT32).decode(&buffer).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_long_run() {
+        let values: Vec<IoTDBValue> = vec![IoTDBValue::LONG(300); 20];
+
+        let buffer = encode(TSDataType::INT64, &values);
+
+        // Values of runs of INT64 are written big endian
+        assert_eq!(vec![0x04, 0x09, 0x28, 0x01, 0x2C], buffer);
+        assert_eq!(
+            values,
+            RleDecoder::new(TSDataType::INT64).decode(&buffer).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_empty() {
+        assert_eq!(vec![0x00], encode(TSDataType::INT32, &[]));
+        assert!(RleDecoder::new(TSDataType::INT32)
+            .decode(&[0x00])
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let ints: Vec<IoTDBValue> = (0..100_000)
+            .map(|i| {
+                IoTDBValue::INT(match i % 1000 {
+                    0..=99 => -5,
+                    100..=600 => i % 7,
+                    _ => i,
+                })
+            })
+            .collect();
+        let longs: Vec<IoTDBValue> = (0..100_000_i64)
+            .map(|i| {
+                IoTDBValue::LONG(match (i / 50) % 3 {
+                    0 => i64::MIN + 3,
+                    1 => i * 1_000_000_007,
+                    _ => 42,
+                })
+            })
+            .collect();
+        let booleans: Vec<IoTDBValue> = (0..1000)
+            .map(|i| IoTDBValue::BOOLEAN(i % 100 < 50 || i % 3 == 0))
+            .collect();
+
+        for (data_type, values) in [
+            (TSDataType::INT32, ints),
+            (TSDataType::INT64, longs),
+            (TSDataType::BOOLEAN, booleans),
+        ] {
+            let buffer = encode(data_type, &values);
+            let decoded = RleDecoder::new(data_type).decode(&buffer).unwrap();
+            assert_eq!(values, decoded);
+        }
+    }
+}
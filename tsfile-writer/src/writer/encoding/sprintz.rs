@@ -0,0 +1,348 @@
+//! SPRINTZ encoding for INT32, INT64, FLOAT and DOUBLE, compatible with the
+//! `IntSprintzEncoder`, `LongSprintzEncoder`, `FloatSprintzEncoder` and
+//! `DoubleSprintzEncoder` of IoTDB with the default `fire` predictor.
+//!
+//! Values are grouped into blocks of 9. A block starts with the bit width (i32), followed
+//! by its first value and the bit packed prediction errors of the other 8 values.
+//! Floating point numbers are predicted on their bits. The remaining values of a page are
+//! written as a block with the header `count | 0x80` followed by the plain values.
+//!
+//! As in IoTDB, a prediction error of exactly the minimal value of the type (e.g. an INT32
+//! of `i32::MIN` after 0 or a FLOAT of -0.0 after 0.0 at the start of a block) maps to 0 and
+//! is decoded as the prediction.
+use crate::writer::encoding::gorilla::{from_bits, to_bits};
+use crate::writer::encoding::time_encoder::read_bits;
+use crate::writer::encoding::{Decoder, Encoder};
+use crate::writer::utils::{read_i32, read_i64};
+use crate::writer::{IoTDBValue, TSDataType, TsFileError};
+
+const BLOCK_SIZE: usize = 8;
+const PLAIN_BLOCK_FLAG: i32 = 0x80;
+
+/// Truncates a result of an operation on 32 bit values like Java does
+fn wrap(value: i64, value_bits: u32) -> i64 {
+    match value_bits {
+        32 => value as i32 as i64,
+        _ => value,
+    }
+}
+
+/// Predictor that learns which fraction of the last delta continues
+struct Fire {
+    value_bits: u32,
+    learn_shift: u32,
+    bit_width: u32,
+    accumulator: i64,
+    delta: i64,
+}
+
+impl Fire {
+    fn new(value_bits: u32) -> Fire {
+        let (learn_shift, bit_width) = match value_bits {
+            32 => (2, 8),
+            _ => (3, 16),
+        };
+        Fire {
+            value_bits,
+            learn_shift,
+            bit_width,
+            accumulator: 0,
+            delta: 0,
+        }
+    }
+
+    fn predict(&self, previous: i64) -> i64 {
+        let alpha = self.accumulator >> self.learn_shift;
+        let diff = wrap(alpha.wrapping_mul(self.delta), self.value_bits) >> self.bit_width;
+        wrap(previous.wrapping_add(diff), self.value_bits)
+    }
+
+    fn train(&mut self, previous: i64, value: i64, error: i64) {
+        let gradient = match error > 0 {
+            true => self.delta.wrapping_neg(),
+            false => self.delta,
+        };
+        self.accumulator = wrap(self.accumulator.wrapping_sub(gradient), self.value_bits);
+        self.delta = wrap(value.wrapping_sub(previous), self.value_bits);
+    }
+}
+
+/// Bits of the value as i64, 32 bit values are sign extended
+fn to_signed(bits: u64, value_bits: u32) -> i64 {
+    wrap(bits as i64, value_bits)
+}
+
+fn value_bits_of(data_type: TSDataType) -> u32 {
+    match data_type {
+        TSDataType::INT64 | TSDataType::DOUBLE => 64,
+        _ => 32,
+    }
+}
+
+pub struct SprintzEncoder {
+    data_type: TSDataType,
+    value_bits: u32,
+    values: Vec<i64>,
+    buffer: Vec<u8>,
+}
+
+impl SprintzEncoder {
+    pub(crate) fn new(data_type: TSDataType) -> SprintzEncoder {
+        Self {
+            data_type,
+            value_bits: value_bits_of(data_type),
+            values: Vec::with_capacity(BLOCK_SIZE + 1),
+            buffer: vec![],
+        }
+    }
+
+    fn write_value(&mut self, value: i64) {
+        match self.value_bits {
+            32 => self.buffer.extend_from_slice(&(value as i32).to_be_bytes()),
+            _ => self.buffer.extend_from_slice(&value.to_be_bytes()),
+        }
+    }
+
+    fn write_plain_block(&mut self) {
+        let header = self.values.len() as i32 | PLAIN_BLOCK_FLAG;
+        self.buffer.extend_from_slice(&header.to_be_bytes());
+        for value in std::mem::take(&mut self.values) {
+            self.write_value(value);
+        }
+    }
+
+    fn write_block(&mut self) {
+        let mut fire = Fire::new(self.value_bits);
+        let mut errors = [0_u64; BLOCK_SIZE];
+        for (i, slot) in errors.iter_mut().enumerate() {
+            let (previous, value) = (self.values[i], self.values[i + 1]);
+            let error = wrap(value.wrapping_sub(fire.predict(previous)), self.value_bits);
+            fire.train(previous, value, error);
+            // Like in IoTDB the minimal error wraps to 0, so its value is not restored
+            let mapped = match error <= 0 {
+                true => error.wrapping_mul(-2),
+                false => error.wrapping_mul(2).wrapping_sub(1),
+            };
+            *slot = match self.value_bits {
+                32 => mapped as u32 as u64,
+                _ => mapped as u64,
+            };
+        }
+        let bit_width = errors
+            .iter()
+            .map(|e| 64 - e.leading_zeros())
+            .fold(1, u32::max);
+        self.buffer
+            .extend_from_slice(&(bit_width as i32).to_be_bytes());
+        self.write_value(self.values[0]);
+        let mut packed = vec![0_u8; bit_width as usize];
+        for (i, error) in errors.iter().enumerate() {
+            for bit in 0..bit_width as usize {
+                if (error >> (bit_width as usize - 1 - bit)) & 0x01 == 1 {
+                    let pos = i * bit_width as usize + bit;
+                    packed[pos >> 3] |= 0x80 >> (pos & 0x07);
+                }
+            }
+        }
+        self.buffer.extend_from_slice(&packed);
+        self.values.clear();
+    }
+}
+
+impl Encoder for SprintzEncoder {
+    fn write(&mut self, value: &IoTDBValue) -> Result<(), TsFileError> {
+        let bits = to_bits(self.data_type, value)?;
+        self.values.push(to_signed(bits, self.value_bits));
+        if self.values.len() == BLOCK_SIZE + 1 {
+            self.write_block();
+        }
+        Ok(())
+    }
+
+    fn size(&mut self) -> u32 {
+        self.buffer.len() as u32
+    }
+
+    fn get_max_byte_size(&self) -> u32 {
+        // The remaining values as plain block
+        4 + (BLOCK_SIZE as u32 + 1) * self.value_bits / 8
+    }
+
+    fn serialize(&mut self, buffer: &mut Vec<u8>) {
+        if !self.values.is_empty() {
+            self.write_plain_block();
+        }
+        buffer.append(&mut self.buffer);
+    }
+
+    fn reset(&mut self) {
+        self.values.clear();
+        self.buffer.clear();
+    }
+}
+
+pub struct SprintzDecoder {
+    data_type: TSDataType,
+    value_bits: u32,
+}
+
+impl SprintzDecoder {
+    pub(crate) fn new(data_type: TSDataType) -> SprintzDecoder {
+        Self {
+            data_type,
+            value_bits: value_bits_of(data_type),
+        }
+    }
+
+    fn read_value(&self, reader: &mut &[u8]) -> Result<i64, TsFileError> {
+        match self.value_bits {
+            32 => Ok(read_i32(reader)? as i64),
+            _ => read_i64(reader),
+        }
+    }
+
+    fn push(&self, value: i64, result: &mut Vec<IoTDBValue>) {
+        let bits = match self.value_bits {
+            32 => value as u32 as u64,
+            _ => value as u64,
+        };
+        result.push(from_bits(self.data_type, bits));
+    }
+}
+
+impl Decoder for SprintzDecoder {
+    fn decode(&mut self, buffer: &[u8]) -> Result<Vec<IoTDBValue>, TsFileError> {
+        let mut reader = buffer;
+        let mut result = vec![];
+        while !reader.is_empty() {
+            let header = read_i32(&mut reader)?;
+            if header & PLAIN_BLOCK_FLAG != 0 {
+                for _ in 0..header & !PLAIN_BLOCK_FLAG {
+                    let value = self.read_value(&mut reader)?;
+                    self.push(value, &mut result);
+                }
+                continue;
+            }
+            let bit_width = header as u32;
+            if bit_width == 0 || bit_width > self.value_bits {
+                return Err(TsFileError::Encoding);
+            }
+            let mut previous = self.read_value(&mut reader)?;
+            self.push(previous, &mut result);
+            if reader.len() < bit_width as usize {
+                return Err(TsFileError::Encoding);
+            }
+            let (packed, remainder) = reader.split_at(bit_width as usize);
+            let mut fire = Fire::new(self.value_bits);
+            for i in 0..BLOCK_SIZE {
+                let mapped = read_bits(packed, i * bit_width as usize, bit_width);
+                let error = match mapped & 0x01 {
+                    0 => ((mapped >> 1) as i64).wrapping_neg(),
+                    _ => ((mapped >> 1) + 1) as i64,
+                };
+                let error = wrap(error, self.value_bits);
+                let value = wrap(fire.predict(previous).wrapping_add(error), self.value_bits);
+                fire.train(previous, value, error);
+                self.push(value, &mut result);
+                previous = value;
+            }
+            reader = remainder;
+        }
+        Ok(result)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::writer::encoding::sprintz::{SprintzDecoder, SprintzEncoder};
+    use crate::writer::encoding::{Decoder, Encoder};
+    use crate::writer::{IoTDBValue, TSDataType};
+
+    fn encode(data_type: TSDataType, values: &[IoTDBValue]) -> Vec<u8> {
+        let mut encoder = SprintzEncoder::new(data_type);
+        for v in values {
+            encoder.write(v).unwrap();
+        }
+        let mut buffer = vec![];
+        encoder.serialize(&mut buffer);
+        buffer
+    }
+
+    #[test]
+    fn test_int_bytes() {
+        let values: Vec<IoTDBValue> = (0..10).map(IoTDBValue::INT).collect();
+
+        let buffer = encode(TSDataType::INT32, &values);
+
+        // The predictor needs many values to learn the slope, so all errors are 1
+        // (mapped to 1) with bit width 1. The last value is written as plain block.
+        let mut expected = vec![0, 0, 0, 1, 0, 0, 0, 0];
+        expected.push(0b1111_1111);
+        expected.extend_from_slice(&[0, 0, 0, 0x81, 0, 0, 0, 9]);
+        assert_eq!(expected, buffer);
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let floats: Vec<IoTDBValue> = (0..10000)
+            .map(|i| match i % 11 {
+                0 => IoTDBValue::FLOAT(-0.0),
+                _ => IoTDBValue::FLOAT(20.0 + ((i / 10) as f32 * 0.1).sin()),
+            })
+            .collect();
+        let doubles: Vec<IoTDBValue> = (0..10001)
+            .map(|i| match i % 7 {
+                0 => IoTDBValue::DOUBLE(-0.0),
+                1 => IoTDBValue::DOUBLE(f64::MAX),
+                _ => IoTDBValue::DOUBLE(1013.25 + i as f64 / 1000.0),
+            })
+            .collect();
+        let ints: Vec<IoTDBValue> = (0..10003)
+            .map(|i| match i % 100 {
+                0 => IoTDBValue::INT(i32::MIN),
+                1 => IoTDBValue::INT(i32::MAX),
+                _ => IoTDBValue::INT(i * 3),
+            })
+            .collect();
+        let longs: Vec<IoTDBValue> = (0..10005)
+            .map(|i| match i % 50 {
+                0 => IoTDBValue::LONG(i64::MIN),
+                _ => IoTDBValue::LONG(1_600_000_000_000 + i * 1000 + i % 7),
+            })
+            .collect();
+
+        for (data_type, values) in [
+            (TSDataType::INT32, ints),
+            (TSDataType::INT64, longs),
+            (TSDataType::FLOAT, floats),
+            (TSDataType::DOUBLE, doubles),
+        ] {
+            let mut encoder = SprintzEncoder::new(data_type);
+            // The encoder is reused for every page
+            for _ in 0..2 {
+                for v in values.iter() {
+                    encoder.write(v).unwrap();
+                }
+                let mut buffer = vec![];
+                encoder.serialize(&mut buffer);
+                let decoded = SprintzDecoder::new(data_type).decode(&buffer).unwrap();
+                assert_eq!(values, decoded);
+            }
+        }
+    }
+
+    #[test]
+    fn test_minimal_error() {
+        let mut values = vec![IoTDBValue::INT(0)];
+        values.extend((0..8).map(|_| IoTDBValue::INT(i32::MIN)));
+
+        let buffer = encode(TSDataType::INT32, &values);
+
+        // The error of i32::MIN wraps to 0 like in IoTDB, all other errors are 0 too
+        assert_eq!(vec![0, 0, 0, 1, 0, 0, 0, 0, 0], buffer);
+        let decoded = SprintzDecoder::new(TSDataType::INT32)
+            .decode(&buffer)
+            .unwrap();
+        assert_eq!(vec![IoTDBValue::INT(0); 9], decoded);
+    }
+}
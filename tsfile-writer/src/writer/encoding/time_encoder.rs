@@ -1,4 +1,5 @@
-use crate::writer::encoding::Encoder;
+use crate::writer::encoding::{Decoder, Encoder};
+use crate::writer::utils::{bytes_for_bits, read_i32, read_i64};
 use crate::writer::{IoTDBValue, TsFileError};
 use std::cmp::max;
 use std::io::Write;
@@ -171,6 +172,68 @@ macro_rules! ts2diff_encoder {
 ts2diff_encoder!(LongTs2DiffEncoder, i64, 64);
 ts2diff_encoder!(IntTs2DiffEncoder, i32, 32);
 
+/// Reads `width` bits starting at bit `pos` (most significant bit first)
+pub(crate) fn read_bits(buffer: &[u8], pos: usize, width: u32) -> u64 {
+    let mut value: u64 = 0;
+    for i in pos..pos + width as usize {
+        let bit = (buffer[i >> 3] >> (7 - (i & 0x07))) & 0x01;
+        value = (value << 1) | bit as u64;
+    }
+    value
+}
+
+macro_rules! ts2diff_decoder {
+    ( $name:ident, $type:ty, $variant:ident, $read:ident ) => {
+        pub struct $name {}
+
+        impl $name {
+            pub(crate) fn new() -> $name {
+                Self {}
+            }
+        }
+
+        impl Decoder for $name {
+            fn decode(&mut self, buffer: &[u8]) -> Result<Vec<IoTDBValue>, TsFileError> {
+                let mut reader = buffer;
+                let mut result = vec![];
+                while !reader.is_empty() {
+                    // Header, see flush of the encoder
+                    // Negative counts or widths beyond the type come from corrupt data
+                    let number_of_entries = usize::try_from(read_i32(&mut reader)?)
+                        .map_err(|_| TsFileError::Encoding)?;
+                    let write_width = u32::try_from(read_i32(&mut reader)?)
+                        .ok()
+                        .filter(|width| *width <= <$type>::BITS)
+                        .ok_or(TsFileError::Encoding)?;
+                    let min_delta = $read(&mut reader)?;
+                    let mut previous = $read(&mut reader)?;
+                    result.push(IoTDBValue::$variant(previous));
+
+                    let payload_bits = number_of_entries
+                        .checked_mul(write_width as usize)
+                        .ok_or(TsFileError::Encoding)?;
+                    let payload_size = bytes_for_bits(payload_bits);
+                    if payload_size > reader.len() {
+                        return Err(TsFileError::Encoding);
+                    }
+                    let (payload, remainder) = reader.split_at(payload_size);
+                    for i in 0..number_of_entries {
+                        let delta =
+                            read_bits(payload, i * write_width as usize, write_width) as $type;
+                        previous = previous.wrapping_add(min_delta).wrapping_add(delta);
+                        result.push(IoTDBValue::$variant(previous));
+                    }
+                    reader = remainder;
+                }
+                Ok(result)
+            }
+        }
+    };
+}
+
+ts2diff_decoder!(LongTs2DiffDecoder, i64, LONG, read_i64);
+ts2diff_decoder!(IntTs2DiffDecoder, i32, INT, read_i32);
+
 impl LongTs2DiffEncoder {
     #[allow(unused_variables)]
     pub(crate) fn to_bytes(number: i64, result: &mut Vec<u8>, pos: usize, width: u32) {
@@ -256,7 +319,11 @@ impl IntTs2DiffEncoder {
 
 #[cfg(test)]
 mod tests {
-    use crate::writer::encoding::time_encoder::{IntTs2DiffEncoder, LongTs2DiffEncoder};
+    use crate::writer::encoding::time_encoder::{
+        IntTs2DiffDecoder, IntTs2DiffEncoder, LongTs2DiffDecoder, LongTs2DiffEncoder,
+    };
+    use crate::writer::encoding::{Decoder, Encoder};
+    use crate::writer::{IoTDBValue, TsFileError};
 
     #[test]
     fn test_long_to_bytes() {
@@ -300,4 +367,66 @@ mod tests {
 
         assert_eq!(result, [0b00010001, 0b00010000])
     }
+
+    #[test]
+    fn test_long_round_trip() {
+        let values: Vec<IoTDBValue> = (0..1000_i64)
+            .map(|i| IoTDBValue::LONG(i * i - 300 * i))
+            .collect();
+        let mut encoder = LongTs2DiffEncoder::new();
+        for v in values.iter() {
+            encoder.write(v).unwrap();
+        }
+        let mut buffer = vec![];
+        encoder.serialize(&mut buffer);
+
+        let decoded = LongTs2DiffDecoder::new().decode(&buffer).unwrap();
+
+        assert_eq!(values, decoded);
+    }
+
+    #[test]
+    fn test_int_round_trip() {
+        let values: Vec<IoTDBValue> = [7, 7, -3, 100_000, 12, 0, -1]
+            .iter()
+            .map(|i| IoTDBValue::INT(*i))
+            .collect();
+        let mut encoder = IntTs2DiffEncoder::new();
+        for v in values.iter() {
+            encoder.write(v).unwrap();
+        }
+        let mut buffer = vec![];
+        encoder.serialize(&mut buffer);
+
+        let decoded = IntTs2DiffDecoder::new().decode(&buffer).unwrap();
+
+        assert_eq!(values, decoded);
+    }
+
+    #[test]
+    fn test_decode_corrupt_header() {
+        fn block(number_of_entries: i32, write_width: i32) -> Vec<u8> {
+            let mut buffer = vec![];
+            buffer.extend(number_of_entries.to_be_bytes());
+            buffer.extend(write_width.to_be_bytes());
+            buffer.extend(0i64.to_be_bytes());
+            buffer.extend(0i64.to_be_bytes());
+            buffer
+        }
+        for buffer in [
+            block(-1, 1),
+            block(1, -1),
+            block(1, 65),
+            block(i32::MAX, 64),
+        ] {
+            assert_eq!(
+                Err(TsFileError::Encoding),
+                LongTs2DiffDecoder::new().decode(&buffer)
+            );
+        }
+        assert_eq!(
+            Ok(vec![IoTDBValue::LONG(0)]),
+            LongTs2DiffDecoder::new().decode(&block(0, 64))
+        );
+    }
 }
@@ -0,0 +1,190 @@
+//! ZIGZAG encoding for INT32 and INT64, compatible with the `IntZigzagEncoder` and
+//! `LongZigzagEncoder` of IoTDB.
+//!
+//! The values of a page are mapped to unsigned numbers (0, -1, 1, -2, ... to 0, 1, 2, 3, ...)
+//! and written as var ints, prefixed by the length of this block and the number of values.
+use crate::writer::encoding::{Decoder, Encoder};
+use crate::writer::utils::{read_var_u32, write_var_u32};
+use crate::writer::{IoTDBValue, TSDataType, TsFileError};
+
+pub struct ZigzagEncoder {
+    data_type: TSDataType,
+    values: Vec<i64>,
+}
+
+impl ZigzagEncoder {
+    pub(crate) fn new(data_type: TSDataType) -> ZigzagEncoder {
+        Self {
+            data_type,
+            values: vec![],
+        }
+    }
+}
+
+/// Writes the value as var int, 7 bits per byte starting with the lowest ones
+fn write_var_u64(mut value: u64, buffer: &mut Vec<u8>) {
+    while value & !0x7F != 0 {
+        buffer.push((value & 0x7F) as u8 | 0x80);
+        value >>= 7;
+    }
+    buffer.push(value as u8);
+}
+
+fn read_var_u64(buffer: &mut &[u8]) -> Result<u64, TsFileError> {
+    let mut value = 0_u64;
+    for shift in (0..64).step_by(7) {
+        let (&b, remainder) = buffer.split_first().ok_or(TsFileError::Encoding)?;
+        *buffer = remainder;
+        value |= ((b & 0x7F) as u64) << shift;
+        if b & 0x80 == 0 {
+            return Ok(value);
+        }
+    }
+    Err(TsFileError::Encoding)
+}
+
+impl Encoder for ZigzagEncoder {
+    fn write(&mut self, value: &IoTDBValue) -> Result<(), TsFileError> {
+        let value = match (self.data_type, value) {
+            (TSDataType::INT32, IoTDBValue::INT(v)) => *v as i64,
+            (TSDataType::INT64, IoTDBValue::LONG(v)) => *v,
+            _ => return Err(TsFileError::WrongTypeForSeries),
+        };
+        self.values.push(value);
+        Ok(())
+    }
+
+    fn size(&mut self) -> u32 {
+        // Nothing is encoded before serialize
+        0
+    }
+
+    fn get_max_byte_size(&self) -> u32 {
+        let value_size = match self.data_type {
+            TSDataType::INT64 => 10,
+            _ => 5,
+        };
+        (10 + self.values.len() * value_size) as u32
+    }
+
+    fn serialize(&mut self, buffer: &mut Vec<u8>) {
+        if self.values.is_empty() {
+            return;
+        }
+        let mut block = vec![];
+        for value in self.values.iter() {
+            let zigzag = match self.data_type {
+                TSDataType::INT64 => ((value << 1) ^ (value >> 63)) as u64,
+                _ => {
+                    let value = *value as i32;
+                    ((value << 1) ^ (value >> 31)) as u32 as u64
+                }
+            };
+            write_var_u64(zigzag, &mut block);
+        }
+        write_var_u32(block.len() as u32, buffer).expect("Writing to a Vec never fails");
+        write_var_u32(self.values.len() as u32, buffer).expect("Writing to a Vec never fails");
+        buffer.extend_from_slice(&block);
+        self.values.clear();
+    }
+
+    fn reset(&mut self) {
+        self.values.clear();
+    }
+}
+
+pub struct ZigzagDecoder {
+    data_type: TSDataType,
+}
+
+impl ZigzagDecoder {
+    pub(crate) fn new(data_type: TSDataType) -> ZigzagDecoder {
+        Self { data_type }
+    }
+}
+
+impl Decoder for ZigzagDecoder {
+    fn decode(&mut self, buffer: &[u8]) -> Result<Vec<IoTDBValue>, TsFileError> {
+        let mut reader = buffer;
+        let mut result = vec![];
+        while !reader.is_empty() {
+            let length = read_var_u32(&mut reader)? as usize;
+            let count = read_var_u32(&mut reader)?;
+            if length > reader.len() {
+                return Err(TsFileError::Encoding);
+            }
+            let (mut block, remainder) = reader.split_at(length);
+            for _ in 0..count {
+                let zigzag = read_var_u64(&mut block)?;
+                let value = (zigzag >> 1) as i64 ^ -((zigzag & 1) as i64);
+                result.push(match self.data_type {
+                    TSDataType::INT64 => IoTDBValue::LONG(value),
+                    _ => IoTDBValue::INT(value as i32),
+                });
+            }
+            reader = remainder;
+        }
+        Ok(result)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::writer::encoding::zigzag::{ZigzagDecoder, ZigzagEncoder};
+    use crate::writer::encoding::{Decoder, Encoder};
+    use crate::writer::{IoTDBValue, TSDataType};
+
+    fn encode(data_type: TSDataType, values: &[IoTDBValue]) -> Vec<u8> {
+        let mut encoder = ZigzagEncoder::new(data_type);
+        for v in values {
+            encoder.write(v).unwrap();
+        }
+        let mut buffer = vec![];
+        encoder.serialize(&mut buffer);
+        buffer
+    }
+
+    #[test]
+    fn test_int_bytes() {
+        let values: Vec<IoTDBValue> = [0, -1, 1, -64, 64].map(IoTDBValue::INT).to_vec();
+
+        let buffer = encode(TSDataType::INT32, &values);
+
+        assert_eq!(vec![0x06, 0x05, 0x00, 0x01, 0x02, 0x7F, 0x80, 0x01], buffer);
+    }
+
+    #[test]
+    fn test_empty() {
+        assert!(encode(TSDataType::INT64, &[]).is_empty());
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let ints: Vec<IoTDBValue> = (0..10000)
+            .map(|i| {
+                IoTDBValue::INT(match i % 5 {
+                    0 => i32::MIN,
+                    1 => i32::MAX,
+                    _ => (i % 300) - 150,
+                })
+            })
+            .collect();
+        let longs: Vec<IoTDBValue> = (0..10000_i64)
+            .map(|i| {
+                IoTDBValue::LONG(match i % 5 {
+                    0 => i64::MIN,
+                    1 => i64::MAX,
+                    _ => (i % 300) - 150,
+                })
+            })
+            .collect();
+
+        for (data_type, values) in [(TSDataType::INT32, ints), (TSDataType::INT64, longs)] {
+            let buffer = encode(data_type, &values);
+            assert_eq!(
+                values,
+                ZigzagDecoder::new(data_type).decode(&buffer).unwrap()
+            );
+        }
+    }
+}
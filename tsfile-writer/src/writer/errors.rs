@@ -6,6 +6,7 @@ pub enum TsFileError {
     Error { source: Option<String> }, // Generic Error
     IoError { source: std::io::Error },
     WriteError,
+    ReadError { source: Option<String> },
     OutOfOrderData,
     IllegalState { source: Option<String> },
     Compression,
@@ -22,6 +23,10 @@ impl PartialEq for TsFileError {
             },
             TsFileError::IoError { .. } => false,
             TsFileError::WriteError => matches!(other, TsFileError::WriteError),
+            TsFileError::ReadError { source: a } => match other {
+                TsFileError::ReadError { source: b } => a == b,
+                _ => false,
+            },
             TsFileError::OutOfOrderData => matches!(other, TsFileError::OutOfOrderData),
             TsFileError::IllegalState { source: a } => match other {
                 TsFileError::IllegalState { source: b } => a == b,
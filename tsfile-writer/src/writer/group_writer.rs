@@ -1,56 +1,421 @@
 use crate::writer::chunk_writer::ChunkWriter;
 use crate::writer::errors::TsFileError;
+use crate::writer::ts_file_config::{DuplicatePolicy, TsFileConfig};
 use crate::writer::tsfile_io_writer::TsFileIoWriter;
 use crate::writer::tsfile_writer::DataPoint;
-use crate::writer::{IoTDBValue, PositionedWrite};
-use std::collections::BTreeMap;
+use crate::writer::{IoTDBValue, MeasurementGroup, MeasurementSchema, PositionedWrite};
+use std::collections::{BTreeMap, HashMap};
+use std::mem::size_of;
 
-pub struct GroupWriter<'a> {
-    pub(crate) path: &'a str,
-    pub(crate) chunk_writers: BTreeMap<&'a str, ChunkWriter>,
-    pub(crate) last_time_map: BTreeMap<&'a str, i64>,
+/// Key of the last time of aligned devices in the last time map, as all their
+/// measurements share one time column
+const ALIGNED_LAST_TIME_KEY: &str = "";
+
+pub struct GroupWriter {
+    pub(crate) path: String,
+    pub(crate) chunk_writers: BTreeMap<String, ChunkWriter>,
+    pub(crate) last_time_map: BTreeMap<String, i64>,
+    /// Writer of the shared time column, only present for aligned devices
+    pub(crate) time_chunk_writer: Option<ChunkWriter>,
+    /// Only present in the unsequence mode
+    unsequence_buffer: Option<UnsequenceBuffer>,
+    duplicate_policy: DuplicatePolicy,
+    /// With `DuplicatePolicy::KeepLast` the last point of every series is held back until the
+    /// next point or the flush, so that a duplicate can still replace it
+    pending_points: BTreeMap<String, (i64, IoTDBValue)>,
+    /// Like `pending_points` for aligned devices
+    pending_row: Option<(i64, Vec<Option<IoTDBValue>>)>,
+}
+
+/// Points of the unsequence mode, they are written to the chunk writers in the order of
+/// their timestamps on flush
+#[derive(Default)]
+struct UnsequenceBuffer {
+    /// Points of non aligned series by their timestamp
+    points: BTreeMap<String, BTreeMap<i64, IoTDBValue>>,
+    /// Rows of aligned devices by their timestamp, the values are in the order of the chunk
+    /// writers
+    rows: BTreeMap<i64, Vec<Option<IoTDBValue>>>,
+    /// Estimated memory usage in bytes
+    size: usize,
 }
 
-impl<'a> GroupWriter<'a> {
+impl UnsequenceBuffer {
+    fn value_size(value: &IoTDBValue) -> usize {
+        size_of::<IoTDBValue>()
+            + match value {
+                IoTDBValue::TEXT(v) => v.len(),
+                _ => 0,
+            }
+    }
+
+    fn row_size(values: &[Option<IoTDBValue>]) -> usize {
+        size_of::<(i64, Vec<Option<IoTDBValue>>)>()
+            + values
+                .iter()
+                .map(|v| match v {
+                    Some(value) => Self::value_size(value),
+                    None => size_of::<Option<IoTDBValue>>(),
+                })
+                .sum::<usize>()
+    }
+
+    /// Buffers the point, a point with the timestamp of a buffered one is resolved by the
+    /// policy. Returns the number of new records.
+    fn push(
+        &mut self,
+        measurement_id: &str,
+        timestamp: i64,
+        value: IoTDBValue,
+        policy: DuplicatePolicy,
+    ) -> Result<u32, TsFileError> {
+        let value_size = Self::value_size(&value);
+        match self.points.get_mut(measurement_id) {
+            Some(points) => match points.get_mut(&timestamp) {
+                Some(last_value) => {
+                    match policy {
+                        DuplicatePolicy::Reject => return Err(TsFileError::OutOfOrderData),
+                        DuplicatePolicy::KeepFirst => {}
+                        DuplicatePolicy::KeepLast => {
+                            self.size = self.size + value_size - Self::value_size(last_value);
+                            *last_value = value;
+                        }
+                    }
+                    return Ok(0);
+                }
+                None => {
+                    points.insert(timestamp, value);
+                }
+            },
+            None => {
+                self.points.insert(
+                    measurement_id.to_owned(),
+                    BTreeMap::from([(timestamp, value)]),
+                );
+            }
+        }
+        self.size += size_of::<i64>() + value_size;
+        Ok(1)
+    }
+
+    /// Like `push` for a row of an aligned device, the values of a row with the same
+    /// timestamp replace the buffered ones (only nulls with `KeepFirst`), nulls do not
+    fn push_row(
+        &mut self,
+        timestamp: i64,
+        values: Vec<Option<IoTDBValue>>,
+        policy: DuplicatePolicy,
+    ) -> Result<u32, TsFileError> {
+        match self.rows.get_mut(&timestamp) {
+            Some(_) if policy == DuplicatePolicy::Reject => Err(TsFileError::OutOfOrderData),
+            Some(last) => {
+                let last_size = Self::row_size(last);
+                for (last_value, value) in last.iter_mut().zip(values) {
+                    if value.is_some()
+                        && (policy == DuplicatePolicy::KeepLast || last_value.is_none())
+                    {
+                        *last_value = value;
+                    }
+                }
+                self.size = self.size + Self::row_size(last) - last_size;
+                Ok(0)
+            }
+            None => {
+                self.size += Self::row_size(&values);
+                let record_count = values.iter().filter(|v| v.is_some()).count() as u32;
+                self.rows.insert(timestamp, values);
+                Ok(record_count)
+            }
+        }
+    }
+}
+
+impl GroupWriter {
+    /// `last_time_map` contains the last timestamps of the series that are flushed already
+    pub(crate) fn new(
+        path: &str,
+        measurement_group: &MeasurementGroup,
+        config: &TsFileConfig,
+        last_time_map: BTreeMap<String, i64>,
+    ) -> GroupWriter {
+        let aligned = measurement_group.is_aligned();
+        let time_encoding = config.time_encoding;
+        GroupWriter {
+            path: path.to_owned(),
+            chunk_writers: measurement_group
+                .measurement_schemas
+                .iter()
+                .map(|(measurement_id, measurement_schema)| {
+                    let chunk_writer =
+                        Self::new_chunk_writer(measurement_id, measurement_schema, aligned, config);
+                    (measurement_id.clone(), chunk_writer)
+                })
+                .collect(),
+            last_time_map,
+            time_chunk_writer: match aligned {
+                true => Some(
+                    ChunkWriter::new_time(measurement_group.time_compression, time_encoding)
+                        .with_page_thresholds(config),
+                ),
+                false => None,
+            },
+            unsequence_buffer: config
+                .unsequence_memory_budget
+                .map(|_| UnsequenceBuffer::default()),
+            duplicate_policy: config.duplicate_policy,
+            pending_points: BTreeMap::new(),
+            pending_row: None,
+        }
+    }
+
+    fn new_chunk_writer(
+        measurement_id: &str,
+        measurement_schema: &MeasurementSchema,
+        aligned: bool,
+        config: &TsFileConfig,
+    ) -> ChunkWriter {
+        match aligned {
+            true => ChunkWriter::new_value(
+                measurement_id,
+                measurement_schema.data_type,
+                measurement_schema.compression,
+                measurement_schema.encoding,
+            ),
+            false => ChunkWriter::new(
+                measurement_id,
+                measurement_schema.data_type,
+                measurement_schema.compression,
+                measurement_schema.encoding,
+                config.time_encoding,
+            ),
+        }
+        .with_max_point_number(measurement_schema.max_point_number)
+        .with_page_thresholds(config)
+    }
+
+    /// Adds a series to a non aligned device, the rows of aligned devices would lack its column
+    pub(crate) fn add_timeseries(
+        &mut self,
+        measurement_id: &str,
+        measurement_schema: &MeasurementSchema,
+        config: &TsFileConfig,
+    ) -> Result<(), TsFileError> {
+        if self.time_chunk_writer.is_some() {
+            return Err(TsFileError::IllegalState {
+                source: Some(format!(
+                    "Series can not be added to the aligned device {}",
+                    self.path
+                )),
+            });
+        }
+        let chunk_writer =
+            Self::new_chunk_writer(measurement_id, measurement_schema, false, config);
+        self.chunk_writers
+            .insert(measurement_id.to_owned(), chunk_writer);
+        Ok(())
+    }
+
     pub(crate) fn write_many(
         &mut self,
         timestamp: i64,
-        values: impl IntoIterator<Item = DataPoint<'a>>,
+        values: impl IntoIterator<Item = DataPoint>,
     ) -> Result<u32, TsFileError> {
+        if self.time_chunk_writer.is_some() {
+            return self.write_aligned(timestamp, values);
+        }
         let mut records = 0;
         for dp in values {
-            records += self.write(dp.measurement_id, timestamp, dp.value)?;
+            match dp.value {
+                Some(value) => records += self.write(&dp.measurement_id, timestamp, value)?,
+                // Nulls of non aligned series are simply not written
+                None => self.validate(&dp.measurement_id, None)?,
+            }
         }
         Ok(records)
     }
+
+    /// Writes one row of an aligned device, null values and measurements without a value are
+    /// written as null
+    fn write_aligned(
+        &mut self,
+        timestamp: i64,
+        values: impl IntoIterator<Item = DataPoint>,
+    ) -> Result<u32, TsFileError> {
+        let duplicate = self.check_is_history_data(ALIGNED_LAST_TIME_KEY, timestamp)?;
+
+        // Validate the whole row first, all columns have to contain the same rows
+        let mut values: HashMap<String, Option<IoTDBValue>> = values
+            .into_iter()
+            .map(|dp| (dp.measurement_id, dp.value))
+            .collect();
+        for (measurement_id, value) in values.iter() {
+            self.validate(measurement_id, value.as_ref())?;
+        }
+        if duplicate && self.duplicate_policy == DuplicatePolicy::KeepFirst {
+            return Ok(0);
+        }
+        let row: Vec<Option<IoTDBValue>> = self
+            .chunk_writers
+            .keys()
+            .map(|measurement_id| values.remove(measurement_id).flatten())
+            .collect();
+
+        if let Some(buffer) = self.unsequence_buffer.as_mut() {
+            if duplicate {
+                // The row to replace is already flushed
+                return Err(TsFileError::OutOfOrderData);
+            }
+            return buffer.push_row(timestamp, row, self.duplicate_policy);
+        }
+
+        if self.duplicate_policy == DuplicatePolicy::KeepLast {
+            if duplicate && self.pending_row.is_none() {
+                // The row to replace is already flushed
+                return Err(TsFileError::OutOfOrderData);
+            }
+            match self.pending_row.take() {
+                Some((last, mut last_row)) if last == timestamp => {
+                    // Values of the duplicate replace the pending ones, nulls do not
+                    for (last_value, value) in last_row.iter_mut().zip(row) {
+                        if value.is_some() {
+                            *last_value = value;
+                        }
+                    }
+                    self.pending_row = Some((last, last_row));
+                    return Ok(0);
+                }
+                Some((last, last_row)) => {
+                    self.write_row(last, last_row)?;
+                }
+                None => {}
+            }
+            let record_count = row.iter().filter(|value| value.is_some()).count() as u32;
+            self.pending_row = Some((timestamp, row));
+            self.set_last_time(ALIGNED_LAST_TIME_KEY, timestamp);
+            return Ok(record_count);
+        }
+        self.write_row(timestamp, row)
+    }
+
+    /// Writes a validated row, the values are in the order of the chunk writers
+    fn write_row(
+        &mut self,
+        timestamp: i64,
+        row: Vec<Option<IoTDBValue>>,
+    ) -> Result<u32, TsFileError> {
+        let time_chunk_writer = match self.time_chunk_writer.as_mut() {
+            Some(time_chunk_writer) => time_chunk_writer,
+            None => panic!("Aligned write to a non aligned device"),
+        };
+        time_chunk_writer.write_time(timestamp)?;
+        let mut record_count = 0;
+        for (chunk_writer, value) in self.chunk_writers.values_mut().zip(row) {
+            record_count += match value {
+                Some(value) => chunk_writer.write(timestamp, value)?,
+                None => chunk_writer.write_null()?,
+            };
+        }
+        time_chunk_writer
+            .check_aligned_page_size_and_may_open_new_page(self.chunk_writers.values_mut())?;
+        self.set_last_time(ALIGNED_LAST_TIME_KEY, timestamp);
+        Ok(record_count)
+    }
 }
 
-impl<'a> GroupWriter<'a> {
-    pub(crate) fn get_last_time_map(&mut self) -> BTreeMap<&'a str, i64> {
+impl GroupWriter {
+    pub(crate) fn get_last_time_map(&mut self) -> BTreeMap<String, i64> {
         self.last_time_map.clone()
     }
 }
 
-impl<'a> GroupWriter<'a> {
+impl GroupWriter {
     pub(crate) fn flush_to_filewriter<T: PositionedWrite>(
         &mut self,
         file_writer: &mut TsFileIoWriter<T>,
-    ) -> u64 {
+    ) -> Result<u64, TsFileError> {
         log::info!("Start flush device id: {}", &self.path);
 
-        self.seal_all_chunks();
+        self.write_unsequence_buffer()?;
+        self.write_pending()?;
+        self.seal_all_chunks()?;
 
         let current_chunk_group_size = self.get_current_chunk_group_size();
 
+        // The time column precedes the value columns of aligned devices
+        if let Some(time_chunk_writer) = self.time_chunk_writer.as_mut() {
+            time_chunk_writer.write_to_file_writer(file_writer)?;
+        }
         for (_, series_writer) in self.chunk_writers.iter_mut() {
-            series_writer.write_to_file_writer(file_writer);
+            series_writer.write_to_file_writer(file_writer)?;
         }
 
-        current_chunk_group_size
+        Ok(current_chunk_group_size)
+    }
+
+    /// Estimated memory usage of the points of the unsequence mode
+    pub(crate) fn unsequence_buffer_size(&self) -> usize {
+        match &self.unsequence_buffer {
+            Some(buffer) => buffer.size,
+            None => 0,
+        }
+    }
+
+    /// Writes the points of the unsequence mode to the chunk writers
+    fn write_unsequence_buffer(&mut self) -> Result<(), TsFileError> {
+        // Without the buffer the points are written to the chunk writers directly
+        let mut buffer = match self.unsequence_buffer.take() {
+            Some(buffer) => buffer,
+            None => return Ok(()),
+        };
+        let result = self.write_buffered_points(&mut buffer);
+        // After an error the points that are not written yet stay buffered
+        self.unsequence_buffer = Some(buffer);
+        result
+    }
+
+    fn write_buffered_points(&mut self, buffer: &mut UnsequenceBuffer) -> Result<(), TsFileError> {
+        let mut series = std::mem::take(&mut buffer.points).into_iter();
+        while let Some((measurement_id, points)) = series.next() {
+            let mut points = points.into_iter();
+            while let Some((timestamp, value)) = points.next() {
+                if let Err(e) = self.write(&measurement_id, timestamp, value) {
+                    buffer.points.insert(measurement_id, points.collect());
+                    buffer.points.extend(series);
+                    return Err(e);
+                }
+            }
+        }
+        // The rows are validated and in the order of the chunk writers already
+        let mut rows = std::mem::take(&mut buffer.rows).into_iter();
+        while let Some((timestamp, row)) = rows.next() {
+            if let Err(e) = self.write_row(timestamp, row) {
+                buffer.rows.extend(rows);
+                return Err(e);
+            }
+        }
+        buffer.size = 0;
+        Ok(())
+    }
+
+    /// Writes the points held back by `DuplicatePolicy::KeepLast`
+    fn write_pending(&mut self) -> Result<(), TsFileError> {
+        for (measurement_id, (timestamp, value)) in std::mem::take(&mut self.pending_points) {
+            if let Some(chunk_writer) = self.chunk_writers.get_mut(&measurement_id) {
+                chunk_writer.write(timestamp, value)?;
+            }
+        }
+        if let Some((timestamp, row)) = self.pending_row.take() {
+            self.write_row(timestamp, row)?;
+        }
+        Ok(())
     }
 
     pub(crate) fn update_max_group_mem_size(&mut self) -> u32 {
-        let mut buffer_size = 0;
+        let mut buffer_size = self.unsequence_buffer_size() as u32;
+        buffer_size += match self.time_chunk_writer.as_mut() {
+            Some(time_chunk_writer) => time_chunk_writer.estimate_max_series_mem_size(),
+            None => 0,
+        };
         for (_, chunk_writer) in self.chunk_writers.iter_mut() {
             let chunk_writer_size = chunk_writer.estimate_max_series_mem_size();
             log::trace!(
@@ -62,10 +427,14 @@ impl<'a> GroupWriter<'a> {
         }
         buffer_size
     }
-    fn seal_all_chunks(&mut self) {
+    fn seal_all_chunks(&mut self) -> Result<(), TsFileError> {
+        if let Some(time_chunk_writer) = self.time_chunk_writer.as_mut() {
+            time_chunk_writer.seal_current_page()?;
+        }
         for (_, writer) in self.chunk_writers.iter_mut() {
-            writer.seal_current_page();
+            writer.seal_current_page()?;
         }
+        Ok(())
     }
     fn get_current_chunk_group_size(&mut self) -> u64 {
         // long size = 0;
@@ -73,7 +442,10 @@ impl<'a> GroupWriter<'a> {
         //   size += writer.getSerializedChunkSize();
         // }
         // return size;
-        let mut size = 0;
+        let mut size = match &self.time_chunk_writer {
+            Some(time_chunk_writer) => time_chunk_writer.get_serialized_chunk_size(),
+            None => 0,
+        };
         for (_, writer) in self.chunk_writers.iter_mut() {
             size += writer.get_serialized_chunk_size();
         }
@@ -81,39 +453,487 @@ impl<'a> GroupWriter<'a> {
     }
 }
 
-impl<'a> GroupWriter<'a> {
+impl GroupWriter {
     pub(crate) fn write(
         &mut self,
-        measurement_id: &'a str,
+        measurement_id: &str,
         timestamp: i64,
         value: IoTDBValue,
     ) -> Result<u32, TsFileError> {
-        // Check is historic
-        self.check_is_history_data(measurement_id, timestamp)?;
+        if self.time_chunk_writer.is_some() {
+            return self.write_aligned(timestamp, vec![DataPoint::new(measurement_id, value)]);
+        }
+        self.validate(measurement_id, Some(&value))?;
+        let duplicate = self.check_is_history_data(measurement_id, timestamp)?;
+        if duplicate && self.duplicate_policy == DuplicatePolicy::KeepFirst {
+            return Ok(0);
+        }
 
-        let record_count = match &mut self.chunk_writers.get_mut(measurement_id) {
-            Some(chunk_writer) => chunk_writer.write(timestamp, value).unwrap(),
-            None => {
-                return Err(TsFileError::IllegalState {
-                    source: Some("Unknown measurement id".to_owned()),
-                });
+        if let Some(buffer) = self.unsequence_buffer.as_mut() {
+            if duplicate {
+                // The point to replace is already flushed
+                return Err(TsFileError::OutOfOrderData);
             }
-        };
-        self.last_time_map.insert(measurement_id, timestamp);
+            return buffer.push(measurement_id, timestamp, value, self.duplicate_policy);
+        }
+
+        if self.duplicate_policy == DuplicatePolicy::KeepLast {
+            if duplicate && !self.pending_points.contains_key(measurement_id) {
+                // The point to replace is already flushed
+                return Err(TsFileError::OutOfOrderData);
+            }
+            self.set_last_time(measurement_id, timestamp);
+            let previous = match self.pending_points.get_mut(measurement_id) {
+                Some(pending) => Some(std::mem::replace(pending, (timestamp, value))),
+                None => {
+                    self.pending_points
+                        .insert(measurement_id.to_owned(), (timestamp, value));
+                    None
+                }
+            };
+            return match previous {
+                // The duplicate replaced the pending point
+                Some((last, _)) if last == timestamp => Ok(0),
+                Some((last, last_value)) => {
+                    self.chunk_writer(measurement_id).write(last, last_value)?;
+                    Ok(1)
+                }
+                None => Ok(1),
+            };
+        }
+
+        let record_count = self.chunk_writer(measurement_id).write(timestamp, value)?;
+        self.set_last_time(measurement_id, timestamp);
         Ok(record_count)
     }
 
+    /// Only allocates the key for the first point of a series
+    fn set_last_time(&mut self, measurement_id: &str, timestamp: i64) {
+        match self.last_time_map.get_mut(measurement_id) {
+            Some(last_time) => *last_time = timestamp,
+            None => {
+                self.last_time_map
+                    .insert(measurement_id.to_owned(), timestamp);
+            }
+        }
+    }
+
+    /// Chunk writer of a validated measurement id
+    fn chunk_writer(&mut self, measurement_id: &str) -> &mut ChunkWriter {
+        match self.chunk_writers.get_mut(measurement_id) {
+            Some(chunk_writer) => chunk_writer,
+            None => panic!("Write to an unknown measurement id"),
+        }
+    }
+
+    /// Checks that the measurement id exists and that the value matches its data type
+    fn validate(
+        &self,
+        measurement_id: &str,
+        value: Option<&IoTDBValue>,
+    ) -> Result<(), TsFileError> {
+        match (self.chunk_writers.get(measurement_id), value) {
+            (Some(chunk_writer), Some(value)) if chunk_writer.data_type != value.data_type() => {
+                Err(TsFileError::WrongTypeForSeries)
+            }
+            (Some(_), _) => Ok(()),
+            (None, _) => Err(TsFileError::IllegalState {
+                source: Some("Unknown measurement id".to_owned()),
+            }),
+        }
+    }
+
+    /// Returns true for a point with the timestamp of the last point of the series, if the
+    /// duplicate policy accepts it. Earlier points are rejected.
     fn check_is_history_data(
-        &mut self,
-        measurement_id: &'a str,
+        &self,
+        measurement_id: &str,
         timestamp: i64,
-    ) -> Result<(), TsFileError> {
-        if !self.last_time_map.contains_key(measurement_id) {
-            self.last_time_map.insert(measurement_id, -1);
+    ) -> Result<bool, TsFileError> {
+        match self.last_time_map.get(measurement_id) {
+            Some(&last)
+                if timestamp == last && self.duplicate_policy != DuplicatePolicy::Reject =>
+            {
+                Ok(true)
+            }
+            Some(&last) if timestamp <= last => Err(TsFileError::OutOfOrderData),
+            // Timestamps have to be positive
+            None if timestamp < 0 => Err(TsFileError::OutOfOrderData),
+            _ => Ok(false),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::writer::compression::CompressionType;
+    use crate::writer::encoding::TSEncoding;
+    use crate::writer::errors::TsFileError;
+    use crate::writer::group_writer::GroupWriter;
+    use crate::writer::schema::{AlignedDeviceBuilder, DeviceBuilder};
+    use crate::writer::ts_file_config::{DuplicatePolicy, TsFileConfig};
+    use crate::writer::tsfile_writer::DataPoint;
+    use crate::writer::{IoTDBValue, MeasurementGroup, MeasurementSchema, TSDataType};
+    use std::collections::BTreeMap;
+
+    fn device() -> MeasurementGroup {
+        DeviceBuilder::new()
+            .add(
+                "s1",
+                TSDataType::INT64,
+                TSEncoding::PLAIN,
+                CompressionType::UNCOMPRESSED,
+            )
+            .build()
+    }
+
+    fn aligned_device() -> MeasurementGroup {
+        AlignedDeviceBuilder::new()
+            .add(
+                "s1",
+                TSDataType::INT32,
+                TSEncoding::PLAIN,
+                CompressionType::UNCOMPRESSED,
+            )
+            .add(
+                "s2",
+                TSDataType::DOUBLE,
+                TSEncoding::PLAIN,
+                CompressionType::UNCOMPRESSED,
+            )
+            .build()
+    }
+
+    #[test]
+    fn add_timeseries() -> Result<(), TsFileError> {
+        let config = TsFileConfig::default();
+        let schema = MeasurementSchema::new(
+            TSDataType::BOOLEAN,
+            TSEncoding::RLE,
+            CompressionType::UNCOMPRESSED,
+        );
+        let mut group = GroupWriter::new("d1", &device(), &config, BTreeMap::new());
+        group.add_timeseries("s2", &schema, &config)?;
+        assert_eq!(
+            vec!["s1", "s2"],
+            group.chunk_writers.keys().collect::<Vec<_>>()
+        );
+        group.write("s2", 1, IoTDBValue::BOOLEAN(true))?;
+
+        // The buffered rows of aligned devices would lack the column
+        let mut aligned = GroupWriter::new("d2", &aligned_device(), &config, BTreeMap::new());
+        assert_eq!(
+            Err(TsFileError::IllegalState {
+                source: Some("Series can not be added to the aligned device d2".to_owned())
+            }),
+            aligned.add_timeseries("s3", &schema, &config)
+        );
+        assert_eq!(2, aligned.chunk_writers.len());
+        Ok(())
+    }
+
+    #[test]
+    fn write_many_with_nulls() -> Result<(), TsFileError> {
+        let config = TsFileConfig::default();
+        let measurement_group = DeviceBuilder::new()
+            .add(
+                "s1",
+                TSDataType::INT32,
+                TSEncoding::PLAIN,
+                CompressionType::UNCOMPRESSED,
+            )
+            .add(
+                "s2",
+                TSDataType::DOUBLE,
+                TSEncoding::PLAIN,
+                CompressionType::UNCOMPRESSED,
+            )
+            .build();
+        let mut group = GroupWriter::new("d1", &measurement_group, &config, BTreeMap::new());
+        let mut aligned = GroupWriter::new("d2", &aligned_device(), &config, BTreeMap::new());
+        for group in [&mut group, &mut aligned] {
+            let records = group.write_many(
+                1,
+                vec![
+                    DataPoint::new("s1", IoTDBValue::INT(1)),
+                    DataPoint::null("s2"),
+                ],
+            )?;
+            assert_eq!(1, records);
+            let records = group.write_many(
+                2,
+                vec![
+                    DataPoint::from_option("s1", None),
+                    DataPoint::from_option("s2", Some(IoTDBValue::DOUBLE(2.0))),
+                ],
+            )?;
+            assert_eq!(1, records);
+            let records =
+                group.write_many(3, vec![DataPoint::null("s1"), DataPoint::null("s2")])?;
+            assert_eq!(0, records);
+            assert_eq!(
+                Err(TsFileError::IllegalState {
+                    source: Some("Unknown measurement id".to_owned())
+                }),
+                group.write_many(4, vec![DataPoint::null("s3")])
+            );
         }
-        if timestamp <= *self.last_time_map.get(measurement_id).unwrap() {
-            return Err(TsFileError::OutOfOrderData);
+
+        // Nulls of non aligned series are not written
+        assert_eq!(
+            (vec![1], vec![IoTDBValue::INT(1)]),
+            group.chunk_writer("s1").decode_current_page()?
+        );
+        assert_eq!(
+            (vec![2], vec![IoTDBValue::DOUBLE(2.0)]),
+            group.chunk_writer("s2").decode_current_page()?
+        );
+        assert!(group.time_chunk_writer.is_none());
+
+        // Rows of aligned devices are kept even if all values are null
+        let time_chunk_writer = aligned.time_chunk_writer.as_mut().unwrap();
+        assert_eq!(
+            (vec![1, 2, 3], vec![]),
+            time_chunk_writer.decode_current_page()?
+        );
+        let (_, values) = aligned.chunk_writer("s1").decode_current_page()?;
+        assert_eq!(vec![IoTDBValue::INT(1)], values);
+        let (_, values) = aligned.chunk_writer("s2").decode_current_page()?;
+        assert_eq!(vec![IoTDBValue::DOUBLE(2.0)], values);
+        Ok(())
+    }
+
+    #[test]
+    fn unsequence_points() -> Result<(), TsFileError> {
+        let config = TsFileConfig::default().with_unsequence_buffer(u32::MAX);
+        let mut group = GroupWriter::new("d1", &device(), &config, BTreeMap::new());
+        for i in 0..1000_i64 {
+            // A permutation of 0..1000
+            let timestamp = i * 7919 % 1000;
+            assert_eq!(
+                1,
+                group.write("s1", timestamp, IoTDBValue::LONG(timestamp))?
+            );
+        }
+        assert_eq!(
+            Err(TsFileError::WrongTypeForSeries),
+            group.write("s1", 1000, IoTDBValue::INT(0))
+        );
+        assert!(group.unsequence_buffer_size() > 0);
+
+        group.write_unsequence_buffer()?;
+
+        assert_eq!(0, group.unsequence_buffer_size());
+        assert_eq!(Some(&999), group.last_time_map.get("s1"));
+        let (timestamps, values) = group.chunk_writer("s1").decode_current_page()?;
+        assert_eq!((0..1000).collect::<Vec<i64>>(), timestamps);
+        assert_eq!((0..1000).map(IoTDBValue::LONG).collect::<Vec<_>>(), values);
+        Ok(())
+    }
+
+    #[test]
+    fn unsequence_rows() -> Result<(), TsFileError> {
+        let config = TsFileConfig::default().with_unsequence_buffer(u32::MAX);
+        let mut group = GroupWriter::new("d2", &aligned_device(), &config, BTreeMap::new());
+        for timestamp in (0..500_i64).rev().map(|i| i * 2) {
+            let value = IoTDBValue::INT(timestamp as i32);
+            // The null of s2 is no record, like in the sequence mode
+            assert_eq!(
+                1,
+                group.write_many(timestamp, vec![DataPoint::new("s1", value)])?
+            );
+        }
+        group.write("s2", 11, IoTDBValue::DOUBLE(1.5))?;
+
+        group.write_unsequence_buffer()?;
+
+        let mut timestamps: Vec<i64> = (0..500).map(|i| i * 2).collect();
+        timestamps.insert(6, 11);
+        let time_chunk_writer = group.time_chunk_writer.as_mut().unwrap();
+        assert_eq!(
+            (timestamps, vec![]),
+            time_chunk_writer.decode_current_page()?
+        );
+        let (_, values) = group.chunk_writer("s1").decode_current_page()?;
+        assert_eq!(
+            (0..500).map(|i| IoTDBValue::INT(i * 2)).collect::<Vec<_>>(),
+            values
+        );
+        let (_, values) = group.chunk_writer("s2").decode_current_page()?;
+        assert_eq!(vec![IoTDBValue::DOUBLE(1.5)], values);
+        Ok(())
+    }
+
+    #[test]
+    fn unsequence_point_behind_flushed_chunk() -> Result<(), TsFileError> {
+        let config = TsFileConfig::default().with_unsequence_buffer(u32::MAX);
+        // The last time of the flushed chunks
+        let last_times = BTreeMap::from([("s1".to_owned(), 100)]);
+        let mut group = GroupWriter::new("d1", &device(), &config, last_times);
+        assert_eq!(
+            Err(TsFileError::OutOfOrderData),
+            group.write("s1", 50, IoTDBValue::LONG(50))
+        );
+        assert_eq!(1, group.write("s1", 150, IoTDBValue::LONG(150))?);
+        assert_eq!(1, group.write("s1", 120, IoTDBValue::LONG(120))?);
+        Ok(())
+    }
+
+    #[test]
+    fn duplicates() -> Result<(), TsFileError> {
+        for policy in [
+            DuplicatePolicy::Reject,
+            DuplicatePolicy::KeepFirst,
+            DuplicatePolicy::KeepLast,
+        ] {
+            let config = TsFileConfig::default().with_duplicate_policy(policy);
+            let mut group = GroupWriter::new("d1", &device(), &config, BTreeMap::new());
+            let mut aligned = GroupWriter::new("d2", &aligned_device(), &config, BTreeMap::new());
+            group.write("s1", 1, IoTDBValue::LONG(1))?;
+            aligned.write_many(1, vec![DataPoint::new("s1", IoTDBValue::INT(1))])?;
+            let duplicate = group.write("s1", 1, IoTDBValue::LONG(2));
+            let duplicate_row = aligned.write_many(
+                1,
+                vec![
+                    DataPoint::new("s1", IoTDBValue::INT(2)),
+                    DataPoint::new("s2", IoTDBValue::DOUBLE(1.5)),
+                ],
+            );
+            let expected = match policy {
+                DuplicatePolicy::Reject => Err(TsFileError::OutOfOrderData),
+                _ => Ok(0),
+            };
+            assert_eq!(expected, duplicate, "{:?}", policy);
+            assert_eq!(expected, duplicate_row, "{:?}", policy);
+            group.write("s1", 2, IoTDBValue::LONG(3))?;
+            aligned.write_many(2, vec![DataPoint::new("s1", IoTDBValue::INT(3))])?;
+            // Earlier timestamps are rejected by all policies
+            assert_eq!(
+                Err(TsFileError::OutOfOrderData),
+                group.write("s1", 1, IoTDBValue::LONG(4))
+            );
+            assert_eq!(
+                Err(TsFileError::OutOfOrderData),
+                aligned.write("s1", 1, IoTDBValue::INT(4))
+            );
+            group.write_pending()?;
+            aligned.write_pending()?;
+
+            let first = match policy {
+                DuplicatePolicy::KeepLast => 2,
+                _ => 1,
+            };
+            assert_eq!(
+                (
+                    vec![1, 2],
+                    vec![IoTDBValue::LONG(first), IoTDBValue::LONG(3)]
+                ),
+                group.chunk_writer("s1").decode_current_page()?,
+                "{:?}",
+                policy
+            );
+            let time_chunk_writer = aligned.time_chunk_writer.as_mut().unwrap();
+            assert_eq!(
+                (vec![1, 2], vec![]),
+                time_chunk_writer.decode_current_page()?
+            );
+            let (_, values) = aligned.chunk_writer("s1").decode_current_page()?;
+            assert_eq!(
+                vec![IoTDBValue::INT(first as i32), IoTDBValue::INT(3)],
+                values,
+                "{:?}",
+                policy
+            );
+            // The value of the replaced row is the only one of s2
+            let (_, values) = aligned.chunk_writer("s2").decode_current_page()?;
+            let expected = match policy {
+                DuplicatePolicy::KeepLast => vec![IoTDBValue::DOUBLE(1.5)],
+                _ => vec![],
+            };
+            assert_eq!(expected, values, "{:?}", policy);
         }
         Ok(())
     }
+
+    #[test]
+    fn unsequence_duplicates() -> Result<(), TsFileError> {
+        for policy in [
+            DuplicatePolicy::Reject,
+            DuplicatePolicy::KeepFirst,
+            DuplicatePolicy::KeepLast,
+        ] {
+            let config = TsFileConfig::default()
+                .with_unsequence_buffer(u32::MAX)
+                .with_duplicate_policy(policy);
+            let mut group = GroupWriter::new("d1", &device(), &config, BTreeMap::new());
+            group.write("s1", 2, IoTDBValue::LONG(1))?;
+            group.write("s1", 1, IoTDBValue::LONG(2))?;
+            let duplicate = group.write("s1", 2, IoTDBValue::LONG(3));
+
+            let mut aligned = GroupWriter::new("d2", &aligned_device(), &config, BTreeMap::new());
+            aligned.write_many(1, vec![DataPoint::new("s1", IoTDBValue::INT(1))])?;
+            let duplicate_row = aligned.write_many(
+                1,
+                vec![
+                    DataPoint::new("s1", IoTDBValue::INT(2)),
+                    DataPoint::new("s2", IoTDBValue::DOUBLE(1.5)),
+                ],
+            );
+
+            let (expected, last, row) = match policy {
+                DuplicatePolicy::Reject => (
+                    Err(TsFileError::OutOfOrderData),
+                    1,
+                    vec![Some(IoTDBValue::INT(1)), None],
+                ),
+                // Nulls of the first row are filled
+                DuplicatePolicy::KeepFirst => (
+                    Ok(0),
+                    1,
+                    vec![Some(IoTDBValue::INT(1)), Some(IoTDBValue::DOUBLE(1.5))],
+                ),
+                DuplicatePolicy::KeepLast => (
+                    Ok(0),
+                    3,
+                    vec![Some(IoTDBValue::INT(2)), Some(IoTDBValue::DOUBLE(1.5))],
+                ),
+            };
+            assert_eq!(expected, duplicate, "{:?}", policy);
+            assert_eq!(expected, duplicate_row, "{:?}", policy);
+            let points = &group.unsequence_buffer.as_ref().unwrap().points["s1"];
+            assert_eq!(
+                &BTreeMap::from([(1, IoTDBValue::LONG(2)), (2, IoTDBValue::LONG(last))]),
+                points
+            );
+            let rows = &aligned.unsequence_buffer.as_ref().unwrap().rows;
+            assert_eq!(&BTreeMap::from([(1, row)]), rows);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn unsequence_buffer_kept_on_error() -> Result<(), TsFileError> {
+        let config = TsFileConfig::default().with_unsequence_buffer(u32::MAX);
+        let mut group = GroupWriter::new("d1", &device(), &config, BTreeMap::new());
+        group.write("s1", 1, IoTDBValue::LONG(1))?;
+        // Points of a series without chunk writer can not be written
+        group.unsequence_buffer.as_mut().unwrap().points.insert(
+            "s0".to_owned(),
+            BTreeMap::from([(1, IoTDBValue::LONG(1)), (2, IoTDBValue::LONG(2))]),
+        );
+
+        assert!(group.write_unsequence_buffer().is_err());
+
+        // The failed point is dropped, the ones after it stay buffered
+        let points = &group.unsequence_buffer.as_ref().unwrap().points;
+        assert_eq!(
+            Some(&BTreeMap::from([(2, IoTDBValue::LONG(2))])),
+            points.get("s0")
+        );
+        assert_eq!(
+            Some(&BTreeMap::from([(1, IoTDBValue::LONG(1))])),
+            points.get("s1")
+        );
+        Ok(())
+    }
 }
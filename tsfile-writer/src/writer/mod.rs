@@ -85,10 +85,10 @@ extern crate core;
 
 use std::cmp::Ordering;
 use std::collections::hash_map::IntoIter;
-use std::collections::{BTreeMap, HashMap};
+use std::collections::{BTreeMap, HashMap, VecDeque};
 use std::fmt::{Display, Formatter};
 use std::hash::Hash;
-use std::io::Write;
+use std::io::{Read, Write};
 use std::{io, vec};
 
 mod chunk_writer;
@@ -97,17 +97,19 @@ pub mod encoding;
 pub mod errors;
 mod group_writer;
 mod murmur128;
+pub mod restorable_tsfile_io_writer;
 pub mod schema;
-mod statistics;
+pub mod statistics;
 mod test;
 pub mod test_utils;
 pub mod ts_file_config;
 mod tsfile_io_writer;
 pub mod tsfile_writer;
-mod utils;
+pub(crate) mod utils;
 
-use crate::writer::chunk_writer::ChunkMetadata;
+pub use crate::writer::chunk_writer::{ChunkHeader, ChunkMetadata};
 use crate::writer::compression::CompressionType;
+use crate::writer::encoding::float::DEFAULT_MAX_POINT_NUMBER;
 use crate::writer::encoding::TSEncoding;
 use crate::writer::errors::TsFileError;
 
@@ -115,25 +117,61 @@ use crate::writer::murmur128::Murmur128;
 use crate::writer::schema::{DeviceBuilder, TsFileSchemaBuilder};
 use crate::writer::statistics::Statistics;
 use crate::writer::ts_file_config::TsFileConfig;
-use crate::writer::utils::{write_var_i32, write_var_u32};
+use crate::writer::utils::{
+    bytes_for_bits, read_byte, read_i64, read_var_i32, read_var_u32, write_var_i32, write_var_u32,
+};
 use crate::writer::MetadataIndexNodeType::LeafDevice;
 
 /// Central enum to pass Values to the writer
 #[allow(dead_code)]
-#[derive(Clone)]
+#[derive(Clone, Debug, PartialEq)]
 pub enum IoTDBValue {
+    BOOLEAN(bool),
+    TEXT(Vec<u8>),
     DOUBLE(f64),
     FLOAT(f32),
     INT(i32),
     LONG(i64),
 }
 
+impl IoTDBValue {
+    /// Data type of a series that stores this value
+    pub fn data_type(&self) -> TSDataType {
+        match self {
+            IoTDBValue::BOOLEAN(_) => TSDataType::BOOLEAN,
+            IoTDBValue::TEXT(_) => TSDataType::TEXT,
+            IoTDBValue::DOUBLE(_) => TSDataType::DOUBLE,
+            IoTDBValue::FLOAT(_) => TSDataType::FLOAT,
+            IoTDBValue::INT(_) => TSDataType::INT32,
+            IoTDBValue::LONG(_) => TSDataType::INT64,
+        }
+    }
+}
+
 impl From<i64> for IoTDBValue {
     fn from(x: i64) -> Self {
         IoTDBValue::LONG(x)
     }
 }
 
+impl From<bool> for IoTDBValue {
+    fn from(x: bool) -> Self {
+        IoTDBValue::BOOLEAN(x)
+    }
+}
+
+impl From<&str> for IoTDBValue {
+    fn from(x: &str) -> Self {
+        IoTDBValue::TEXT(x.as_bytes().to_vec())
+    }
+}
+
+impl From<String> for IoTDBValue {
+    fn from(x: String) -> Self {
+        IoTDBValue::TEXT(x.into_bytes())
+    }
+}
+
 /// Extension of the Write trait
 /// that allows to get the position of the "buffer"
 /// via the `get_position()` method
@@ -170,10 +208,12 @@ impl<T: Write> PositionedWrite for WriteWrapper<T> {
 
 impl<T: Write> WriteWrapper<T> {
     fn new(writer: T) -> WriteWrapper<T> {
-        WriteWrapper {
-            position: 0,
-            writer,
-        }
+        Self::at_position(writer, 0)
+    }
+
+    /// Wraps a writer that already contains `position` bytes
+    fn at_position(writer: T, position: u64) -> WriteWrapper<T> {
+        WriteWrapper { position, writer }
     }
 }
 
@@ -185,17 +225,26 @@ impl PositionedWrite for Vec<u8> {
 
 #[derive(Copy, Clone, Eq, PartialEq, Debug)]
 pub enum TSDataType {
+    BOOLEAN,
     INT32,
     INT64,
     FLOAT,
+    DOUBLE,
+    TEXT,
+    /// Type of the time column of aligned devices
+    VECTOR,
 }
 
 impl TSDataType {
     pub fn serialize(&self) -> u8 {
         match self {
+            TSDataType::BOOLEAN => 0,
             TSDataType::INT32 => 1,
             TSDataType::INT64 => 2,
             TSDataType::FLOAT => 3,
+            TSDataType::DOUBLE => 4,
+            TSDataType::TEXT => 5,
+            TSDataType::VECTOR => 6,
         }
     }
 }
@@ -205,9 +254,13 @@ impl TryFrom<u8> for TSDataType {
 
     fn try_from(value: u8) -> Result<Self, Self::Error> {
         match value {
+            0 => Ok(TSDataType::BOOLEAN),
             1 => Ok(TSDataType::INT32),
             2 => Ok(TSDataType::INT64),
             3 => Ok(TSDataType::FLOAT),
+            4 => Ok(TSDataType::DOUBLE),
+            5 => Ok(TSDataType::TEXT),
+            6 => Ok(TSDataType::VECTOR),
             _ => Err(()),
         }
     }
@@ -218,6 +271,8 @@ pub struct MeasurementSchema {
     pub data_type: TSDataType,
     pub encoding: TSEncoding,
     pub compression: CompressionType,
+    /// Decimal places kept by TS2DIFF for FLOAT and DOUBLE, like `max_point_number` of IoTDB
+    pub max_point_number: u32,
 }
 
 #[derive(Clone, PartialEq, Eq, Hash)]
@@ -244,8 +299,9 @@ impl PartialOrd<Self> for Path {
 }
 
 impl MeasurementSchema {
-    #[allow(dead_code)]
-    fn new(
+    /// A series with the default number of decimal places, e.g. for
+    /// `TsFileWriter::register_timeseries`
+    pub fn new(
         data_type: TSDataType,
         encoding: TSEncoding,
         compression: CompressionType,
@@ -254,33 +310,42 @@ impl MeasurementSchema {
             data_type,
             encoding,
             compression,
+            max_point_number: DEFAULT_MAX_POINT_NUMBER,
         }
     }
 }
 
 #[derive(Clone)]
-pub struct MeasurementGroup<'a> {
-    measurement_schemas: HashMap<&'a str, MeasurementSchema>,
+pub struct MeasurementGroup {
+    measurement_schemas: HashMap<String, MeasurementSchema>,
+    /// Aligned devices store their timestamps once in a time chunk shared by all value chunks
+    aligned: bool,
+    /// Compression of the time chunk of aligned devices
+    time_compression: CompressionType,
 }
 
-impl<'a> MeasurementGroup<'a> {
-    pub fn get_timeseries(&self) -> IntoIter<&'a str, MeasurementSchema> {
+impl MeasurementGroup {
+    pub fn get_timeseries(&self) -> IntoIter<String, MeasurementSchema> {
         self.measurement_schemas.clone().into_iter()
     }
+
+    pub fn is_aligned(&self) -> bool {
+        self.aligned
+    }
 }
 
 #[derive(Clone)]
-pub struct Schema<'a> {
-    measurement_groups: HashMap<&'a str, MeasurementGroup<'a>>,
+pub struct Schema {
+    measurement_groups: HashMap<String, MeasurementGroup>,
 }
 
-impl<'a> Schema<'a> {
-    pub fn get_devices(&self) -> IntoIter<&'a str, MeasurementGroup<'a>> {
+impl Schema {
+    pub fn get_devices(&self) -> IntoIter<String, MeasurementGroup> {
         self.measurement_groups.clone().into_iter()
     }
 }
 
-impl<'a> Display for Schema<'a> {
+impl Display for Schema {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         let mut devices = vec![];
         for device_id in self.measurement_groups.keys() {
@@ -290,14 +355,14 @@ impl<'a> Display for Schema<'a> {
     }
 }
 
-impl<'a> Schema<'a> {
+impl Schema {
     pub fn simple(
-        device_id: &'a str,
-        measurement_id: &'a str,
+        device_id: impl Into<String>,
+        measurement_id: impl Into<String>,
         data_type: TSDataType,
         encoding: TSEncoding,
         compression: CompressionType,
-    ) -> Schema<'a> {
+    ) -> Schema {
         TsFileSchemaBuilder::new()
             .add(
                 device_id,
@@ -309,7 +374,7 @@ impl<'a> Schema<'a> {
     }
 }
 
-struct ChunkGroupMetadata {
+pub(crate) struct ChunkGroupMetadata {
     device_id: String,
     chunk_metadata: Vec<ChunkMetadata>,
 }
@@ -323,11 +388,26 @@ impl ChunkGroupMetadata {
     }
 }
 
-#[derive(Clone)]
-enum MetadataIndexNodeType {
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub enum MetadataIndexNodeType {
     LeafMeasurement,
     InternalMeasurement,
     LeafDevice,
+    InternalDevice,
+}
+
+impl TryFrom<u8> for MetadataIndexNodeType {
+    type Error = ();
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            0x00 => Ok(MetadataIndexNodeType::InternalDevice),
+            0x01 => Ok(LeafDevice),
+            0x02 => Ok(MetadataIndexNodeType::InternalMeasurement),
+            0x03 => Ok(MetadataIndexNodeType::LeafMeasurement),
+            _ => Err(()),
+        }
+    }
 }
 
 impl Serializable for MetadataIndexNodeType {
@@ -347,6 +427,7 @@ impl Serializable for MetadataIndexNodeType {
             MetadataIndexNodeType::LeafMeasurement => 0x03,
             MetadataIndexNodeType::InternalMeasurement => 0x02,
             LeafDevice => 0x01,
+            MetadataIndexNodeType::InternalDevice => 0x00,
         };
         file.write(&[byte]);
 
@@ -354,14 +435,29 @@ impl Serializable for MetadataIndexNodeType {
     }
 }
 
-struct TimeseriesMetadata {}
-
 #[derive(Clone)]
-struct MetadataIndexEntry {
+pub struct MetadataIndexEntry {
     name: String,
     offset: usize,
 }
 
+impl MetadataIndexEntry {
+    pub fn name(&self) -> &str {
+        self.name.as_str()
+    }
+
+    pub fn offset(&self) -> u64 {
+        self.offset as u64
+    }
+
+    fn deserialize(reader: &mut dyn Read) -> Result<MetadataIndexEntry, TsFileError> {
+        Ok(MetadataIndexEntry {
+            name: read_str(reader)?,
+            offset: read_i64(reader)? as usize,
+        })
+    }
+}
+
 impl Serializable for MetadataIndexEntry {
     fn serialize(&self, file: &mut dyn PositionedWrite) -> Result<(), TsFileError> {
         // int byteLen = 0;
@@ -377,7 +473,7 @@ impl Serializable for MetadataIndexEntry {
 }
 
 #[derive(Clone)]
-struct MetadataIndexNode {
+pub struct MetadataIndexNode {
     children: Vec<MetadataIndexEntry>,
     end_offset: usize,
     node_type: MetadataIndexNodeType,
@@ -415,115 +511,112 @@ impl MetadataIndexNode {
         }
     }
 
+    pub fn children(&self) -> &[MetadataIndexEntry] {
+        self.children.as_slice()
+    }
+
+    /// Offset where the data of the last child ends
+    pub fn end_offset(&self) -> u64 {
+        self.end_offset as u64
+    }
+
+    pub fn node_type(&self) -> MetadataIndexNodeType {
+        self.node_type
+    }
+
+    pub(crate) fn deserialize(reader: &mut dyn Read) -> Result<MetadataIndexNode, TsFileError> {
+        let size = read_var_u32(reader)?;
+        let mut children = Vec::with_capacity(size as usize);
+        for _ in 0..size {
+            children.push(MetadataIndexEntry::deserialize(reader)?);
+        }
+        let end_offset = read_i64(reader)? as usize;
+        let node_type = read_byte(reader)?
+            .try_into()
+            .map_err(|_| TsFileError::ReadError {
+                source: Some("Unknown metadata index node type".to_owned()),
+            })?;
+        Ok(MetadataIndexNode {
+            children,
+            end_offset,
+            node_type,
+        })
+    }
+
     fn add_current_index_node_to_queue(
         current_index_node: &mut MetadataIndexNode,
-        measurement_metadata_index_queue: &mut Vec<MetadataIndexNode>,
+        metadata_index_node_queue: &mut VecDeque<MetadataIndexNode>,
         file: &mut dyn PositionedWrite,
     ) {
-        // TODO file.getPosition
         // currentIndexNode.setEndOffset(out.getPosition());
         current_index_node.end_offset = file.get_position() as usize;
         // metadataIndexNodeQueue.add(currentIndexNode);
-        measurement_metadata_index_queue.push(current_index_node.clone());
+        metadata_index_node_queue.push_back(current_index_node.clone());
     }
 
-    #[allow(unused_variables)]
+    /// Serializes the nodes of the queue level by level, until only the root is left
     fn generate_root_node(
-        mut measurement_metadata_index_queue: Vec<MetadataIndexNode>,
+        mut metadata_index_node_queue: VecDeque<MetadataIndexNode>,
         file: &mut dyn PositionedWrite,
         node_type: MetadataIndexNodeType,
         config: &TsFileConfig,
     ) -> MetadataIndexNode {
-        // int queueSize = metadataIndexNodeQueue.size();
-        // MetadataIndexNode metadataIndexNode;
-        // MetadataIndexNode currentIndexNode = new MetadataIndexNode(type);
-        // while (queueSize != 1) {
-        //   for (int i = 0; i < queueSize; i++) {
-        //     metadataIndexNode = metadataIndexNodeQueue.poll();
-        //     // when constructing from internal node, each node is related to an entry
-        //     if (currentIndexNode.isFull()) {
-        //       addCurrentIndexNodeToQueue(currentIndexNode, metadataIndexNodeQueue, out);
-        //       currentIndexNode = new MetadataIndexNode(type);
-        //     }
-        //     currentIndexNode.addEntry(
-        //         new MetadataIndexEntry(metadataIndexNode.peek().getName(), out.getPosition()));
-        //     metadataIndexNode.serializeTo(out.wrapAsStream());
-        //   }
-        //   addCurrentIndexNodeToQueue(currentIndexNode, metadataIndexNodeQueue, out);
-        //   currentIndexNode = new MetadataIndexNode(type);
-        //   queueSize = metadataIndexNodeQueue.size();
-        // }
-        // return metadataIndexNodeQueue.poll();
-        // TODO
-        let mut queue_size = measurement_metadata_index_queue.len();
-        let mut metadata_index_node;
-        let mut current_index_metadata = MetadataIndexNode::new(node_type.clone());
-
-        while queue_size != 1 {
-            for i in 0..queue_size {
-                metadata_index_node = measurement_metadata_index_queue.last().unwrap().clone();
-                let device = match metadata_index_node.children.get(0) {
-                    None => {
-                        panic!("...")
-                    }
-                    Some(inner) => inner.name.clone(),
-                };
-                measurement_metadata_index_queue.remove(measurement_metadata_index_queue.len() - 1);
-                if current_index_metadata.is_full(config) {
-                    current_index_metadata.end_offset = file.get_position() as usize;
-                    measurement_metadata_index_queue.push(current_index_metadata.clone());
+        let mut current_index_node = MetadataIndexNode::new(node_type);
+        while metadata_index_node_queue.len() != 1 {
+            for _ in 0..metadata_index_node_queue.len() {
+                let metadata_index_node = metadata_index_node_queue
+                    .pop_front()
+                    .expect("The queue is not empty");
+                // when constructing from internal node, each node is related to an entry
+                if current_index_node.is_full(config) {
+                    Self::add_current_index_node_to_queue(
+                        &mut current_index_node,
+                        &mut metadata_index_node_queue,
+                        file,
+                    );
+                    current_index_node = MetadataIndexNode::new(node_type);
                 }
-                // ...
-                let name = match metadata_index_node.children.get(0) {
-                    None => {
-                        panic!("This should not happen!")
-                    }
-                    Some(node) => node.name.clone(),
+                let name = match metadata_index_node.children.first() {
+                    Some(entry) => entry.name.clone(),
+                    None => panic!("Index nodes always have children"),
                 };
-                current_index_metadata.children.push(MetadataIndexEntry {
+                current_index_node.children.push(MetadataIndexEntry {
                     name,
                     offset: file.get_position() as usize,
                 });
+                metadata_index_node.serialize(file);
             }
-            // ...
             Self::add_current_index_node_to_queue(
-                &mut current_index_metadata,
-                &mut measurement_metadata_index_queue,
+                &mut current_index_node,
+                &mut metadata_index_node_queue,
                 file,
             );
-            current_index_metadata = MetadataIndexNode {
-                children: vec![],
-                end_offset: 0,
-                node_type: node_type.clone(),
-            };
-            queue_size = measurement_metadata_index_queue.len();
+            current_index_node = MetadataIndexNode::new(node_type);
         }
-        return measurement_metadata_index_queue.get(0).unwrap().clone();
+        metadata_index_node_queue
+            .pop_front()
+            .expect("The queue contains the root")
     }
 
-    #[allow(unused_variables)]
     fn construct_metadata_index(
         device_timeseries_metadata_map: &BTreeMap<String, Vec<Box<dyn TimeSeriesMetadatable>>>,
         file: &mut dyn PositionedWrite,
         config: &TsFileConfig,
     ) -> MetadataIndexNode {
-        let mut device_metadata_index_map: HashMap<String, MetadataIndexNode> = HashMap::new();
+        // Devices have to be sorted as readers search the index binary
+        let mut device_metadata_index_map: BTreeMap<String, MetadataIndexNode> = BTreeMap::new();
 
         for (device, list_metadata) in device_timeseries_metadata_map.iter() {
             if list_metadata.is_empty() {
                 continue;
             }
 
-            let mut measurement_metadata_index_queue: Vec<MetadataIndexNode> = vec![];
+            let mut measurement_metadata_index_queue: VecDeque<MetadataIndexNode> = VecDeque::new();
 
-            let timeseries_metadata: TimeseriesMetadata;
             let mut current_index_node: MetadataIndexNode =
                 MetadataIndexNode::new(MetadataIndexNodeType::LeafMeasurement);
 
-            // for (int i = 0; i < entry.getValue().size(); i++) {
-            for i in 0..list_metadata.len() {
-                let timeseries_metadata = list_metadata.get(i).unwrap();
-
+            for (i, timeseries_metadata) in list_metadata.iter().enumerate() {
                 if i % config.max_degree_of_index_node == 0 {
                     if current_index_node.is_full(config) {
                         Self::add_current_index_node_to_queue(
@@ -541,13 +634,11 @@ impl MetadataIndexNode {
                 }
                 timeseries_metadata.serialize(file);
             }
-            // addCurrentIndexNodeToQueue(currentIndexNode, measurementMetadataIndexQueue, out);
-            // deviceMetadataIndexMap.put(
-            //       entry.getKey(),
-            //       generateRootNode(
-            //           measurementMetadataIndexQueue, out, MetadataIndexNodeType.INTERNAL_MEASUREMENT));
-            current_index_node.end_offset = file.get_position() as usize;
-            measurement_metadata_index_queue.push(current_index_node.clone());
+            Self::add_current_index_node_to_queue(
+                &mut current_index_node,
+                &mut measurement_metadata_index_queue,
+                file,
+            );
 
             let root_node = Self::generate_root_node(
                 measurement_metadata_index_queue,
@@ -558,6 +649,8 @@ impl MetadataIndexNode {
             device_metadata_index_map.insert(device.clone(), root_node);
         }
 
+        // if not exceed the max child nodes num, ignore the device index and directly point to the
+        // measurement
         if device_metadata_index_map.len() <= config.max_degree_of_index_node {
             let mut metadata_index_node = MetadataIndexNode::new(LeafDevice);
 
@@ -572,46 +665,46 @@ impl MetadataIndexNode {
             return metadata_index_node;
         }
 
-        panic!("This is not yet implemented!");
-
-        // // if not exceed the max child nodes num, ignore the device index and directly point to the
-        // // measurement
-        // if (deviceMetadataIndexMap.size() <= config.getMaxDegreeOfIndexNode()) {
-        //   MetadataIndexNode metadataIndexNode =
-        //       new MetadataIndexNode(MetadataIndexNodeType.LEAF_DEVICE);
-        //   for (Map.Entry<String, MetadataIndexNode> entry : deviceMetadataIndexMap.entrySet()) {
-        //     metadataIndexNode.addEntry(new MetadataIndexEntry(entry.getKey(), out.getPosition()));
-        //     entry.getValue().serializeTo(out.wrapAsStream());
-        //   }
-        //   metadataIndexNode.setEndOffset(out.getPosition());
-        //   return metadataIndexNode;
-        // }
-        //
-        // // else, build level index for devices
-        // Queue<MetadataIndexNode> deviceMetadataIndexQueue = new ArrayDeque<>();
-        // MetadataIndexNode currentIndexNode = new MetadataIndexNode(MetadataIndexNodeType.LEAF_DEVICE);
-        //
-        // for (Map.Entry<String, MetadataIndexNode> entry : deviceMetadataIndexMap.entrySet()) {
-        //   // when constructing from internal node, each node is related to an entry
-        //   if (currentIndexNode.isFull()) {
-        //     addCurrentIndexNodeToQueue(currentIndexNode, deviceMetadataIndexQueue, out);
-        //     currentIndexNode = new MetadataIndexNode(MetadataIndexNodeType.LEAF_DEVICE);
-        //   }
-        //   currentIndexNode.addEntry(new MetadataIndexEntry(entry.getKey(), out.getPosition()));
-        //   entry.getValue().serializeTo(out.wrapAsStream());
-        // }
-        // addCurrentIndexNodeToQueue(currentIndexNode, deviceMetadataIndexQueue, out);
-        // MetadataIndexNode deviceMetadataIndexNode =
-        //     generateRootNode(deviceMetadataIndexQueue, out, MetadataIndexNodeType.INTERNAL_DEVICE);
-        // deviceMetadataIndexNode.setEndOffset(out.getPosition());
-        // return deviceMetadataIndexNode;
+        // else, build level index for devices
+        let mut device_metadata_index_queue: VecDeque<MetadataIndexNode> = VecDeque::new();
+        let mut current_index_node = MetadataIndexNode::new(LeafDevice);
+
+        for (device, value) in device_metadata_index_map {
+            // when constructing from internal node, each node is related to an entry
+            if current_index_node.is_full(config) {
+                Self::add_current_index_node_to_queue(
+                    &mut current_index_node,
+                    &mut device_metadata_index_queue,
+                    file,
+                );
+                current_index_node = MetadataIndexNode::new(LeafDevice);
+            }
+            current_index_node.children.push(MetadataIndexEntry {
+                name: device,
+                offset: file.get_position() as usize,
+            });
+            value.serialize(file);
+        }
+        Self::add_current_index_node_to_queue(
+            &mut current_index_node,
+            &mut device_metadata_index_queue,
+            file,
+        );
+        let mut device_metadata_index_node = Self::generate_root_node(
+            device_metadata_index_queue,
+            file,
+            MetadataIndexNodeType::InternalDevice,
+            config,
+        );
+        device_metadata_index_node.end_offset = file.get_position() as usize;
+        device_metadata_index_node
     }
     fn is_full(&self, config: &TsFileConfig) -> bool {
         self.children.len() >= config.max_degree_of_index_node
     }
 }
 
-trait TimeSeriesMetadatable {
+trait TimeSeriesMetadatable: Send {
     fn get_measurement_id(&self) -> String;
     fn serialize(&self, file: &mut dyn PositionedWrite) -> io::Result<()>;
 }
@@ -632,7 +725,7 @@ impl TimeSeriesMetadatable for TimeSeriesMetadata {
     }
 }
 
-struct TimeSeriesMetadata {
+pub struct TimeSeriesMetadata {
     time_series_metadata_type: u8,
     chunk_meta_data_list_data_size: usize,
     measurement_id: String,
@@ -640,6 +733,67 @@ struct TimeSeriesMetadata {
     statistics: Statistics,
     buffer: Vec<u8>,
 }
+
+impl TimeSeriesMetadata {
+    pub fn measurement_id(&self) -> &str {
+        self.measurement_id.as_str()
+    }
+
+    pub fn data_type(&self) -> TSDataType {
+        self.data_type
+    }
+
+    pub fn statistics(&self) -> &Statistics {
+        &self.statistics
+    }
+
+    /// Mask of the series (0x80 for the time column, 0x40 for value columns of aligned devices)
+    pub fn mask(&self) -> u8 {
+        self.time_series_metadata_type & 0xC0
+    }
+
+    pub(crate) fn deserialize(reader: &mut dyn Read) -> Result<TimeSeriesMetadata, TsFileError> {
+        let time_series_metadata_type = read_byte(reader)?;
+        let measurement_id = read_str(reader)?;
+        let data_type = read_data_type(reader)?;
+        let chunk_meta_data_list_data_size = read_var_u32(reader)? as usize;
+        let statistics = Statistics::deserialize(data_type, reader)?;
+        let mut buffer = vec![0_u8; chunk_meta_data_list_data_size];
+        reader.read_exact(&mut buffer)?;
+        Ok(TimeSeriesMetadata {
+            time_series_metadata_type,
+            chunk_meta_data_list_data_size,
+            measurement_id,
+            data_type,
+            statistics,
+            buffer,
+        })
+    }
+
+    /// Parses the list of chunk metadata that belongs to this series.
+    /// If the series consists of only one chunk, its statistics are the ones of the series.
+    pub fn chunk_metadata_list(&self) -> Result<Vec<ChunkMetadata>, TsFileError> {
+        let serialize_statistics = (self.time_series_metadata_type & 0x3F) == 1;
+        let mut reader = self.buffer.as_slice();
+        let mut result = vec![];
+        while !reader.is_empty() {
+            let offset = read_i64(&mut reader)?;
+            let statistics = if serialize_statistics {
+                Statistics::deserialize(self.data_type, &mut reader)?
+            } else {
+                self.statistics.clone()
+            };
+            result.push(ChunkMetadata::new(
+                self.measurement_id.clone(),
+                self.data_type,
+                offset as u64,
+                statistics,
+                self.mask(),
+            ));
+        }
+        Ok(result)
+    }
+}
 //
 // impl<T> Serializable for TimeSeriesMetadata<T> {
 //     fn serialize(&self, file: &mut dyn PositionedWrite) -> io::Result<()> {
@@ -669,11 +823,11 @@ impl HashFunction {
 
     fn hash(&self, value: &str) -> usize {
         // return Math.abs(Murmur128Hash.hash(value, seed)) % cap;
-        (self._murmur_hash(value, self.seed).abs() % self.cap) as usize
+        (self._murmur_hash(value, self.seed).wrapping_abs() % self.cap) as usize
     }
 }
 
-struct BloomFilter {
+pub struct BloomFilter {
     size: i32,
     hash_function_size: i32,
     func: Vec<HashFunction>,
@@ -689,6 +843,12 @@ impl BloomFilter {
         }
     }
 
+    /// Checks if the path (`device.measurement`) may be contained in the file.
+    /// False positives are possible (with the configured error rate), false negatives are not.
+    pub fn might_contain(&self, path: &str) -> bool {
+        self.func.iter().all(|f| self.bit_set[f.hash(path)])
+    }
+
     fn new(size: i32, hash_function_size: i32, config: &TsFileConfig) -> BloomFilter {
         let mut func = vec![];
 
@@ -734,12 +894,47 @@ impl BloomFilter {
         )
     }
 
+    pub(crate) fn deserialize(
+        reader: &mut dyn Read,
+        config: &TsFileConfig,
+    ) -> Result<BloomFilter, TsFileError> {
+        let number_of_bytes = read_var_u32(reader)? as usize;
+        let mut bytes = vec![0_u8; number_of_bytes];
+        reader.read_exact(&mut bytes)?;
+        let size = read_var_u32(reader)? as i32;
+        let hash_function_size = read_var_u32(reader)? as i32;
+        if size <= 0 || hash_function_size as usize > config.seeds.len() {
+            return Err(TsFileError::ReadError {
+                source: Some(format!(
+                    "Invalid bloom filter with size {} and {} hash functions",
+                    size, hash_function_size
+                )),
+            });
+        }
+
+        let mut filter = BloomFilter::new(size, hash_function_size, config);
+        for (byte_index, byte) in bytes.iter().enumerate() {
+            for bit_index in 0..8 {
+                let i = byte_index * 8 + bit_index;
+                if i < filter.bit_set.len() && (byte >> bit_index) & 0x01 == 0x01 {
+                    filter.bit_set[i] = true;
+                }
+            }
+        }
+        Ok(filter)
+    }
+
+    /// Number of bits of the filter
+    pub fn size(&self) -> i32 {
+        self.size
+    }
+
+    pub fn hash_function_size(&self) -> i32 {
+        self.hash_function_size
+    }
+
     fn serialize_bits(&self) -> Vec<u8> {
-        let number_of_bytes = if self.bit_set.len() % 8 == 0 {
-            self.bit_set.len() / 8
-        } else {
-            (self.bit_set.len() + (self.bit_set.len() % 8)) / 8
-        };
+        let number_of_bytes = bytes_for_bits(self.bit_set.len());
 
         let mut result = vec![0_u8; number_of_bytes];
 
@@ -776,18 +971,19 @@ impl Serializable for BloomFilter {
         // Real
         let bytes = self.serialize_bits();
 
-        write_var_u32(bytes.len() as u32, file);
-        file.write_all(bytes.as_slice());
-        write_var_u32(self.size as u32, file);
-        write_var_u32(self.hash_function_size as u32, file);
+        write_var_u32(bytes.len() as u32, file)?;
+        file.write_all(bytes.as_slice())?;
+        write_var_u32(self.size as u32, file)?;
+        write_var_u32(self.hash_function_size as u32, file)?;
 
         Ok(())
     }
 }
 
-struct TsFileMetadata {
+pub struct TsFileMetadata {
     metadata_index: Option<MetadataIndexNode>,
     meta_offset: u64,
+    bloom_filter: Option<BloomFilter>,
 }
 
 impl TsFileMetadata {
@@ -795,8 +991,43 @@ impl TsFileMetadata {
         TsFileMetadata {
             metadata_index,
             meta_offset,
+            bloom_filter: None,
         }
     }
+
+    /// Root of the metadata index tree
+    pub fn metadata_index(&self) -> Option<&MetadataIndexNode> {
+        self.metadata_index.as_ref()
+    }
+
+    /// Offset of the separator marker that starts the index area of the file
+    pub fn meta_offset(&self) -> u64 {
+        self.meta_offset
+    }
+
+    pub fn bloom_filter(&self) -> Option<&BloomFilter> {
+        self.bloom_filter.as_ref()
+    }
+
+    /// Deserializes the footer, i.e. everything between the index area and the
+    /// footer size at the end of the file
+    pub(crate) fn deserialize(
+        buffer: &[u8],
+        config: &TsFileConfig,
+    ) -> Result<TsFileMetadata, TsFileError> {
+        let mut reader = buffer;
+        let metadata_index = MetadataIndexNode::deserialize(&mut reader)?;
+        let meta_offset = read_i64(&mut reader)? as u64;
+        let bloom_filter = match reader.is_empty() {
+            true => None,
+            false => Some(BloomFilter::deserialize(&mut reader, config)?),
+        };
+        Ok(TsFileMetadata {
+            metadata_index: Some(metadata_index),
+            meta_offset,
+            bloom_filter,
+        })
+    }
 }
 
 impl Serializable for TsFileMetadata {
@@ -857,6 +1088,27 @@ fn write_str(file: &mut dyn PositionedWrite, s: &str) -> Result<(), TsFileError>
     Ok(())
 }
 
+pub(crate) fn read_str(reader: &mut dyn Read) -> Result<String, TsFileError> {
+    let len = read_var_i32(reader)?;
+    if len < 0 {
+        return Err(TsFileError::ReadError {
+            source: Some(format!("Negative string length {}", len)),
+        });
+    }
+    let mut bytes = vec![0_u8; len as usize];
+    reader.read_exact(&mut bytes)?;
+    String::from_utf8(bytes).map_err(|_| TsFileError::ReadError {
+        source: Some("String is not valid UTF-8".to_owned()),
+    })
+}
+
+pub(crate) fn read_data_type(reader: &mut dyn Read) -> Result<TSDataType, TsFileError> {
+    let byte = read_byte(reader)?;
+    byte.try_into().map_err(|_| TsFileError::ReadError {
+        source: Some(format!("Unknown data type {}", byte)),
+    })
+}
+
 #[cfg(test)]
 mod tests {
     use std::collections::HashMap;
@@ -865,12 +1117,16 @@ mod tests {
     use crate::writer::compression::CompressionType;
     use crate::writer::encoding::TSEncoding;
     use crate::writer::schema::{DeviceBuilder, TsFileSchemaBuilder};
+    use crate::writer::ts_file_config::TsFileConfigBuilder;
     use crate::writer::tsfile_writer::TsFileWriter;
     use crate::writer::utils::{read_var_u32, write_var_u32};
     use crate::writer::{
-        IoTDBValue, MeasurementGroup, MeasurementSchema, Schema, TSDataType, TsFileError,
-        WriteWrapper,
+        BloomFilter, IoTDBValue, MeasurementGroup, MeasurementSchema, MetadataIndexNode,
+        MetadataIndexNodeType, PositionedWrite, Schema, Serializable, TSDataType,
+        TimeSeriesMetadatable, TsFileError, WriteWrapper,
     };
+    use std::collections::BTreeMap;
+    use std::io;
 
     #[test]
     fn it_works() {
@@ -900,13 +1156,15 @@ mod tests {
         );
 
         let mut measurement_schema_map = HashMap::new();
-        measurement_schema_map.insert("s1", measurement_schema);
+        measurement_schema_map.insert("s1".to_owned(), measurement_schema);
         let measurement_group = MeasurementGroup {
             measurement_schemas: measurement_schema_map,
+            aligned: false,
+            time_compression: CompressionType::UNCOMPRESSED,
         };
         let mut measurement_groups_map = HashMap::new();
         let d1 = "d1";
-        measurement_groups_map.insert(d1, measurement_group);
+        measurement_groups_map.insert(d1.to_owned(), measurement_group);
         let schema = Schema {
             measurement_groups: measurement_groups_map,
         };
@@ -920,7 +1178,7 @@ mod tests {
         TsFileWriter::write(&mut writer, "d1", "s1", 10, IoTDBValue::INT(14));
         TsFileWriter::write(&mut writer, "d1", "s1", 100, IoTDBValue::INT(15));
 
-        writer.close();
+        writer.close().unwrap();
 
         let buffer_writer = writer.file_io_writer.out;
 
@@ -964,7 +1222,7 @@ mod tests {
             writer.write(device, "s2", i, IoTDBValue::INT(i as i32));
         }
 
-        writer.close();
+        writer.close().unwrap();
     }
 
     #[test]
@@ -995,7 +1253,7 @@ mod tests {
             }
         }
 
-        writer.close();
+        writer.close().unwrap();
     }
 
     #[test]
@@ -1026,13 +1284,42 @@ mod tests {
             }
         }
 
-        writer.close();
+        writer.close().unwrap();
+    }
+
+    #[test]
+    fn write_double() -> Result<(), TsFileError> {
+        let schema = Schema::simple(
+            "d1",
+            "s1",
+            TSDataType::DOUBLE,
+            TSEncoding::PLAIN,
+            CompressionType::UNCOMPRESSED,
+        );
+        let mut writer = TsFileWriter::new_from_writer(schema, Vec::new(), Default::default())?;
+
+        writer.write("d1", "s1", 1, IoTDBValue::DOUBLE(std::f64::consts::PI))?;
+        writer.write("d1", "s1", 2, IoTDBValue::DOUBLE(-1.0e300))?;
+        assert_eq!(
+            Err(TsFileError::WrongTypeForSeries),
+            writer.write("d1", "s1", 3, IoTDBValue::FLOAT(1.0))
+        );
+        writer.close()?;
+
+        let out = writer.file_io_writer.out;
+        // Chunk header of s1 with data size 43 and data type DOUBLE (4)
+        assert_eq!(
+            [0x05, 0x04, 0x73, 0x31, 0x2B, 0x04, 0x00, 0x00],
+            out[11..19]
+        );
+
+        Ok(())
     }
 
     #[test]
     fn read_var_int() {
         for number in [
-            1, 12, 123, 1234, 12345, 123456, 1234567, 12345678, 123456789,
+            1, 12, 123, 255, 1234, 12345, 16383, 123456, 1234567, 12345678, 123456789,
         ] {
             let mut result: Vec<u8> = vec![];
 
@@ -1045,6 +1332,20 @@ mod tests {
         }
     }
 
+    #[test]
+    fn read_var_int_corrupt() {
+        let mut buffer: Vec<u8> = vec![];
+        write_var_u32(u32::MAX, &mut buffer).unwrap();
+        assert_eq!(Ok(u32::MAX), read_var_u32(&mut buffer.as_slice()));
+
+        // A sixth byte would shift beyond the 32 bits
+        let buffer = [0xFF_u8, 0xFF, 0xFF, 0xFF, 0xFF, 0x01];
+        assert!(matches!(
+            read_var_u32(&mut &buffer[..]),
+            Err(TsFileError::ReadError { .. })
+        ));
+    }
+
     #[test]
     fn write_var_int() {
         let number: u32 = 123456789;
@@ -1172,7 +1473,7 @@ mod tests {
         writer.write("d1", "s2", 1, IoTDBValue::LONG(14));
         writer.write("d1", "s3", 1, IoTDBValue::FLOAT(15.0));
 
-        writer.close();
+        writer.close().unwrap();
 
         // assert_eq!(buffer_writer.writer, expected);
         assert_eq!(writer.file_io_writer.out.position, expected.len() as u64);
@@ -1219,7 +1520,7 @@ mod tests {
 
         writer.write("d1", "s", 1, IoTDBValue::INT(13));
 
-        writer.close();
+        writer.close().unwrap();
 
         assert_eq!(writer.file_io_writer.out.writer, expected);
     }
@@ -1267,7 +1568,7 @@ mod tests {
 
         writer.write("d1", "s", 1, IoTDBValue::LONG(13));
 
-        writer.close();
+        writer.close().unwrap();
 
         assert_eq!(writer.file_io_writer.out.writer, expected);
     }
@@ -1314,7 +1615,7 @@ mod tests {
 
         writer.write("d1", "s", 1, IoTDBValue::FLOAT(13.0));
 
-        writer.close();
+        writer.close().unwrap();
 
         assert_eq!(writer.file_io_writer.out.writer, expected);
     }
@@ -1513,7 +1814,7 @@ mod tests {
             writer.write("d1", "s", i, IoTDBValue::INT(i as i32));
         }
 
-        writer.close();
+        writer.close().unwrap();
 
         assert_eq!(writer.file_io_writer.out.writer, expected);
     }
@@ -1541,7 +1842,7 @@ mod tests {
             writer.write("d1", "s", i, IoTDBValue::LONG(2 * i));
         }
 
-        writer.close();
+        writer.close().unwrap();
     }
 
     #[test]
@@ -1603,7 +1904,7 @@ mod tests {
         let mut writer = TsFileWriter::new_from_writer(schema, buffer, Default::default()).unwrap();
 
         writer.write("d1", "s1", 1, IoTDBValue::LONG(13));
-        writer.close();
+        writer.close().unwrap();
 
         assert_eq!(expected, writer.file_io_writer.out.as_slice());
     }
@@ -1650,7 +1951,7 @@ mod tests {
         for i in 0..10 {
             writer.write("d1", "s1", i, IoTDBValue::LONG(i))?;
         }
-        writer.close();
+        writer.close()?;
 
         assert_eq!(expected, writer.file_io_writer.out.as_slice());
 
@@ -1698,7 +1999,7 @@ mod tests {
         for i in 0..10 {
             writer.write("d1", "s1", i, IoTDBValue::INT(i as i32))?;
         }
-        writer.close();
+        writer.close()?;
 
         assert_eq!(expected, writer.file_io_writer.out.as_slice());
 
@@ -1706,7 +2007,6 @@ mod tests {
     }
 
     #[test]
-    #[ignore = "Is luck dependent due to HashMap"]
     fn write_snappy_complex() {
         let expected = [
             0x54, 0x73, 0x46, 0x69, 0x6C, 0x65, 0x03, 0x00, 0x04, 0x64, 0x31, 0x05, 0x04, 0x73,
@@ -1779,7 +2079,7 @@ mod tests {
         writer.write("d1", "s1", 1, IoTDBValue::LONG(13));
         writer.write("d1", "s2", 1, IoTDBValue::FLOAT(14.0));
         writer.write("d2", "s1", 1, IoTDBValue::INT(15));
-        writer.close();
+        writer.close().unwrap();
 
         assert_eq!(expected, writer.file_io_writer.out.as_slice());
     }
@@ -1798,7 +2098,193 @@ mod tests {
 
         writer.write("d1", "s1", 1, IoTDBValue::LONG(1))?;
 
-        writer.close();
+        writer.close()?;
+
+        Ok(())
+    }
+
+    /// Timeseries metadata that only consists of the measurement id
+    struct TestTimeseriesMetadata(String);
+
+    impl TimeSeriesMetadatable for TestTimeseriesMetadata {
+        fn get_measurement_id(&self) -> String {
+            self.0.clone()
+        }
+
+        fn serialize(&self, file: &mut dyn PositionedWrite) -> io::Result<()> {
+            file.write_all(self.0.as_bytes())
+        }
+    }
+
+    /// Builds the metadata index of the devices with the same measurements each
+    fn construct_metadata_index(
+        devices: &[String],
+        measurements: usize,
+        max_degree_of_index_node: usize,
+    ) -> Result<(MetadataIndexNode, Vec<u8>), TsFileError> {
+        let config = TsFileConfigBuilder::new()
+            .max_degree_of_index_node(max_degree_of_index_node)
+            .build()?;
+        let mut map: BTreeMap<String, Vec<Box<dyn TimeSeriesMetadatable>>> = BTreeMap::new();
+        for device in devices {
+            let timeseries_metadata = (0..measurements)
+                .map(|i| {
+                    Box::new(TestTimeseriesMetadata(format!("s{:02}", i)))
+                        as Box<dyn TimeSeriesMetadatable>
+                })
+                .collect();
+            map.insert(device.clone(), timeseries_metadata);
+        }
+        let mut buffer = vec![];
+        let root = MetadataIndexNode::construct_metadata_index(&map, &mut buffer, &config);
+        Ok((root, buffer))
+    }
+
+    /// Nodes of the index level by level, down to the leaf nodes of the node type
+    fn index_levels(
+        root: &MetadataIndexNode,
+        buffer: &[u8],
+    ) -> Result<Vec<Vec<MetadataIndexNode>>, TsFileError> {
+        let mut levels = vec![vec![root.clone()]];
+        while let Some(nodes) = levels.last() {
+            if !matches!(
+                nodes[0].node_type(),
+                MetadataIndexNodeType::InternalDevice | MetadataIndexNodeType::InternalMeasurement
+            ) {
+                break;
+            }
+            let mut children = vec![];
+            for node in nodes {
+                children.append(&mut child_nodes(node, buffer)?);
+            }
+            levels.push(children);
+        }
+        Ok(levels)
+    }
+
+    /// Deserializes the nodes the children of an internal or a LeafDevice node point to
+    fn child_nodes(
+        node: &MetadataIndexNode,
+        buffer: &[u8],
+    ) -> Result<Vec<MetadataIndexNode>, TsFileError> {
+        let children = node.children();
+        let mut result = vec![];
+        for (i, child) in children.iter().enumerate() {
+            let end = match children.get(i + 1) {
+                Some(next) => next.offset(),
+                None => node.end_offset(),
+            };
+            let mut data = &buffer[child.offset() as usize..end as usize];
+            result.push(MetadataIndexNode::deserialize(&mut data)?);
+        }
+        Ok(result)
+    }
+
+    #[test]
+    fn metadata_index_many_devices() -> Result<(), TsFileError> {
+        let devices: Vec<String> = (0..3000).map(|i| format!("root.sg.d{}", i)).collect();
+        let (root, buffer) = construct_metadata_index(&devices, 1, 256)?;
+
+        let levels = index_levels(&root, &buffer)?;
+        assert_eq!(2, levels.len());
+        assert_eq!(MetadataIndexNodeType::InternalDevice, root.node_type());
+        // 3000 devices need 12 full leaf nodes of 256 devices, except the last one
+        let leaves = &levels[1];
+        assert_eq!(12, leaves.len());
+        for (i, leaf) in leaves.iter().enumerate() {
+            assert_eq!(MetadataIndexNodeType::LeafDevice, leaf.node_type());
+            let expected = if i < 11 { 256 } else { 3000 - 11 * 256 };
+            assert_eq!(expected, leaf.children().len());
+            // The root points to the first device of every leaf
+            assert_eq!(root.children()[i].name(), leaf.children()[0].name());
+        }
+        let mut expected = devices.clone();
+        expected.sort();
+        let names: Vec<String> = leaves
+            .iter()
+            .flat_map(|leaf| leaf.children().iter().map(|c| c.name().to_owned()))
+            .collect();
+        assert_eq!(expected, names);
+        for device_node in child_nodes(&leaves[0], &buffer)? {
+            assert_eq!(
+                MetadataIndexNodeType::LeafMeasurement,
+                device_node.node_type()
+            );
+            assert_eq!(1, device_node.children().len());
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn metadata_index_multi_level() -> Result<(), TsFileError> {
+        // A degree of 3 needs several internal levels for devices and measurements
+        let devices: Vec<String> = (0..40).map(|i| format!("d{:02}", i)).collect();
+        let (root, buffer) = construct_metadata_index(&devices, 30, 3)?;
+
+        let levels = index_levels(&root, &buffer)?;
+        // 40 devices in 14 leaves, then 5, 2 and 1 internal nodes
+        let sizes: Vec<usize> = levels.iter().map(|level| level.len()).collect();
+        assert_eq!(vec![1, 2, 5, 14], sizes);
+        for (depth, level) in levels.iter().enumerate() {
+            let node_type = match depth {
+                3 => MetadataIndexNodeType::LeafDevice,
+                _ => MetadataIndexNodeType::InternalDevice,
+            };
+            for node in level {
+                assert_eq!(node_type, node.node_type());
+                assert!((1..=3).contains(&node.children().len()));
+            }
+        }
+        let leaves = &levels[3];
+        assert_eq!(40, leaves.iter().map(|l| l.children().len()).sum::<usize>());
+
+        // Every third of the 30 measurements is an entry: 10 entries in 4 leaves,
+        // then 2 internal nodes below the root of the device
+        for device_root in child_nodes(&leaves[0], &buffer)? {
+            let levels = index_levels(&device_root, &buffer)?;
+            let sizes: Vec<usize> = levels.iter().map(|level| level.len()).collect();
+            assert_eq!(vec![1, 2, 4], sizes);
+            assert_eq!(
+                MetadataIndexNodeType::InternalMeasurement,
+                device_root.node_type()
+            );
+            assert!(levels[1]
+                .iter()
+                .all(|n| n.node_type() == MetadataIndexNodeType::InternalMeasurement));
+            let names: Vec<&str> = levels[2]
+                .iter()
+                .flat_map(|leaf| {
+                    assert_eq!(MetadataIndexNodeType::LeafMeasurement, leaf.node_type());
+                    assert!(leaf.children().len() <= 3);
+                    leaf.children().iter().map(|c| c.name())
+                })
+                .collect();
+            let expected: Vec<String> = (0..10).map(|i| format!("s{:02}", i * 3)).collect();
+            assert_eq!(expected, names);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn bloom_filter_round_trip() -> Result<(), TsFileError> {
+        let config = Default::default();
+        // Size is no multiple of 8
+        let mut filter = BloomFilter::new(257, 5, &config);
+        for i in 0..20 {
+            filter.add(format!("root.sg.d{}.s1", i));
+        }
+        filter.bit_set[256] = true;
+
+        let mut buffer: Vec<u8> = vec![];
+        filter.serialize(&mut buffer)?;
+        let deserialized = BloomFilter::deserialize(&mut buffer.as_slice(), &config)?;
+
+        assert_eq!(257, deserialized.size());
+        assert_eq!(5, deserialized.hash_function_size());
+        assert_eq!(filter.bit_set, deserialized.bit_set);
+        for i in 0..20 {
+            assert!(deserialized.might_contain(&format!("root.sg.d{}.s1", i)));
+        }
 
         Ok(())
     }
@@ -1806,3 +2292,10 @@ mod tests {
 
 pub const ONLY_ONE_PAGE_CHUNK_HEADER: u8 = 5;
 pub const CHUNK_HEADER: u8 = 1;
+pub const CHUNK_GROUP_HEADER: u8 = 0;
+pub const SEPARATOR: u8 = 2;
+pub const OPERATION_INDEX_RANGE: u8 = 4;
+/// Mask of the chunk header marker of the time chunk of aligned devices
+pub const TIME_COLUMN_MASK: u8 = 0x80;
+/// Mask of the chunk header marker of the value chunks of aligned devices
+pub const VALUE_COLUMN_MASK: u8 = 0x40;
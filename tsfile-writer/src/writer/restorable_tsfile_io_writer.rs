@@ -0,0 +1,549 @@
+//! Recovers TsFiles whose writer was not closed, e.g. after a power loss, like the
+//! `RestorableTsFileIOWriter` of IoTDB.
+//!
+//! Such a file has no footer and can not be read. The chunk groups of the data section are
+//! scanned sequentially and the file is truncated after the last complete chunk. The metadata
+//! of the remaining chunks is rebuilt, so that the file can be sealed or written further.
+//!
+//! ```
+//! use tsfile_writer::writer::restorable_tsfile_io_writer::RestorableTsFileIoWriter;
+//! use tsfile_writer::writer::compression::CompressionType;
+//! use tsfile_writer::writer::encoding::TSEncoding;
+//! use tsfile_writer::writer::{IoTDBValue, Schema, TSDataType};
+//!
+//! # use tsfile_writer::writer::tsfile_writer::TsFileWriter;
+//! # let schema = Schema::simple("d1", "s1", TSDataType::INT64, TSEncoding::PLAIN, CompressionType::UNCOMPRESSED);
+//! # let mut writer = TsFileWriter::new("target/restore_doc.tsfile", schema.clone(), Default::default()).unwrap();
+//! # writer.close().unwrap();
+//! let restorable = RestorableTsFileIoWriter::open("target/restore_doc.tsfile", Default::default()).unwrap();
+//! if restorable.can_write() {
+//!     let mut writer = restorable.into_writer(schema).unwrap();
+//!     writer.write("d1", "s1", 1, IoTDBValue::LONG(13)).unwrap();
+//!     writer.close().unwrap();
+//! }
+//! ```
+use crate::reader::{Chunk, TsFileSequenceReader, HEADER_SIZE, MAGIC_STRING, VERSION_NUMBER};
+use crate::writer::errors::TsFileError;
+use crate::writer::statistics::Statistics;
+use crate::writer::ts_file_config::TsFileConfig;
+use crate::writer::tsfile_io_writer::TsFileIoWriter;
+use crate::writer::tsfile_writer::TsFileWriter;
+use crate::writer::{
+    ChunkGroupMetadata, ChunkMetadata, Schema, WriteWrapper, CHUNK_GROUP_HEADER, CHUNK_HEADER,
+    ONLY_ONE_PAGE_CHUNK_HEADER, SEPARATOR, TIME_COLUMN_MASK, VALUE_COLUMN_MASK,
+};
+use std::fs::{File, OpenOptions};
+use std::io::{BufReader, Read, Seek, SeekFrom};
+
+pub struct RestorableTsFileIoWriter {
+    config: TsFileConfig,
+    /// Only present if the file was not complete
+    io_writer: Option<TsFileIoWriter<WriteWrapper<File>>>,
+    truncated_size: u64,
+    crashed: bool,
+}
+
+impl RestorableTsFileIoWriter {
+    /// Opens the file and recovers it if it has no footer. The config has to contain the
+    /// time encoding the file was written with, as it is needed to decode chunks with a
+    /// single page, whose statistics are only stored in the lost footer.
+    pub fn open(
+        filename: &str,
+        config: TsFileConfig,
+    ) -> Result<RestorableTsFileIoWriter, TsFileError> {
+        let mut file = OpenOptions::new().read(true).write(true).open(filename)?;
+        let file_size = file.seek(SeekFrom::End(0))?;
+        if TsFileSequenceReader::new(BufReader::new(&file), config).is_ok() {
+            return Ok(RestorableTsFileIoWriter {
+                config,
+                io_writer: None,
+                truncated_size: file_size,
+                crashed: false,
+            });
+        }
+
+        let (chunk_group_metadata_list, truncated_size) = match file_size < HEADER_SIZE {
+            true => {
+                // The writer did not even write the header completely
+                let mut content = vec![];
+                file.seek(SeekFrom::Start(0))?;
+                file.read_to_end(&mut content)?;
+                let mut header = MAGIC_STRING.to_vec();
+                header.push(VERSION_NUMBER);
+                if !header.starts_with(&content) {
+                    return Err(TsFileError::ReadError {
+                        source: Some("No TsFile magic string at the head of the file".to_owned()),
+                    });
+                }
+                (vec![], 0)
+            }
+            false => {
+                let mut reader =
+                    TsFileSequenceReader::new_incomplete(BufReader::new(&file), config)?;
+                Self::self_check(&mut reader)
+            }
+        };
+        log::info!(
+            "Truncate incomplete file {} from {} to {} bytes",
+            filename,
+            file_size,
+            truncated_size
+        );
+        file.set_len(truncated_size)?;
+        file.seek(SeekFrom::Start(truncated_size))?;
+        let io_writer = match truncated_size {
+            0 => TsFileIoWriter::new(WriteWrapper::new(file), config)?,
+            _ => TsFileIoWriter::restore(
+                WriteWrapper::at_position(file, truncated_size),
+                config,
+                chunk_group_metadata_list,
+            ),
+        };
+        Ok(RestorableTsFileIoWriter {
+            config,
+            io_writer: Some(io_writer),
+            truncated_size,
+            crashed: true,
+        })
+    }
+
+    /// True if the file had no footer
+    pub fn has_crashed(&self) -> bool {
+        self.crashed
+    }
+
+    /// Complete files can not be written further
+    pub fn can_write(&self) -> bool {
+        self.io_writer.is_some()
+    }
+
+    /// Size of the file after the truncation of incomplete chunks
+    pub fn truncated_size(&self) -> u64 {
+        self.truncated_size
+    }
+
+    /// Writes the footer for the recovered chunks, complete files are left as they are
+    pub fn seal(self) {
+        if let Some(mut io_writer) = self.io_writer {
+            io_writer.end_file();
+        }
+    }
+
+    /// Continues writing the recovered file, the schema has to contain the series that are
+    /// written from now on. Like in the file before, points are rejected with
+    /// `TsFileError::OutOfOrderData` if they do not follow the recovered ones of their series.
+    pub fn into_writer(
+        self,
+        schema: Schema,
+    ) -> Result<TsFileWriter<WriteWrapper<File>>, TsFileError> {
+        match self.io_writer {
+            Some(io_writer) => Ok(TsFileWriter::new_from_io_writer(
+                schema,
+                io_writer,
+                self.config,
+            )),
+            None => Err(TsFileError::IllegalState {
+                source: Some("The file is complete and can not be written".to_owned()),
+            }),
+        }
+    }
+
+    /// Returns the complete chunk groups and the position after their last complete chunk.
+    ///
+    /// Unlike IoTDB, the complete chunks of the last chunk group are kept, as the writer
+    /// flushes its chunk groups one after another and the last one is often complete.
+    /// The time chunk of an aligned device is only kept with at least one of its value chunks.
+    fn self_check<R: Read + Seek>(
+        reader: &mut TsFileSequenceReader<R>,
+    ) -> (Vec<ChunkGroupMetadata>, u64) {
+        let mut chunk_group_metadata_list = vec![];
+        let mut truncated_size = HEADER_SIZE;
+        if let Err(e) = Self::scan(reader, &mut chunk_group_metadata_list, &mut truncated_size) {
+            log::warn!("Stop recovery at offset {}: {:?}", truncated_size, e);
+        }
+        chunk_group_metadata_list.retain(|group| !group.chunk_metadata.is_empty());
+        (chunk_group_metadata_list, truncated_size)
+    }
+
+    fn scan<R: Read + Seek>(
+        reader: &mut TsFileSequenceReader<R>,
+        chunk_group_metadata_list: &mut Vec<ChunkGroupMetadata>,
+        truncated_size: &mut u64,
+    ) -> Result<(), TsFileError> {
+        reader.seek(HEADER_SIZE)?;
+        let mut time_chunk: Option<(Chunk, ChunkMetadata)> = None;
+        loop {
+            let offset = reader.position()?;
+            let marker = reader.read_marker()?;
+            match marker {
+                CHUNK_GROUP_HEADER => {
+                    let device_id = reader.read_chunk_group_header()?;
+                    chunk_group_metadata_list.push(ChunkGroupMetadata::new(device_id, vec![]));
+                    time_chunk = None;
+                }
+                // The footer was not written completely
+                SEPARATOR => return Ok(()),
+                m if m & 0x3F == CHUNK_HEADER || m & 0x3F == ONLY_ONE_PAGE_CHUNK_HEADER => {
+                    let chunk = reader.read_chunk(offset)?;
+                    let time = time_chunk.as_ref().map(|(chunk, _)| chunk);
+                    let chunk_metadata = ChunkMetadata::new(
+                        chunk.header.measurement_id.clone(),
+                        chunk.header.data_type,
+                        offset,
+                        Self::chunk_statistics(&chunk, time)?,
+                        chunk.header.mask,
+                    );
+                    let group = match chunk_group_metadata_list.last_mut() {
+                        Some(group) => group,
+                        None => {
+                            return Err(TsFileError::ReadError {
+                                source: Some(format!(
+                                    "Chunk at offset {} does not belong to a chunk group",
+                                    offset
+                                )),
+                            });
+                        }
+                    };
+                    if chunk.header.mask == TIME_COLUMN_MASK {
+                        time_chunk = Some((chunk, chunk_metadata));
+                        continue;
+                    }
+                    if let Some((_, time_metadata)) = time_chunk.as_ref() {
+                        // The first value chunk completes the time chunk
+                        if group.chunk_metadata.is_empty() {
+                            group.chunk_metadata.push(time_metadata.clone());
+                        }
+                    }
+                    group.chunk_metadata.push(chunk_metadata);
+                    *truncated_size = reader.position()?;
+                }
+                _ => {
+                    return Err(TsFileError::ReadError {
+                        source: Some(format!("Unknown marker {} at offset {}", marker, offset)),
+                    });
+                }
+            }
+        }
+    }
+
+    /// The statistics of a chunk with several pages are merged from the page headers,
+    /// chunks with a single page are decoded
+    fn chunk_statistics(
+        chunk: &Chunk,
+        time_chunk: Option<&Chunk>,
+    ) -> Result<Statistics, TsFileError> {
+        let mut statistics = Statistics::new(chunk.header.data_type);
+        if chunk.pages.len() > 1 {
+            for page in chunk.pages.iter() {
+                match &page.header.statistics {
+                    Some(page_statistics) => statistics.merge(page_statistics),
+                    None => {
+                        return Err(TsFileError::ReadError {
+                            source: Some(format!(
+                                "Page of chunk at offset {} without statistics",
+                                chunk.offset
+                            )),
+                        });
+                    }
+                }
+            }
+            return Ok(statistics);
+        }
+        match (chunk.header.mask, time_chunk) {
+            (TIME_COLUMN_MASK, _) => {
+                for timestamp in chunk.read_timestamps()? {
+                    statistics.update_time(timestamp)?;
+                }
+            }
+            (VALUE_COLUMN_MASK, Some(time_chunk)) => {
+                for (timestamp, value) in chunk.read_aligned_points(time_chunk)? {
+                    statistics.update(timestamp, &value)?;
+                }
+            }
+            (VALUE_COLUMN_MASK, None) => {
+                return Err(TsFileError::ReadError {
+                    source: Some(format!(
+                        "Value chunk at offset {} without time chunk",
+                        chunk.offset
+                    )),
+                });
+            }
+            _ => {
+                for (timestamp, value) in chunk.read_points()? {
+                    statistics.update(timestamp, &value)?;
+                }
+            }
+        }
+        Ok(statistics)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::reader::TsFileSequenceReader;
+    use crate::writer::compression::CompressionType;
+    use crate::writer::encoding::TSEncoding;
+    use crate::writer::errors::TsFileError;
+    use crate::writer::restorable_tsfile_io_writer::RestorableTsFileIoWriter;
+    use crate::writer::schema::{AlignedDeviceBuilder, DeviceBuilder, TsFileSchemaBuilder};
+    use crate::writer::ts_file_config::{TsFileConfig, TsFileConfigBuilder};
+    use crate::writer::tsfile_writer::{DataPoint, TsFileWriter};
+    use crate::writer::{IoTDBValue, Schema, TSDataType};
+    use std::io::Cursor;
+
+    const SERIES: [(&str, &str); 4] = [("d1", "s1"), ("d1", "s2"), ("d2", "s1"), ("d2", "s2")];
+
+    fn schema() -> Schema {
+        TsFileSchemaBuilder::new()
+            .add(
+                "d1",
+                DeviceBuilder::new()
+                    .add(
+                        "s1",
+                        TSDataType::INT64,
+                        TSEncoding::TS2DIFF,
+                        CompressionType::SNAPPY,
+                    )
+                    .add(
+                        "s2",
+                        TSDataType::FLOAT,
+                        TSEncoding::GORILLA,
+                        CompressionType::UNCOMPRESSED,
+                    )
+                    .build(),
+            )
+            .add(
+                "d2",
+                AlignedDeviceBuilder::new()
+                    .add(
+                        "s1",
+                        TSDataType::INT32,
+                        TSEncoding::RLE,
+                        CompressionType::UNCOMPRESSED,
+                    )
+                    .add(
+                        "s2",
+                        TSDataType::TEXT,
+                        TSEncoding::PLAIN,
+                        CompressionType::SNAPPY,
+                    )
+                    .build(),
+            )
+            .build()
+    }
+
+    fn config() -> TsFileConfig {
+        // d1.s1 gets several pages, the other series a single one
+        TsFileConfigBuilder::new()
+            .max_number_of_points_in_page(100)
+            .build()
+            .unwrap()
+    }
+
+    /// Returns a complete file
+    fn write_file() -> Result<Vec<u8>, TsFileError> {
+        let mut writer = TsFileWriter::new_from_writer(schema(), Vec::new(), config())?;
+        for timestamp in 0..1000 {
+            writer.write("d1", "s1", timestamp, IoTDBValue::LONG(timestamp * 3))?;
+        }
+        for timestamp in 0..3 {
+            writer.write("d1", "s2", timestamp, IoTDBValue::FLOAT(timestamp as f32))?;
+            writer.write_many(
+                "d2",
+                timestamp,
+                vec![
+                    DataPoint::new("s1", IoTDBValue::INT(timestamp as i32)),
+                    match timestamp {
+                        1 => DataPoint::null("s2"),
+                        _ => DataPoint::new("s2", IoTDBValue::from("v")),
+                    },
+                ],
+            )?;
+        }
+        writer.close()?;
+        Ok(writer.file_io_writer.out)
+    }
+
+    fn write_to(name: &str, content: &[u8]) -> String {
+        std::fs::create_dir_all("target").unwrap();
+        let filename = format!("target/restore_{}.tsfile", name);
+        std::fs::write(&filename, content).unwrap();
+        filename
+    }
+
+    /// Statistics of all series that are contained in the file
+    fn statistics(content: Vec<u8>) -> Result<Vec<String>, TsFileError> {
+        let mut reader = TsFileSequenceReader::new(Cursor::new(content), config())?;
+        let mut result = vec![];
+        for (device, measurement) in SERIES {
+            if let Some(timeseries) = reader.read_timeseries_metadata_of(device, measurement)? {
+                result.push(format!(
+                    "{}.{}: {:?}",
+                    device,
+                    measurement,
+                    timeseries.statistics()
+                ));
+            }
+        }
+        Ok(result)
+    }
+
+    #[test]
+    fn restore_lost_footer() -> Result<(), TsFileError> {
+        let content = write_file()?;
+        let meta_offset = TsFileSequenceReader::new(Cursor::new(content.clone()), config())?
+            .file_metadata()?
+            .meta_offset();
+        let expected = statistics(content.clone())?;
+        assert_eq!(4, expected.len());
+
+        for (i, end) in [meta_offset, meta_offset + 1, content.len() as u64 - 3]
+            .into_iter()
+            .enumerate()
+        {
+            let filename = write_to(&format!("footer_{}", i), &content[..end as usize]);
+            let restorable = RestorableTsFileIoWriter::open(&filename, config())?;
+            assert!(restorable.has_crashed());
+            assert!(restorable.can_write());
+            assert_eq!(meta_offset, restorable.truncated_size());
+            restorable.seal();
+
+            let restored = std::fs::read(&filename)?;
+            assert_eq!(content, restored);
+            assert_eq!(expected, statistics(restored)?);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn restore_incomplete_chunks() -> Result<(), TsFileError> {
+        let content = write_file()?;
+        let chunk_groups = TsFileSequenceReader::new(Cursor::new(content.clone()), config())?
+            .chunk_groups()?
+            .collect::<Result<Vec<_>, _>>()?;
+        assert_eq!("d2", chunk_groups[1].device_id);
+        let d2 = &chunk_groups[1].chunks;
+
+        // The second value chunk of d2 is lost
+        let filename = write_to("value_chunk", &content[..d2[2].offset as usize + 10]);
+        let restorable = RestorableTsFileIoWriter::open(&filename, config())?;
+        assert_eq!(d2[2].offset, restorable.truncated_size());
+        restorable.seal();
+        let restored = statistics(std::fs::read(&filename)?)?;
+        assert_eq!(3, restored.len());
+        assert!(restored[2].starts_with("d2.s1"));
+
+        // The time chunk of d2 is dropped with its only value chunk
+        let filename = write_to("time_chunk", &content[..d2[1].offset as usize + 10]);
+        let restorable = RestorableTsFileIoWriter::open(&filename, config())?;
+        assert!(restorable.truncated_size() < d2[0].offset);
+        restorable.seal();
+        let restored = statistics(std::fs::read(&filename)?)?;
+        assert_eq!(2, restored.len());
+        assert!(restored[1].starts_with("d1.s2"));
+        Ok(())
+    }
+
+    #[test]
+    fn restore_and_continue() -> Result<(), TsFileError> {
+        let content = write_file()?;
+        let chunk_groups = TsFileSequenceReader::new(Cursor::new(content.clone()), config())?
+            .chunk_groups()?
+            .collect::<Result<Vec<_>, _>>()?;
+        let filename = write_to(
+            "continue",
+            &content[..chunk_groups[1].chunks[2].offset as usize + 1],
+        );
+
+        let restorable = RestorableTsFileIoWriter::open(&filename, config())?;
+        let mut writer = restorable.into_writer(schema())?;
+        for timestamp in 1000..1010 {
+            writer.write("d1", "s1", timestamp, IoTDBValue::LONG(timestamp * 3))?;
+        }
+        writer.close()?;
+
+        let mut reader = TsFileSequenceReader::open(&filename, config())?;
+        let s1 = reader.read_timeseries_metadata_of("d1", "s1")?.unwrap();
+        assert_eq!(1010, s1.statistics().count());
+        assert_eq!(Some(IoTDBValue::LONG(3027)), s1.statistics().last_value());
+        let chunk_groups = reader.chunk_groups()?.collect::<Result<Vec<_>, _>>()?;
+        let devices: Vec<&str> = chunk_groups
+            .iter()
+            .filter(|g| !g.chunks.is_empty())
+            .map(|g| g.device_id.as_str())
+            .collect();
+        assert_eq!(vec!["d1", "d2", "d1"], devices);
+        assert_eq!(10, chunk_groups[2].chunks[0].read_points()?.len());
+        Ok(())
+    }
+
+    #[test]
+    fn restore_and_reject_old_points() -> Result<(), TsFileError> {
+        let content = write_file()?;
+        let chunk_groups = TsFileSequenceReader::new(Cursor::new(content.clone()), config())?
+            .chunk_groups()?
+            .collect::<Result<Vec<_>, _>>()?;
+        // The value chunk of d2.s2 is lost
+        let filename = write_to(
+            "old_points",
+            &content[..chunk_groups[1].chunks[2].offset as usize + 1],
+        );
+
+        let restorable = RestorableTsFileIoWriter::open(&filename, config())?;
+        let mut writer = restorable.into_writer(schema())?;
+        assert_eq!(
+            Err(TsFileError::OutOfOrderData),
+            writer.write("d1", "s1", 999, IoTDBValue::LONG(0))
+        );
+        assert_eq!(
+            Err(TsFileError::OutOfOrderData),
+            writer.write("d1", "s2", 1, IoTDBValue::FLOAT(0.0))
+        );
+        // The time chunk of the aligned device is kept
+        assert_eq!(
+            Err(TsFileError::OutOfOrderData),
+            writer.write("d2", "s2", 2, IoTDBValue::from("x"))
+        );
+        writer.write("d1", "s1", 1000, IoTDBValue::LONG(0))?;
+        writer.write("d2", "s2", 3, IoTDBValue::from("x"))?;
+        writer.close()?;
+
+        let mut reader = TsFileSequenceReader::open(&filename, config())?;
+        let s1 = reader.read_timeseries_metadata_of("d1", "s1")?.unwrap();
+        assert_eq!(1001, s1.statistics().count());
+        Ok(())
+    }
+
+    #[test]
+    fn restore_complete_file() -> Result<(), TsFileError> {
+        let content = write_file()?;
+        let filename = write_to("complete", &content);
+
+        let restorable = RestorableTsFileIoWriter::open(&filename, config())?;
+        assert!(!restorable.has_crashed());
+        assert!(!restorable.can_write());
+        assert_eq!(content.len() as u64, restorable.truncated_size());
+        restorable.seal();
+        assert_eq!(content, std::fs::read(&filename)?);
+        assert!(RestorableTsFileIoWriter::open(&filename, config())?
+            .into_writer(schema())
+            .is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn restore_incomplete_header() -> Result<(), TsFileError> {
+        let filename = write_to("header", b"TsF");
+        let restorable = RestorableTsFileIoWriter::open(&filename, config())?;
+        assert!(restorable.has_crashed());
+        assert_eq!(0, restorable.truncated_size());
+        let mut writer = restorable.into_writer(schema())?;
+        writer.write("d1", "s2", 1, IoTDBValue::FLOAT(1.0))?;
+        writer.close()?;
+        assert_eq!(1, statistics(std::fs::read(&filename)?)?.len());
+
+        let filename = write_to("no_tsfile", b"NoTsFile");
+        assert!(RestorableTsFileIoWriter::open(&filename, config()).is_err());
+        Ok(())
+    }
+}
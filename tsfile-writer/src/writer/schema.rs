@@ -1,16 +1,17 @@
 //! Contains the classes for defining the Schema of a TsFile, i.e. which Devices / Snesors
 //! it contains and their datatype / encoding / compression
+use crate::writer::encoding::float::DEFAULT_MAX_POINT_NUMBER;
 use crate::writer::{
     CompressionType, MeasurementGroup, MeasurementSchema, Schema, TSDataType, TSEncoding,
 };
 use std::collections::HashMap;
 
-pub struct TsFileSchemaBuilder<'a> {
-    measurement_groups_map: HashMap<&'a str, MeasurementGroup<'a>>,
+pub struct TsFileSchemaBuilder {
+    measurement_groups_map: HashMap<String, MeasurementGroup>,
 }
 
-impl<'a> TsFileSchemaBuilder<'a> {
-    pub fn new() -> TsFileSchemaBuilder<'a> {
+impl TsFileSchemaBuilder {
+    pub fn new() -> TsFileSchemaBuilder {
         TsFileSchemaBuilder {
             measurement_groups_map: HashMap::new(),
         }
@@ -18,36 +19,32 @@ impl<'a> TsFileSchemaBuilder<'a> {
 
     pub fn add(
         &mut self,
-        device: &'a str,
-        schema: MeasurementGroup<'a>,
-    ) -> &mut TsFileSchemaBuilder<'a> {
-        self.measurement_groups_map.insert(device, schema);
+        device: impl Into<String>,
+        schema: MeasurementGroup,
+    ) -> &mut TsFileSchemaBuilder {
+        self.measurement_groups_map.insert(device.into(), schema);
         self
     }
 
-    pub fn build(&mut self) -> Schema<'a> {
-        // Copy the content
-        let mut measurement_groups: HashMap<&str, MeasurementGroup> = HashMap::new();
-        measurement_groups.clear();
-        for (s, mg) in self.measurement_groups_map.iter_mut() {
-            measurement_groups.insert(s, mg.clone());
+    pub fn build(&mut self) -> Schema {
+        Schema {
+            measurement_groups: self.measurement_groups_map.clone(),
         }
-        Schema { measurement_groups }
     }
 }
 
-impl<'a> Default for TsFileSchemaBuilder<'a> {
+impl Default for TsFileSchemaBuilder {
     fn default() -> Self {
         Self::new()
     }
 }
 
-pub struct DeviceBuilder<'a> {
-    measurement_groups_map: HashMap<&'a str, MeasurementSchema>,
+pub struct DeviceBuilder {
+    measurement_groups_map: HashMap<String, MeasurementSchema>,
 }
 
-impl<'a> DeviceBuilder<'a> {
-    pub fn new() -> DeviceBuilder<'a> {
+impl DeviceBuilder {
+    pub fn new() -> DeviceBuilder {
         DeviceBuilder {
             measurement_groups_map: HashMap::new(),
         }
@@ -55,37 +52,126 @@ impl<'a> DeviceBuilder<'a> {
 
     pub fn add(
         &mut self,
-        measurement: &'a str,
+        measurement: impl Into<String>,
         data_type: TSDataType,
         encoding: TSEncoding,
         compression: CompressionType,
-    ) -> &mut DeviceBuilder<'a> {
-        self.measurement_groups_map.insert(
+    ) -> &mut DeviceBuilder {
+        self.add_with_max_point_number(
             measurement,
+            data_type,
+            encoding,
+            compression,
+            DEFAULT_MAX_POINT_NUMBER,
+        )
+    }
+
+    /// Like `add`, with the number of decimal places kept by TS2DIFF for FLOAT and DOUBLE
+    pub fn add_with_max_point_number(
+        &mut self,
+        measurement: impl Into<String>,
+        data_type: TSDataType,
+        encoding: TSEncoding,
+        compression: CompressionType,
+        max_point_number: u32,
+    ) -> &mut DeviceBuilder {
+        self.measurement_groups_map.insert(
+            measurement.into(),
             MeasurementSchema {
                 data_type,
                 compression,
                 encoding,
+                max_point_number,
             },
         );
         self
     }
 
-    pub fn build(&mut self) -> MeasurementGroup<'a> {
+    pub fn build(&mut self) -> MeasurementGroup {
         assert!(!self.measurement_groups_map.is_empty());
-        // Copy the content
-        let mut measurement_schemas: HashMap<&'a str, MeasurementSchema> = HashMap::new();
-        measurement_schemas.clear();
-        for (s, ms) in self.measurement_groups_map.iter_mut() {
-            measurement_schemas.insert(s, ms.clone());
+        MeasurementGroup {
+            measurement_schemas: self.measurement_groups_map.clone(),
+            aligned: false,
+            time_compression: CompressionType::UNCOMPRESSED,
+        }
+    }
+}
+
+impl Default for DeviceBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Builds an aligned device, i.e. all measurements share one time column.
+/// Measurements without a value at a timestamp are stored as nulls.
+pub struct AlignedDeviceBuilder {
+    measurement_groups_map: HashMap<String, MeasurementSchema>,
+    time_compression: CompressionType,
+}
+
+impl AlignedDeviceBuilder {
+    pub fn new() -> AlignedDeviceBuilder {
+        AlignedDeviceBuilder {
+            measurement_groups_map: HashMap::new(),
+            time_compression: CompressionType::UNCOMPRESSED,
         }
+    }
+
+    pub fn add(
+        &mut self,
+        measurement: impl Into<String>,
+        data_type: TSDataType,
+        encoding: TSEncoding,
+        compression: CompressionType,
+    ) -> &mut AlignedDeviceBuilder {
+        self.add_with_max_point_number(
+            measurement,
+            data_type,
+            encoding,
+            compression,
+            DEFAULT_MAX_POINT_NUMBER,
+        )
+    }
+
+    /// Like `add`, with the number of decimal places kept by TS2DIFF for FLOAT and DOUBLE
+    pub fn add_with_max_point_number(
+        &mut self,
+        measurement: impl Into<String>,
+        data_type: TSDataType,
+        encoding: TSEncoding,
+        compression: CompressionType,
+        max_point_number: u32,
+    ) -> &mut AlignedDeviceBuilder {
+        self.measurement_groups_map.insert(
+            measurement.into(),
+            MeasurementSchema {
+                data_type,
+                compression,
+                encoding,
+                max_point_number,
+            },
+        );
+        self
+    }
+
+    /// Compression of the shared time column, default is UNCOMPRESSED
+    pub fn time_compression(&mut self, compression: CompressionType) -> &mut AlignedDeviceBuilder {
+        self.time_compression = compression;
+        self
+    }
+
+    pub fn build(&mut self) -> MeasurementGroup {
+        assert!(!self.measurement_groups_map.is_empty());
         MeasurementGroup {
-            measurement_schemas,
+            measurement_schemas: self.measurement_groups_map.clone(),
+            aligned: true,
+            time_compression: self.time_compression,
         }
     }
 }
 
-impl<'a> Default for DeviceBuilder<'a> {
+impl Default for AlignedDeviceBuilder {
     fn default() -> Self {
         Self::new()
     }
@@ -93,7 +179,7 @@ impl<'a> Default for DeviceBuilder<'a> {
 
 #[cfg(test)]
 mod test {
-    use crate::writer::schema::{DeviceBuilder, TsFileSchemaBuilder};
+    use crate::writer::schema::{AlignedDeviceBuilder, DeviceBuilder, TsFileSchemaBuilder};
     use crate::writer::{CompressionType, TSDataType, TSEncoding};
 
     #[test]
@@ -129,4 +215,38 @@ mod test {
             2
         );
     }
+
+    #[test]
+    fn aligned_device_builder() {
+        let schema = TsFileSchemaBuilder::new()
+            .add(
+                "d1",
+                AlignedDeviceBuilder::new()
+                    .add(
+                        "s1",
+                        TSDataType::INT32,
+                        TSEncoding::PLAIN,
+                        CompressionType::UNCOMPRESSED,
+                    )
+                    .time_compression(CompressionType::SNAPPY)
+                    .build(),
+            )
+            .add(
+                "d2",
+                DeviceBuilder::new()
+                    .add(
+                        "s1",
+                        TSDataType::INT32,
+                        TSEncoding::PLAIN,
+                        CompressionType::UNCOMPRESSED,
+                    )
+                    .build(),
+            )
+            .build();
+
+        let d1 = schema.measurement_groups.get("d1").unwrap();
+        assert!(d1.is_aligned());
+        assert_eq!(CompressionType::SNAPPY, d1.time_compression);
+        assert!(!schema.measurement_groups.get("d2").unwrap().is_aligned());
+    }
 }
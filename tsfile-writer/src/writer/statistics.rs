@@ -1,7 +1,27 @@
-use crate::writer::utils::size_var_u32;
+use crate::writer::utils::{
+    read_byte, read_f32, read_f64, read_i32, read_i64, read_var_u32, size_var_u32,
+};
 use crate::writer::{
     write_var_u32, IoTDBValue, PositionedWrite, Serializable, TSDataType, TsFileError,
 };
+use std::io::Read;
+
+/// Value of a statistics field of the numeric types, None if the statistics are empty
+macro_rules! statistics_value {
+    ( $self:ident, $field:ident ) => {
+        if $self.count() == 0 {
+            None
+        } else {
+            match $self {
+                Statistics::INT32(s) => Some(IoTDBValue::INT(s.$field)),
+                Statistics::INT64(s) => Some(IoTDBValue::LONG(s.$field)),
+                Statistics::FLOAT(s) => Some(IoTDBValue::FLOAT(s.$field)),
+                Statistics::DOUBLE(s) => Some(IoTDBValue::DOUBLE(s.$field)),
+                Statistics::BOOLEAN(_) | Statistics::TEXT(_) | Statistics::VECTOR(_) => None,
+            }
+        }
+    };
+}
 
 #[derive(Clone, Debug)]
 #[allow(clippy::upper_case_acronyms)]
@@ -9,14 +29,77 @@ pub enum Statistics {
     INT32(StatisticsStruct<i32, i64>),
     INT64(StatisticsStruct<i64, f64>),
     FLOAT(StatisticsStruct<f32, f64>),
+    DOUBLE(StatisticsStruct<f64, f64>),
+    BOOLEAN(BooleanStatistics),
+    TEXT(BinaryStatistics),
+    VECTOR(TimeStatistics),
 }
 
 impl Statistics {
-    pub(crate) fn count(&self) -> u32 {
+    pub fn count(&self) -> u32 {
         match self {
             Statistics::INT32(s) => s.count,
             Statistics::INT64(s) => s.count,
             Statistics::FLOAT(s) => s.count,
+            Statistics::DOUBLE(s) => s.count,
+            Statistics::BOOLEAN(s) => s.count,
+            Statistics::TEXT(s) => s.count,
+            Statistics::VECTOR(s) => s.count,
+        }
+    }
+    pub fn start_time(&self) -> i64 {
+        match self {
+            Statistics::INT32(s) => s.ts_first,
+            Statistics::INT64(s) => s.ts_first,
+            Statistics::FLOAT(s) => s.ts_first,
+            Statistics::DOUBLE(s) => s.ts_first,
+            Statistics::BOOLEAN(s) => s.ts_first,
+            Statistics::TEXT(s) => s.ts_first,
+            Statistics::VECTOR(s) => s.ts_first,
+        }
+    }
+    pub fn end_time(&self) -> i64 {
+        match self {
+            Statistics::INT32(s) => s.ts_last,
+            Statistics::INT64(s) => s.ts_last,
+            Statistics::FLOAT(s) => s.ts_last,
+            Statistics::DOUBLE(s) => s.ts_last,
+            Statistics::BOOLEAN(s) => s.ts_last,
+            Statistics::TEXT(s) => s.ts_last,
+            Statistics::VECTOR(s) => s.ts_last,
+        }
+    }
+    /// Minimal value, None for empty statistics, BOOLEAN, TEXT and VECTOR
+    pub fn min_value(&self) -> Option<IoTDBValue> {
+        statistics_value!(self, min_value)
+    }
+    /// Maximal value, None for empty statistics, BOOLEAN, TEXT and VECTOR
+    pub fn max_value(&self) -> Option<IoTDBValue> {
+        statistics_value!(self, max_value)
+    }
+    pub fn first_value(&self) -> Option<IoTDBValue> {
+        match self {
+            Statistics::BOOLEAN(s) if s.count > 0 => Some(IoTDBValue::BOOLEAN(s.first_value)),
+            Statistics::TEXT(s) if s.count > 0 => Some(IoTDBValue::TEXT(s.first_value.clone())),
+            _ => statistics_value!(self, first_value),
+        }
+    }
+    pub fn last_value(&self) -> Option<IoTDBValue> {
+        match self {
+            Statistics::BOOLEAN(s) if s.count > 0 => Some(IoTDBValue::BOOLEAN(s.last_value)),
+            Statistics::TEXT(s) if s.count > 0 => Some(IoTDBValue::TEXT(s.last_value.clone())),
+            _ => statistics_value!(self, last_value),
+        }
+    }
+    /// Sum of the values, None for TEXT and VECTOR
+    pub fn sum(&self) -> Option<f64> {
+        match self {
+            Statistics::INT32(s) => Some(s.sum_value as f64),
+            Statistics::INT64(s) => Some(s.sum_value),
+            Statistics::FLOAT(s) => Some(s.sum_value),
+            Statistics::DOUBLE(s) => Some(s.sum_value),
+            Statistics::BOOLEAN(s) => Some(s.sum_value as f64),
+            Statistics::TEXT(_) | Statistics::VECTOR(_) => None,
         }
     }
     pub(crate) fn get_serialized_size(&self) -> u32 {
@@ -24,18 +107,35 @@ impl Statistics {
             Statistics::INT32(s) => s.get_serialized_size(),
             Statistics::INT64(s) => s.get_serialized_size(),
             Statistics::FLOAT(s) => s.get_serialized_size(),
+            Statistics::DOUBLE(s) => s.get_serialized_size(),
+            Statistics::BOOLEAN(s) => s.get_serialized_size(),
+            Statistics::TEXT(s) => s.get_serialized_size(),
+            Statistics::VECTOR(s) => s.get_serialized_size(),
         }
     }
 }
 
 impl Statistics {
-    pub(crate) fn update(&mut self, timestamp: i64, value: &IoTDBValue) {
+    /// Counts a timestamp of the time column of an aligned device
+    pub(crate) fn update_time(&mut self, timestamp: i64) -> Result<(), TsFileError> {
+        match self {
+            Statistics::VECTOR(s) => s.update(timestamp),
+            _ => return Err(TsFileError::WrongTypeForSeries),
+        }
+        Ok(())
+    }
+
+    pub(crate) fn update(&mut self, timestamp: i64, value: &IoTDBValue) -> Result<(), TsFileError> {
         match (self, value) {
             (Statistics::INT32(s), IoTDBValue::INT(v)) => s.update(timestamp, *v),
             (Statistics::INT64(s), IoTDBValue::LONG(v)) => s.update(timestamp, *v),
             (Statistics::FLOAT(s), IoTDBValue::FLOAT(v)) => s.update(timestamp, *v),
-            _ => todo!(),
+            (Statistics::DOUBLE(s), IoTDBValue::DOUBLE(v)) => s.update(timestamp, *v),
+            (Statistics::BOOLEAN(s), IoTDBValue::BOOLEAN(v)) => s.update(timestamp, *v),
+            (Statistics::TEXT(s), IoTDBValue::TEXT(v)) => s.update(timestamp, v),
+            _ => return Err(TsFileError::WrongTypeForSeries),
         }
+        Ok(())
     }
 }
 
@@ -60,6 +160,30 @@ impl Statistics {
                     panic!("...")
                 }
             },
+            Statistics::DOUBLE(s) => match other {
+                Statistics::DOUBLE(othr) => s.merge(othr),
+                _ => {
+                    panic!("...")
+                }
+            },
+            Statistics::BOOLEAN(s) => match other {
+                Statistics::BOOLEAN(othr) => s.merge(othr),
+                _ => {
+                    panic!("...")
+                }
+            },
+            Statistics::TEXT(s) => match other {
+                Statistics::TEXT(othr) => s.merge(othr),
+                _ => {
+                    panic!("...")
+                }
+            },
+            Statistics::VECTOR(s) => match other {
+                Statistics::VECTOR(othr) => s.merge(othr),
+                _ => {
+                    panic!("...")
+                }
+            },
         }
     }
 }
@@ -70,16 +194,49 @@ impl Statistics {
             TSDataType::INT32 => Statistics::INT32(StatisticsStruct::<i32, i64>::new()),
             TSDataType::INT64 => Statistics::INT64(StatisticsStruct::<i64, f64>::new()),
             TSDataType::FLOAT => Statistics::FLOAT(StatisticsStruct::<f32, f64>::new()),
+            TSDataType::DOUBLE => Statistics::DOUBLE(StatisticsStruct::<f64, f64>::new()),
+            TSDataType::BOOLEAN => Statistics::BOOLEAN(BooleanStatistics::new()),
+            TSDataType::TEXT => Statistics::TEXT(BinaryStatistics::new()),
+            TSDataType::VECTOR => Statistics::VECTOR(TimeStatistics::new()),
         }
     }
 }
 
+impl Statistics {
+    pub(crate) fn deserialize(
+        data_type: TSDataType,
+        reader: &mut dyn Read,
+    ) -> Result<Statistics, TsFileError> {
+        Ok(match data_type {
+            TSDataType::INT32 => {
+                Statistics::INT32(StatisticsStruct::<i32, i64>::deserialize(reader)?)
+            }
+            TSDataType::INT64 => {
+                Statistics::INT64(StatisticsStruct::<i64, f64>::deserialize(reader)?)
+            }
+            TSDataType::FLOAT => {
+                Statistics::FLOAT(StatisticsStruct::<f32, f64>::deserialize(reader)?)
+            }
+            TSDataType::DOUBLE => {
+                Statistics::DOUBLE(StatisticsStruct::<f64, f64>::deserialize(reader)?)
+            }
+            TSDataType::BOOLEAN => Statistics::BOOLEAN(BooleanStatistics::deserialize(reader)?),
+            TSDataType::TEXT => Statistics::TEXT(BinaryStatistics::deserialize(reader)?),
+            TSDataType::VECTOR => Statistics::VECTOR(TimeStatistics::deserialize(reader)?),
+        })
+    }
+}
+
 impl Serializable for Statistics {
     fn serialize(&self, file: &mut dyn PositionedWrite) -> Result<(), TsFileError> {
         match self {
             Statistics::INT32(s) => s.serialize(file),
             Statistics::INT64(s) => s.serialize(file),
             Statistics::FLOAT(s) => s.serialize(file),
+            Statistics::DOUBLE(s) => s.serialize(file),
+            Statistics::BOOLEAN(s) => s.serialize(file),
+            Statistics::TEXT(s) => s.serialize(file),
+            Statistics::VECTOR(s) => s.serialize(file),
         }
     }
 }
@@ -119,8 +276,14 @@ impl StatisticsStruct<f32, f64> {
     }
 }
 
+impl StatisticsStruct<f64, f64> {
+    pub(crate) fn get_serialized_size(&self) -> u32 {
+        size_var_u32(self.count) as u32 + 16 + 40
+    }
+}
+
 macro_rules! implement_statistics {
-    ( $type:ty, $sum:ty ) => {
+    ( $type:ty, $sum:ty, $read_value:ident, $read_sum:ident ) => {
         impl StatisticsStruct<$type, $sum> {
             pub(crate) fn new() -> StatisticsStruct<$type, $sum> {
                 StatisticsStruct {
@@ -154,6 +317,22 @@ macro_rules! implement_statistics {
                 self.sum_value += statistics.sum_value;
             }
 
+            pub(crate) fn deserialize(
+                reader: &mut dyn Read,
+            ) -> Result<StatisticsStruct<$type, $sum>, TsFileError> {
+                // Same layout as written by serialize
+                Ok(StatisticsStruct {
+                    count: read_var_u32(reader)?,
+                    ts_first: read_i64(reader)?,
+                    ts_last: read_i64(reader)?,
+                    min_value: $read_value(reader)?,
+                    max_value: $read_value(reader)?,
+                    first_value: $read_value(reader)?,
+                    last_value: $read_value(reader)?,
+                    sum_value: $read_sum(reader)?,
+                })
+            }
+
             pub(crate) fn update(&mut self, timestamp: i64, value: $type) {
                 if timestamp < self.ts_first {
                     self.ts_first = timestamp;
@@ -193,8 +372,237 @@ macro_rules! implement_statistics {
     };
 }
 
-implement_statistics!(i32, i64);
-implement_statistics!(i64, f64);
-implement_statistics!(f32, f64);
-// TODO Implement / use
-// implement_statistics!(f64);
+implement_statistics!(i32, i64, read_i32, read_i64);
+implement_statistics!(i64, f64, read_i64, read_f64);
+implement_statistics!(f32, f64, read_f32, read_f64);
+implement_statistics!(f64, f64, read_f64, read_f64);
+
+/// Statistics of BOOLEAN series, they have no min and max value and
+/// the sum is the number of true values
+#[derive(Copy, Clone, Debug)]
+pub struct BooleanStatistics {
+    ts_first: i64,
+    ts_last: i64,
+
+    first_value: bool,
+    last_value: bool,
+    count: u32,
+    sum_value: i64,
+}
+
+impl BooleanStatistics {
+    pub(crate) fn new() -> BooleanStatistics {
+        BooleanStatistics {
+            ts_first: i64::MAX,
+            ts_last: i64::MIN,
+            first_value: false,
+            last_value: false,
+            count: 0,
+            sum_value: 0,
+        }
+    }
+
+    pub(crate) fn get_serialized_size(&self) -> u32 {
+        // first, last (1 byte each) and sum (8 bytes)
+        size_var_u32(self.count) as u32 + 16 + 10
+    }
+
+    pub(crate) fn merge(&mut self, statistics: &BooleanStatistics) {
+        if statistics.ts_first < self.ts_first {
+            self.ts_first = statistics.ts_first;
+            self.first_value = statistics.first_value;
+        }
+        if statistics.ts_last > self.ts_last {
+            self.ts_last = statistics.ts_last;
+            self.last_value = statistics.last_value;
+        }
+        self.count += statistics.count;
+        self.sum_value += statistics.sum_value;
+    }
+
+    pub(crate) fn deserialize(reader: &mut dyn Read) -> Result<BooleanStatistics, TsFileError> {
+        Ok(BooleanStatistics {
+            count: read_var_u32(reader)?,
+            ts_first: read_i64(reader)?,
+            ts_last: read_i64(reader)?,
+            first_value: read_byte(reader)? == 1,
+            last_value: read_byte(reader)? == 1,
+            sum_value: read_i64(reader)?,
+        })
+    }
+
+    pub(crate) fn update(&mut self, timestamp: i64, value: bool) {
+        if timestamp < self.ts_first {
+            self.ts_first = timestamp;
+            self.first_value = value;
+        }
+        if timestamp > self.ts_last {
+            self.ts_last = timestamp;
+            self.last_value = value;
+        }
+        self.count += 1;
+        if value {
+            self.sum_value += 1;
+        }
+    }
+}
+
+impl Serializable for BooleanStatistics {
+    fn serialize(&self, file: &mut dyn PositionedWrite) -> Result<(), TsFileError> {
+        write_var_u32(self.count, file)?;
+        file.write_all(&self.ts_first.to_be_bytes())?;
+        file.write_all(&self.ts_last.to_be_bytes())?;
+
+        file.write_all(&[self.first_value as u8])?;
+        file.write_all(&[self.last_value as u8])?;
+        file.write_all(&self.sum_value.to_be_bytes())?;
+
+        Ok(())
+    }
+}
+
+/// Statistics of TEXT series, only the first and the last value are stored
+#[derive(Clone, Debug)]
+pub struct BinaryStatistics {
+    ts_first: i64,
+    ts_last: i64,
+
+    first_value: Vec<u8>,
+    last_value: Vec<u8>,
+    count: u32,
+}
+
+impl BinaryStatistics {
+    pub(crate) fn new() -> BinaryStatistics {
+        BinaryStatistics {
+            ts_first: i64::MAX,
+            ts_last: i64::MIN,
+            first_value: vec![],
+            last_value: vec![],
+            count: 0,
+        }
+    }
+
+    pub(crate) fn get_serialized_size(&self) -> u32 {
+        // first and last value, each with its length as i32
+        size_var_u32(self.count) as u32
+            + 16
+            + 8
+            + self.first_value.len() as u32
+            + self.last_value.len() as u32
+    }
+
+    pub(crate) fn merge(&mut self, statistics: &BinaryStatistics) {
+        if statistics.ts_first < self.ts_first {
+            self.ts_first = statistics.ts_first;
+            self.first_value = statistics.first_value.clone();
+        }
+        if statistics.ts_last > self.ts_last {
+            self.ts_last = statistics.ts_last;
+            self.last_value = statistics.last_value.clone();
+        }
+        self.count += statistics.count;
+    }
+
+    pub(crate) fn deserialize(reader: &mut dyn Read) -> Result<BinaryStatistics, TsFileError> {
+        Ok(BinaryStatistics {
+            count: read_var_u32(reader)?,
+            ts_first: read_i64(reader)?,
+            ts_last: read_i64(reader)?,
+            first_value: read_binary(reader)?,
+            last_value: read_binary(reader)?,
+        })
+    }
+
+    pub(crate) fn update(&mut self, timestamp: i64, value: &[u8]) {
+        if timestamp < self.ts_first {
+            self.ts_first = timestamp;
+            self.first_value = value.to_vec();
+        }
+        if timestamp > self.ts_last {
+            self.ts_last = timestamp;
+            self.last_value = value.to_vec();
+        }
+        self.count += 1;
+    }
+}
+
+impl Serializable for BinaryStatistics {
+    fn serialize(&self, file: &mut dyn PositionedWrite) -> Result<(), TsFileError> {
+        write_var_u32(self.count, file)?;
+        file.write_all(&self.ts_first.to_be_bytes())?;
+        file.write_all(&self.ts_last.to_be_bytes())?;
+
+        file.write_all(&(self.first_value.len() as i32).to_be_bytes())?;
+        file.write_all(&self.first_value)?;
+        file.write_all(&(self.last_value.len() as i32).to_be_bytes())?;
+        file.write_all(&self.last_value)?;
+
+        Ok(())
+    }
+}
+
+/// Statistics of the time column of aligned devices, only contain count, start and end time
+#[derive(Copy, Clone, Debug)]
+pub struct TimeStatistics {
+    ts_first: i64,
+    ts_last: i64,
+    count: u32,
+}
+
+impl TimeStatistics {
+    pub(crate) fn new() -> TimeStatistics {
+        TimeStatistics {
+            ts_first: i64::MAX,
+            ts_last: i64::MIN,
+            count: 0,
+        }
+    }
+
+    pub(crate) fn get_serialized_size(&self) -> u32 {
+        size_var_u32(self.count) as u32 + 16
+    }
+
+    pub(crate) fn merge(&mut self, statistics: &TimeStatistics) {
+        self.ts_first = self.ts_first.min(statistics.ts_first);
+        self.ts_last = self.ts_last.max(statistics.ts_last);
+        self.count += statistics.count;
+    }
+
+    pub(crate) fn deserialize(reader: &mut dyn Read) -> Result<TimeStatistics, TsFileError> {
+        Ok(TimeStatistics {
+            count: read_var_u32(reader)?,
+            ts_first: read_i64(reader)?,
+            ts_last: read_i64(reader)?,
+        })
+    }
+
+    pub(crate) fn update(&mut self, timestamp: i64) {
+        self.ts_first = self.ts_first.min(timestamp);
+        self.ts_last = self.ts_last.max(timestamp);
+        self.count += 1;
+    }
+}
+
+impl Serializable for TimeStatistics {
+    fn serialize(&self, file: &mut dyn PositionedWrite) -> Result<(), TsFileError> {
+        write_var_u32(self.count, file)?;
+        file.write_all(&self.ts_first.to_be_bytes())?;
+        file.write_all(&self.ts_last.to_be_bytes())?;
+
+        Ok(())
+    }
+}
+
+/// Reads a binary that is prefixed with its length as i32
+fn read_binary(reader: &mut dyn Read) -> Result<Vec<u8>, TsFileError> {
+    let length = read_i32(reader)?;
+    if length < 0 {
+        return Err(TsFileError::ReadError {
+            source: Some(format!("Invalid binary length {}", length)),
+        });
+    }
+    let mut value = vec![0_u8; length as usize];
+    reader.read_exact(&mut value)?;
+    Ok(value)
+}
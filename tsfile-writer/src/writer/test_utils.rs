@@ -24,9 +24,9 @@ pub fn validate_output(filename: &str, expected_structure: &str) {
     assert_eq!(expected_structure, real)
 }
 
-pub fn write_ts_file<'a, F: FnOnce(&mut TsFileWriter<WriteWrapper<File>>)>(
-    filename: &'a str,
-    schema: Schema<'a>,
+pub fn write_ts_file<F: FnOnce(&mut TsFileWriter<WriteWrapper<File>>)>(
+    filename: &str,
+    schema: Schema,
     test_code: F,
 ) -> Result<(), TsFileError> {
     let mut writer = TsFileWriter::new(filename, schema, Default::default())?;
@@ -35,7 +35,7 @@ pub fn write_ts_file<'a, F: FnOnce(&mut TsFileWriter<WriteWrapper<File>>)>(
     test_code(&mut writer);
 
     // writer.flush();
-    writer.close();
+    writer.close()?;
 
     Ok(())
 }
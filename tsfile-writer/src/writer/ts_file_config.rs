@@ -1,3 +1,27 @@
+//! Contains the `TsFileConfig` and its `TsFileConfigBuilder`, which validates the settings
+//! and can read them from the `iotdb-common.properties` of IoTDB or, with the feature `toml`,
+//! a TOML file
+use crate::writer::chunk_writer::{
+    MAX_NUMBER_OF_POINTS_IN_PAGE, PAGE_SIZE_THRESHOLD, VALUE_COUNT_IN_ONE_PAGE_FOR_NEXT_CHECK,
+};
+use crate::writer::encoding::Encoder;
+use crate::writer::tsfile_writer::CHUNK_GROUP_SIZE_THRESHOLD_BYTE;
+use crate::writer::{CompressionType, TSDataType, TSEncoding, TsFileError};
+use std::path::Path;
+use std::str::FromStr;
+
+/// What happens to a point with the same timestamp as the last point of its series
+#[derive(PartialEq, Copy, Clone, Debug)]
+pub enum DuplicatePolicy {
+    /// The point is rejected with `TsFileError::OutOfOrderData`
+    Reject,
+    /// The point is ignored
+    KeepFirst,
+    /// The point replaces the last one. Only possible as long as the last point is not flushed,
+    /// later duplicates are rejected with `TsFileError::OutOfOrderData`
+    KeepLast,
+}
+
 #[derive(Clone, Copy)]
 pub struct TsFileConfig {
     pub(crate) max_degree_of_index_node: usize,
@@ -7,6 +31,28 @@ pub struct TsFileConfig {
     pub(crate) minimal_size: i32,
     pub(crate) maximal_hash_function_size: i32,
     pub(crate) seeds: [u8; 8],
+    /// Encoding of the timestamps of all series, IoTDB reads it from its `time_encoder` setting
+    pub(crate) time_encoding: TSEncoding,
+    /// Memory budget in bytes of the unsequence mode, None writes in sequence
+    pub(crate) unsequence_memory_budget: Option<u32>,
+    pub(crate) duplicate_policy: DuplicatePolicy,
+    /// Memory of all chunk groups in bytes that triggers a flush
+    pub(crate) group_size_in_byte: u32,
+    /// Memory of a page in bytes that seals it
+    pub(crate) page_size_in_byte: u32,
+    pub(crate) max_number_of_points_in_page: u32,
+    /// Number of points of a new page before its size is checked first
+    pub(crate) value_count_in_one_page_for_next_check: u32,
+    /// Unknown devices and measurements are registered on their first point
+    pub(crate) auto_create_schema: bool,
+    pub(crate) default_boolean_encoding: TSEncoding,
+    pub(crate) default_int32_encoding: TSEncoding,
+    pub(crate) default_int64_encoding: TSEncoding,
+    pub(crate) default_float_encoding: TSEncoding,
+    pub(crate) default_double_encoding: TSEncoding,
+    pub(crate) default_text_encoding: TSEncoding,
+    /// Compression of the series created by `auto_create_schema`
+    pub(crate) default_compression: CompressionType,
 }
 
 impl Default for TsFileConfig {
@@ -19,6 +65,516 @@ impl Default for TsFileConfig {
             minimal_size: 256,
             maximal_hash_function_size: 8,
             seeds: [5, 7, 11, 19, 31, 37, 43, 59],
+            time_encoding: TSEncoding::TS2DIFF,
+            unsequence_memory_budget: None,
+            duplicate_policy: DuplicatePolicy::Reject,
+            group_size_in_byte: CHUNK_GROUP_SIZE_THRESHOLD_BYTE,
+            page_size_in_byte: PAGE_SIZE_THRESHOLD,
+            max_number_of_points_in_page: MAX_NUMBER_OF_POINTS_IN_PAGE,
+            value_count_in_one_page_for_next_check: VALUE_COUNT_IN_ONE_PAGE_FOR_NEXT_CHECK,
+            auto_create_schema: false,
+            // The defaults of IoTDB
+            default_boolean_encoding: TSEncoding::RLE,
+            default_int32_encoding: TSEncoding::TS2DIFF,
+            default_int64_encoding: TSEncoding::TS2DIFF,
+            default_float_encoding: TSEncoding::GORILLA,
+            default_double_encoding: TSEncoding::GORILLA,
+            default_text_encoding: TSEncoding::PLAIN,
+            default_compression: CompressionType::SNAPPY,
+        }
+    }
+}
+
+impl TsFileConfig {
+    /// Sets the encoding of the timestamps, which has to support INT64 values,
+    /// i.e. TS2DIFF (the default), PLAIN, RLE, GORILLA, ZIGZAG, CHIMP or SPRINTZ
+    pub fn with_time_encoding(mut self, encoding: TSEncoding) -> Result<TsFileConfig, TsFileError> {
+        <dyn Encoder>::new(TSDataType::INT64, encoding)?;
+        self.time_encoding = encoding;
+        Ok(self)
+    }
+
+    /// Enables the unsequence mode: points may arrive out of order, they are buffered per
+    /// series and sorted on flush, duplicates are resolved by the `DuplicatePolicy`. The
+    /// buffers are flushed once they exceed `memory_budget` bytes. Points not after the last
+    /// flushed point of their series are rejected with `TsFileError::OutOfOrderData`, unless
+    /// they are duplicates ignored by `DuplicatePolicy::KeepFirst`.
+    pub fn with_unsequence_buffer(mut self, memory_budget: u32) -> TsFileConfig {
+        self.unsequence_memory_budget = Some(memory_budget);
+        self
+    }
+
+    /// Sets how points with the timestamp of the last point of their series are handled,
+    /// default is `DuplicatePolicy::Reject`. In the unsequence mode the policy also resolves
+    /// points with the timestamp of a buffered point.
+    pub fn with_duplicate_policy(mut self, policy: DuplicatePolicy) -> TsFileConfig {
+        self.duplicate_policy = policy;
+        self
+    }
+
+    /// Enables the automatic creation of unknown devices and measurements on write. The data
+    /// type is taken from the first value, the encoding and compression from the defaults
+    /// (see `TsFileConfigBuilder::default_encoding` and `default_compression`).
+    pub fn with_auto_create_schema(mut self) -> TsFileConfig {
+        self.auto_create_schema = true;
+        self
+    }
+
+    /// Encoding of series of the data type created by `auto_create_schema`
+    pub(crate) fn default_encoding(&self, data_type: TSDataType) -> TSEncoding {
+        match data_type {
+            TSDataType::BOOLEAN => self.default_boolean_encoding,
+            TSDataType::INT32 => self.default_int32_encoding,
+            TSDataType::INT64 => self.default_int64_encoding,
+            TSDataType::FLOAT => self.default_float_encoding,
+            TSDataType::DOUBLE => self.default_double_encoding,
+            TSDataType::TEXT => self.default_text_encoding,
+            TSDataType::VECTOR => TSEncoding::PLAIN,
+        }
+    }
+}
+
+/// Builds a validated `TsFileConfig`, settings that are not set keep their default
+pub struct TsFileConfigBuilder {
+    config: TsFileConfig,
+}
+
+impl TsFileConfigBuilder {
+    pub fn new() -> TsFileConfigBuilder {
+        TsFileConfigBuilder {
+            config: TsFileConfig::default(),
+        }
+    }
+
+    /// Reads the settings from the content of a properties file like `iotdb-common.properties`.
+    /// As in Java properties, a key ends at the first `=`, `:` or whitespace, which may be
+    /// surrounded by whitespace, lines starting with `#` or `!` are comments and the rest of
+    /// a line belongs to the value. Escapes and line continuations are not supported.
+    /// The keys are the ones of IoTDB: `group_size_in_byte`, `page_size_in_byte`,
+    /// `max_number_of_points_in_page`, `max_degree_of_index_node`, `bloom_filter_error_rate`,
+    /// `time_encoder`, `enable_auto_create_schema`, `default_boolean_encoding` (and the other
+    /// data types) and `compressor`, plus `value_count_in_one_page_for_next_check`. Other keys
+    /// are ignored.
+    pub fn from_properties(content: &str) -> Result<TsFileConfigBuilder, TsFileError> {
+        let mut builder = TsFileConfigBuilder::new();
+        for line in content.lines() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with(['#', '!']) {
+                continue;
+            }
+            let key_end = line
+                .find(|c: char| c == '=' || c == ':' || c.is_whitespace())
+                .unwrap_or(line.len());
+            let (key, value) = line.split_at(key_end);
+            let value = value.trim_start();
+            let value = value.strip_prefix(['=', ':']).unwrap_or(value);
+            builder.set(key, value.trim_start())?;
+        }
+        Ok(builder)
+    }
+
+    /// Reads the settings from the content of a TOML file with the keys of `from_properties`.
+    /// Only the keys at the top level are read, tables are ignored.
+    #[cfg(feature = "toml")]
+    pub fn from_toml(content: &str) -> Result<TsFileConfigBuilder, TsFileError> {
+        let table = match content.parse::<toml::Value>() {
+            Ok(toml::Value::Table(table)) => table,
+            Ok(_) => return Err(invalid("The TOML content is no table")),
+            Err(e) => return Err(invalid(&format!("Invalid TOML: {}", e))),
+        };
+        let mut builder = TsFileConfigBuilder::new();
+        for (key, value) in &table {
+            let value = match value {
+                toml::Value::String(s) => s.clone(),
+                toml::Value::Integer(i) => i.to_string(),
+                toml::Value::Float(f) => f.to_string(),
+                toml::Value::Boolean(b) => b.to_string(),
+                _ => continue,
+            };
+            builder.set(key, &value)?;
+        }
+        Ok(builder)
+    }
+
+    /// Like `from_toml` with the content of the file if its extension is `toml` (requires the
+    /// feature `toml`), otherwise like `from_properties`
+    pub fn from_file(path: impl AsRef<Path>) -> Result<TsFileConfigBuilder, TsFileError> {
+        let path = path.as_ref();
+        let content = std::fs::read_to_string(path)?;
+        match path.extension() {
+            #[cfg(feature = "toml")]
+            Some(extension) if extension == "toml" => Self::from_toml(&content),
+            #[cfg(not(feature = "toml"))]
+            Some(extension) if extension == "toml" => {
+                Err(invalid("Reading TOML files requires the feature toml"))
+            }
+            _ => Self::from_properties(&content),
+        }
+    }
+
+    /// Applies the setting of a key of `from_properties`, unknown keys are ignored
+    fn set(&mut self, key: &str, value: &str) -> Result<&mut TsFileConfigBuilder, TsFileError> {
+        Ok(match key {
+            "group_size_in_byte" => self.group_size_in_byte(parse(key, value)?),
+            "page_size_in_byte" => self.page_size_in_byte(parse(key, value)?),
+            "max_number_of_points_in_page" => self.max_number_of_points_in_page(parse(key, value)?),
+            "value_count_in_one_page_for_next_check" => {
+                self.value_count_in_one_page_for_next_check(parse(key, value)?)
+            }
+            "max_degree_of_index_node" => self.max_degree_of_index_node(parse(key, value)?),
+            "bloom_filter_error_rate" => self.bloom_filter_error_rate(parse(key, value)?),
+            "time_encoder" => self.time_encoding(parse_encoding(value)?),
+            "enable_auto_create_schema" => self.auto_create_schema(parse(key, value)?),
+            "default_boolean_encoding" => {
+                self.default_encoding(TSDataType::BOOLEAN, parse_encoding(value)?)
+            }
+            "default_int32_encoding" => {
+                self.default_encoding(TSDataType::INT32, parse_encoding(value)?)
+            }
+            "default_int64_encoding" => {
+                self.default_encoding(TSDataType::INT64, parse_encoding(value)?)
+            }
+            "default_float_encoding" => {
+                self.default_encoding(TSDataType::FLOAT, parse_encoding(value)?)
+            }
+            "default_double_encoding" => {
+                self.default_encoding(TSDataType::DOUBLE, parse_encoding(value)?)
+            }
+            "default_text_encoding" => {
+                self.default_encoding(TSDataType::TEXT, parse_encoding(value)?)
+            }
+            "compressor" => self.default_compression(parse_compression(value)?),
+            _ => self,
+        })
+    }
+
+    /// Memory of all chunk groups in bytes that triggers a flush, default is 128 MiB
+    pub fn group_size_in_byte(&mut self, size: u32) -> &mut TsFileConfigBuilder {
+        self.config.group_size_in_byte = size;
+        self
+    }
+
+    /// Memory of a page in bytes that seals it, default is 64 KiB
+    pub fn page_size_in_byte(&mut self, size: u32) -> &mut TsFileConfigBuilder {
+        self.config.page_size_in_byte = size;
+        self
+    }
+
+    /// Number of points that seals a page, default is 1048576
+    pub fn max_number_of_points_in_page(&mut self, count: u32) -> &mut TsFileConfigBuilder {
+        self.config.max_number_of_points_in_page = count;
+        self
+    }
+
+    /// Number of points of a new page before its size is checked first, default is 7989.
+    /// Not used for TEXT series: their values have variable length, so like in IoTDB their
+    /// pages are checked after the first point.
+    pub fn value_count_in_one_page_for_next_check(
+        &mut self,
+        count: u32,
+    ) -> &mut TsFileConfigBuilder {
+        self.config.value_count_in_one_page_for_next_check = count;
+        self
+    }
+
+    /// Maximal number of children of a node of the metadata index, default is 256
+    pub fn max_degree_of_index_node(&mut self, degree: usize) -> &mut TsFileConfigBuilder {
+        self.config.max_degree_of_index_node = degree;
+        self
+    }
+
+    /// False positive rate of the bloom filter between 0.01 and 0.1, default is 0.05
+    pub fn bloom_filter_error_rate(&mut self, rate: f64) -> &mut TsFileConfigBuilder {
+        self.config.bloom_filter_error_rate = rate;
+        self
+    }
+
+    /// See `TsFileConfig::with_time_encoding`
+    pub fn time_encoding(&mut self, encoding: TSEncoding) -> &mut TsFileConfigBuilder {
+        self.config.time_encoding = encoding;
+        self
+    }
+
+    /// See `TsFileConfig::with_unsequence_buffer`
+    pub fn unsequence_buffer(&mut self, memory_budget: u32) -> &mut TsFileConfigBuilder {
+        self.config.unsequence_memory_budget = Some(memory_budget);
+        self
+    }
+
+    /// See `TsFileConfig::with_duplicate_policy`
+    pub fn duplicate_policy(&mut self, policy: DuplicatePolicy) -> &mut TsFileConfigBuilder {
+        self.config.duplicate_policy = policy;
+        self
+    }
+
+    /// See `TsFileConfig::with_auto_create_schema`
+    pub fn auto_create_schema(&mut self, enabled: bool) -> &mut TsFileConfigBuilder {
+        self.config.auto_create_schema = enabled;
+        self
+    }
+
+    /// Encoding of automatically created series of the data type. The defaults are the ones
+    /// of IoTDB: RLE for BOOLEAN, TS2DIFF for INT32 and INT64, GORILLA for FLOAT and DOUBLE
+    /// and PLAIN for TEXT.
+    pub fn default_encoding(
+        &mut self,
+        data_type: TSDataType,
+        encoding: TSEncoding,
+    ) -> &mut TsFileConfigBuilder {
+        match data_type {
+            TSDataType::BOOLEAN => self.config.default_boolean_encoding = encoding,
+            TSDataType::INT32 => self.config.default_int32_encoding = encoding,
+            TSDataType::INT64 => self.config.default_int64_encoding = encoding,
+            TSDataType::FLOAT => self.config.default_float_encoding = encoding,
+            TSDataType::DOUBLE => self.config.default_double_encoding = encoding,
+            TSDataType::TEXT => self.config.default_text_encoding = encoding,
+            TSDataType::VECTOR => {}
+        }
+        self
+    }
+
+    /// Compression of automatically created series, default is SNAPPY
+    pub fn default_compression(
+        &mut self,
+        compression: CompressionType,
+    ) -> &mut TsFileConfigBuilder {
+        self.config.default_compression = compression;
+        self
+    }
+
+    pub fn build(&mut self) -> Result<TsFileConfig, TsFileError> {
+        let config = self.config;
+        if config.group_size_in_byte == 0 {
+            return Err(invalid("group_size_in_byte has to be positive"));
+        }
+        if config.page_size_in_byte == 0 || config.page_size_in_byte > config.group_size_in_byte {
+            return Err(invalid(
+                "page_size_in_byte has to be positive and at most group_size_in_byte",
+            ));
+        }
+        if config.max_number_of_points_in_page == 0 {
+            return Err(invalid("max_number_of_points_in_page has to be positive"));
+        }
+        if config.value_count_in_one_page_for_next_check == 0 {
+            return Err(invalid(
+                "value_count_in_one_page_for_next_check has to be positive",
+            ));
         }
+        if config.max_degree_of_index_node < 2 {
+            return Err(invalid("max_degree_of_index_node has to be at least 2"));
+        }
+        if !(config.min_bloom_filter_error_rate..=config.max_bloom_filter_error_rate)
+            .contains(&config.bloom_filter_error_rate)
+        {
+            return Err(invalid(
+                "bloom_filter_error_rate has to be between 0.01 and 0.1",
+            ));
+        }
+        for data_type in [
+            TSDataType::BOOLEAN,
+            TSDataType::INT32,
+            TSDataType::INT64,
+            TSDataType::FLOAT,
+            TSDataType::DOUBLE,
+            TSDataType::TEXT,
+        ] {
+            <dyn Encoder>::new(data_type, config.default_encoding(data_type))?;
+        }
+        config.with_time_encoding(config.time_encoding)
+    }
+}
+
+impl Default for TsFileConfigBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn invalid(message: &str) -> TsFileError {
+    TsFileError::IllegalState {
+        source: Some(message.to_owned()),
+    }
+}
+
+fn parse<T: FromStr>(key: &str, value: &str) -> Result<T, TsFileError> {
+    value
+        .parse()
+        .map_err(|_| invalid(&format!("Invalid value {} of {}", value, key)))
+}
+
+/// Parses the names of IoTDB, e.g. TS_2DIFF
+fn parse_encoding(value: &str) -> Result<TSEncoding, TsFileError> {
+    match value.to_uppercase().as_str() {
+        "PLAIN" => Ok(TSEncoding::PLAIN),
+        "RLE" => Ok(TSEncoding::RLE),
+        "TS_2DIFF" | "TS2DIFF" => Ok(TSEncoding::TS2DIFF),
+        "DICTIONARY" => Ok(TSEncoding::DICTIONARY),
+        "GORILLA" => Ok(TSEncoding::GORILLA),
+        "ZIGZAG" => Ok(TSEncoding::ZIGZAG),
+        "CHIMP" => Ok(TSEncoding::CHIMP),
+        "SPRINTZ" => Ok(TSEncoding::SPRINTZ),
+        _ => Err(invalid(&format!("Unknown encoding {}", value))),
+    }
+}
+
+/// Parses the names of IoTDB, the types without their cargo feature are unknown
+fn parse_compression(value: &str) -> Result<CompressionType, TsFileError> {
+    match value.to_uppercase().as_str() {
+        "UNCOMPRESSED" => Ok(CompressionType::UNCOMPRESSED),
+        "SNAPPY" => Ok(CompressionType::SNAPPY),
+        #[cfg(feature = "gzip")]
+        "GZIP" => Ok(CompressionType::GZIP),
+        #[cfg(feature = "lz4")]
+        "LZ4" => Ok(CompressionType::LZ4),
+        #[cfg(feature = "zstd")]
+        "ZSTD" => Ok(CompressionType::ZSTD),
+        #[cfg(feature = "lzma2")]
+        "LZMA2" => Ok(CompressionType::LZMA2),
+        _ => Err(invalid(&format!("Unknown compression {}", value))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::writer::ts_file_config::{DuplicatePolicy, TsFileConfig, TsFileConfigBuilder};
+    use crate::writer::{CompressionType, TSDataType, TSEncoding, TsFileError};
+
+    #[test]
+    fn build_defaults() -> Result<(), TsFileError> {
+        let config = TsFileConfigBuilder::new().build()?;
+        let default = TsFileConfig::default();
+        assert_eq!(default.group_size_in_byte, config.group_size_in_byte);
+        assert_eq!(default.page_size_in_byte, config.page_size_in_byte);
+        assert_eq!(default.time_encoding, config.time_encoding);
+        Ok(())
+    }
+
+    #[test]
+    fn with_time_encoding() -> Result<(), TsFileError> {
+        let config = TsFileConfig::default().with_time_encoding(TSEncoding::GORILLA)?;
+        assert_eq!(TSEncoding::GORILLA, config.time_encoding);
+        // Timestamps are INT64, which DICTIONARY does not support
+        assert_eq!(
+            Some(TsFileError::Encoding),
+            TsFileConfig::default()
+                .with_time_encoding(TSEncoding::DICTIONARY)
+                .err()
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn build_validates() {
+        assert!(TsFileConfigBuilder::new()
+            .group_size_in_byte(0)
+            .build()
+            .is_err());
+        assert!(TsFileConfigBuilder::new()
+            .group_size_in_byte(1024)
+            .page_size_in_byte(2048)
+            .build()
+            .is_err());
+        assert!(TsFileConfigBuilder::new()
+            .max_number_of_points_in_page(0)
+            .build()
+            .is_err());
+        assert!(TsFileConfigBuilder::new()
+            .max_degree_of_index_node(1)
+            .build()
+            .is_err());
+        assert!(TsFileConfigBuilder::new()
+            .bloom_filter_error_rate(0.5)
+            .build()
+            .is_err());
+        assert!(TsFileConfigBuilder::new()
+            .time_encoding(TSEncoding::DICTIONARY)
+            .build()
+            .is_err());
+        assert!(TsFileConfigBuilder::new()
+            .default_encoding(TSDataType::BOOLEAN, TSEncoding::GORILLA)
+            .build()
+            .is_err());
+    }
+
+    #[test]
+    fn from_properties() -> Result<(), TsFileError> {
+        let content = "\
+# Comment
+group_size_in_byte=1048576
+page_size_in_byte = 4096
+max_number_of_points_in_page: 1000
+max_degree_of_index_node 64
+time_encoder=GORILLA
+bloom_filter_error_rate=0.01
+unknown_key=value
+enable_auto_create_schema=true
+default_int32_encoding=RLE
+compressor=UNCOMPRESSED
+";
+        let config = TsFileConfigBuilder::from_properties(content)?
+            .duplicate_policy(DuplicatePolicy::KeepLast)
+            .build()?;
+        assert_eq!(1048576, config.group_size_in_byte);
+        assert_eq!(4096, config.page_size_in_byte);
+        assert_eq!(1000, config.max_number_of_points_in_page);
+        assert_eq!(64, config.max_degree_of_index_node);
+        assert_eq!(TSEncoding::GORILLA, config.time_encoding);
+        assert_eq!(0.01, config.bloom_filter_error_rate);
+        assert_eq!(DuplicatePolicy::KeepLast, config.duplicate_policy);
+        assert!(config.auto_create_schema);
+        assert_eq!(TSEncoding::RLE, config.default_encoding(TSDataType::INT32));
+        assert_eq!(CompressionType::UNCOMPRESSED, config.default_compression);
+        Ok(())
+    }
+
+    #[test]
+    fn from_properties_keeps_rest_of_line() {
+        // Like in Java properties there are no comments at the end of a line
+        let result = TsFileConfigBuilder::from_properties("page_size_in_byte=4096 # 4 KiB");
+        assert!(result.is_err());
+        let result = TsFileConfigBuilder::from_properties("time_encoder=\"TS_2DIFF\"");
+        assert!(result.is_err());
+        // Only the first separator ends the key
+        let result = TsFileConfigBuilder::from_properties("time_encoder = = GORILLA");
+        assert!(result.is_err());
+    }
+
+    #[test]
+    #[cfg(feature = "toml")]
+    fn from_toml() -> Result<(), TsFileError> {
+        let content = r#"
+group_size_in_byte = 2_097_152 # 2 MiB
+time_encoder = "TS_2DIFF" # "GORILLA"
+max_degree_of_index_node = 64
+enable_auto_create_schema = true
+bloom_filter_error_rate = 0.01
+
+[other]
+page_size_in_byte = 0
+"#;
+        let config = TsFileConfigBuilder::from_toml(content)?.build()?;
+        assert_eq!(2097152, config.group_size_in_byte);
+        assert_eq!(TSEncoding::TS2DIFF, config.time_encoding);
+        assert_eq!(64, config.max_degree_of_index_node);
+        assert!(config.auto_create_schema);
+        assert_eq!(0.01, config.bloom_filter_error_rate);
+        assert_eq!(
+            TsFileConfig::default().page_size_in_byte,
+            config.page_size_in_byte
+        );
+        Ok(())
+    }
+
+    #[test]
+    #[cfg(feature = "toml")]
+    fn from_toml_invalid() {
+        assert!(TsFileConfigBuilder::from_toml("group_size_in_byte = ").is_err());
+        assert!(TsFileConfigBuilder::from_toml("group_size_in_byte = -1").is_err());
+    }
+
+    #[test]
+    fn from_properties_invalid_value() {
+        assert!(TsFileConfigBuilder::from_properties("page_size_in_byte=large").is_err());
+        assert!(TsFileConfigBuilder::from_properties("time_encoder=UNKNOWN").is_err());
     }
 }
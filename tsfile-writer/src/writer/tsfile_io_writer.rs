@@ -7,17 +7,34 @@ use crate::writer::{
 };
 use std::collections::BTreeMap;
 
-pub struct TsFileIoWriter<'a, T: PositionedWrite> {
+pub struct TsFileIoWriter<T: PositionedWrite> {
     config: TsFileConfig,
     pub(crate) out: T,
-    current_chunk_group_device_id: Option<&'a str>,
+    current_chunk_group_device_id: Option<String>,
     chunk_metadata_list: Vec<ChunkMetadata>,
     current_chunk_metadata: Option<ChunkMetadata>,
     chunk_group_metadata_list: Vec<ChunkGroupMetadata>,
     timeseries_metadata_map: BTreeMap<String, Vec<Box<dyn TimeSeriesMetadatable>>>,
 }
 
-impl<'a, T: PositionedWrite> TsFileIoWriter<'a, T> {
+impl<T: PositionedWrite> TsFileIoWriter<T> {
+    /// Last timestamp of every series in the chunks written so far, by device and
+    /// measurement id, e.g. the ones of a recovered file
+    pub(crate) fn last_time_map(&self) -> BTreeMap<String, BTreeMap<String, i64>> {
+        let mut result: BTreeMap<String, BTreeMap<String, i64>> = BTreeMap::new();
+        for chunk_group in &self.chunk_group_metadata_list {
+            let last_times = result.entry(chunk_group.device_id.clone()).or_default();
+            for chunk in &chunk_group.chunk_metadata {
+                let end_time = chunk.statistics.end_time();
+                let last_time = last_times
+                    .entry(chunk.measurement_id.clone())
+                    .or_insert(end_time);
+                *last_time = end_time.max(*last_time);
+            }
+        }
+        result
+    }
+
     pub(crate) fn end_current_chunk(&mut self) {
         match &self.current_chunk_metadata {
             None => {
@@ -31,7 +48,7 @@ impl<'a, T: PositionedWrite> TsFileIoWriter<'a, T> {
     }
 }
 
-impl<'a, T: PositionedWrite> TsFileIoWriter<'a, T> {
+impl<T: PositionedWrite> TsFileIoWriter<T> {
     #[allow(clippy::too_many_arguments)]
     pub(crate) fn start_flush_chunk(
         &mut self,
@@ -64,11 +81,8 @@ impl<'a, T: PositionedWrite> TsFileIoWriter<'a, T> {
     }
 }
 
-impl<'a, T: PositionedWrite> TsFileIoWriter<'a, T> {
-    pub(crate) fn new(
-        writer: T,
-        config: TsFileConfig,
-    ) -> Result<TsFileIoWriter<'a, T>, TsFileError> {
+impl<T: PositionedWrite> TsFileIoWriter<T> {
+    pub(crate) fn new(writer: T, config: TsFileConfig) -> Result<TsFileIoWriter<T>, TsFileError> {
         let mut io_writer = TsFileIoWriter {
             config,
             out: writer,
@@ -82,13 +96,31 @@ impl<'a, T: PositionedWrite> TsFileIoWriter<'a, T> {
         Ok(io_writer)
     }
 
+    /// Continues a file whose data section up to the position of the writer contains the
+    /// given chunk groups, e.g. one recovered by the `RestorableTsFileIoWriter`
+    pub(crate) fn restore(
+        writer: T,
+        config: TsFileConfig,
+        chunk_group_metadata_list: Vec<ChunkGroupMetadata>,
+    ) -> TsFileIoWriter<T> {
+        TsFileIoWriter {
+            config,
+            out: writer,
+            current_chunk_group_device_id: None,
+            chunk_metadata_list: vec![],
+            current_chunk_metadata: None,
+            chunk_group_metadata_list,
+            timeseries_metadata_map: BTreeMap::new(),
+        }
+    }
+
     fn start_file(&mut self) -> Result<(), TsFileError> {
         self.out.write_all("TsFile".as_bytes())?;
         self.out.write_all(&[0x03])?;
         Ok(())
     }
 
-    pub(crate) fn start_chunk_group(&mut self, device_id: &'a str) -> Result<(), TsFileError> {
+    pub(crate) fn start_chunk_group(&mut self, device_id: &str) -> Result<(), TsFileError> {
         log::info!(
             "Start chunk group:{}, file position {}",
             &device_id,
@@ -97,7 +129,7 @@ impl<'a, T: PositionedWrite> TsFileIoWriter<'a, T> {
         let chunk_group_header = ChunkGroupHeader::new(device_id);
         chunk_group_header.serialize(&mut self.out)?;
 
-        self.current_chunk_group_device_id = Some(device_id);
+        self.current_chunk_group_device_id = Some(device_id.to_owned());
         self.chunk_metadata_list.clear();
         Ok(())
     }
@@ -113,20 +145,19 @@ impl<'a, T: PositionedWrite> TsFileIoWriter<'a, T> {
     //     out.flush();
     //   }
     pub(crate) fn end_chunk_group(&mut self) {
-        if self.current_chunk_group_device_id == None || self.chunk_metadata_list.is_empty() {
-            return;
-        }
-        let device_id = self.current_chunk_group_device_id.unwrap();
+        let device_id = match self.current_chunk_group_device_id.take() {
+            Some(device_id) if !self.chunk_metadata_list.is_empty() => device_id,
+            _ => return,
+        };
         // for chunk_metadata in &self.chunk_metadata_list {
         //     self.chunk_group_metadata_list.get_mut(device_id.as_str()).unwrap().push(
         //         chunk_metadata.clone()
         //     )
         // }
         self.chunk_group_metadata_list.push(ChunkGroupMetadata::new(
-            device_id.into(),
+            device_id,
             self.chunk_metadata_list.clone(),
         ));
-        self.current_chunk_group_device_id = None;
         self.chunk_metadata_list.clear();
         self.out.flush();
     }
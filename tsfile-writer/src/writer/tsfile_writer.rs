@@ -1,29 +1,40 @@
 //! Contains the TsFileWriter as central class to write tsfiles
-use crate::writer::chunk_writer::ChunkWriter;
+use crate::writer::encoding::Encoder;
 use crate::writer::errors::TsFileError;
 use crate::writer::group_writer::GroupWriter;
 use crate::writer::ts_file_config::TsFileConfig;
 use crate::writer::tsfile_io_writer::TsFileIoWriter;
 use crate::writer::{
-    ChunkGroupMetadata, IoTDBValue, PositionedWrite, Schema, TimeSeriesMetadatable, WriteWrapper,
+    ChunkGroupMetadata, CompressionType, IoTDBValue, MeasurementGroup, MeasurementSchema,
+    PositionedWrite, Schema, TimeSeriesMetadatable, WriteWrapper,
 };
-use std::borrow::Borrow;
 use std::collections::{BTreeMap, HashMap};
 use std::fs::{create_dir_all, File};
 
-const CHUNK_GROUP_SIZE_THRESHOLD_BYTE: u32 = 128 * 1024 * 1024;
+pub(crate) const CHUNK_GROUP_SIZE_THRESHOLD_BYTE: u32 = 128 * 1024 * 1024;
 
 /// Class defined to hold a datapoint for writing into a given device.
-/// Consists of a measurement_id and a value
-pub struct DataPoint<'a> {
-    pub(crate) measurement_id: &'a str,
-    pub(crate) value: IoTDBValue,
+/// Consists of a measurement_id and a value, which may be null
+pub struct DataPoint {
+    pub(crate) measurement_id: String,
+    pub(crate) value: Option<IoTDBValue>,
 }
 
-impl<'a> DataPoint<'a> {
-    pub fn new(measurement_id: &'a str, value: IoTDBValue) -> DataPoint<'a> {
+impl DataPoint {
+    pub fn new(measurement_id: impl Into<String>, value: IoTDBValue) -> DataPoint {
+        Self::from_option(measurement_id, Some(value))
+    }
+
+    /// A missing value, it is skipped for non aligned devices and written as null
+    /// for aligned devices
+    pub fn null(measurement_id: impl Into<String>) -> DataPoint {
+        Self::from_option(measurement_id, None)
+    }
+
+    /// A datapoint that is null if the value is None
+    pub fn from_option(measurement_id: impl Into<String>, value: Option<IoTDBValue>) -> DataPoint {
         Self {
-            measurement_id,
+            measurement_id: measurement_id.into(),
             value,
         }
     }
@@ -102,39 +113,43 @@ impl<'a> DataPoint<'a> {
 /// writer.write("d2", "s1", 1, IoTDBValue::LONG(14));
 /// writer.write("d2", "s2", 1, IoTDBValue::FLOAT(14.0 as f32));
 /// ```
-pub struct TsFileWriter<'a, T: PositionedWrite> {
+pub struct TsFileWriter<T: PositionedWrite> {
     #[allow(dead_code)]
     filename: String,
-    pub(crate) file_io_writer: TsFileIoWriter<'a, T>,
-    group_writers: BTreeMap<&'a str, GroupWriter<'a>>,
+    pub(crate) file_io_writer: TsFileIoWriter<T>,
+    group_writers: BTreeMap<String, GroupWriter>,
     #[allow(dead_code)]
     chunk_group_metadata: Vec<ChunkGroupMetadata>,
     #[allow(dead_code)]
     timeseries_metadata_map: HashMap<String, Vec<Box<dyn TimeSeriesMetadatable>>>,
     record_count: u32,
     record_count_for_next_mem_check: u32,
-    non_aligned_timeseries_last_time_map: BTreeMap<&'a str, BTreeMap<&'a str, i64>>,
-    pub schema: Schema<'a>,
+    non_aligned_timeseries_last_time_map: BTreeMap<String, BTreeMap<String, i64>>,
+    pub schema: Schema,
     #[allow(dead_code)]
     config: TsFileConfig,
 }
 
-impl<'a, T: PositionedWrite> TsFileWriter<'a, T> {
-    pub fn close(&mut self) {
+impl<T: PositionedWrite> TsFileWriter<T> {
+    pub fn close(&mut self) -> Result<(), TsFileError> {
         log::info!("start close file");
-        self.flush_all_chunk_groups();
+        self.flush_all_chunk_groups()?;
         self.file_io_writer.end_file();
+        Ok(())
     }
 }
 
-impl<'a, T: PositionedWrite> TsFileWriter<'a, T> {
+impl<T: PositionedWrite> TsFileWriter<T> {
     pub fn write(
         &mut self,
-        device: &'a str,
-        measurement_id: &'a str,
+        device: &str,
+        measurement_id: &str,
         timestamp: i64,
         value: IoTDBValue,
     ) -> Result<(), TsFileError> {
+        if self.config.auto_create_schema {
+            self.auto_create_timeseries(device, measurement_id, &value)?;
+        }
         match self.group_writers.get_mut(device) {
             Some(group) => {
                 let records_written = group.write(measurement_id, timestamp, value)?;
@@ -146,15 +161,44 @@ impl<'a, T: PositionedWrite> TsFileWriter<'a, T> {
                 });
             }
         }
-        self.check_memory_size_and_may_flush_chunks();
+        self.check_unsequence_buffer_and_may_flush()?;
+        self.check_memory_size_and_may_flush_chunks()?;
         Ok(())
     }
 
+    /// Writes the values of a device at one timestamp. With `auto_create_schema` the data
+    /// type of a null is unknown, so nulls of unknown series are dropped and values that are
+    /// all nulls of an unknown device return `Ok(())` without writing anything.
     pub fn write_many(
         &mut self,
-        device: &'a str,
+        device: &str,
+        timestamp: i64,
+        values: impl IntoIterator<Item = DataPoint>,
+    ) -> Result<(), TsFileError> {
+        if self.config.auto_create_schema {
+            let mut values: Vec<DataPoint> = values.into_iter().collect();
+            for dp in values.iter() {
+                if let Some(value) = &dp.value {
+                    self.auto_create_timeseries(device, &dp.measurement_id, value)?;
+                }
+            }
+            let group = match self.schema.measurement_groups.get(device) {
+                Some(group) => group,
+                None => return Ok(()),
+            };
+            values.retain(|dp| {
+                dp.value.is_some() || group.measurement_schemas.contains_key(&dp.measurement_id)
+            });
+            return self.write_group(device, timestamp, values);
+        }
+        self.write_group(device, timestamp, values)
+    }
+
+    fn write_group(
+        &mut self,
+        device: &str,
         timestamp: i64,
-        values: impl IntoIterator<Item = DataPoint<'a>>,
+        values: impl IntoIterator<Item = DataPoint>,
     ) -> Result<(), TsFileError> {
         match self.group_writers.get_mut(device) {
             Some(group) => {
@@ -163,33 +207,185 @@ impl<'a, T: PositionedWrite> TsFileWriter<'a, T> {
             }
             None => return Err(TsFileError::IllegalState { source: None }),
         }
-        self.check_memory_size_and_may_flush_chunks();
+        self.check_unsequence_buffer_and_may_flush()?;
+        self.check_memory_size_and_may_flush_chunks()?;
+        Ok(())
+    }
+
+    /// Adds a device to the schema of the file, its points can be written right away
+    pub fn register_device(
+        &mut self,
+        device: impl Into<String>,
+        measurement_group: MeasurementGroup,
+    ) -> Result<(), TsFileError> {
+        let device = device.into();
+        if self.schema.measurement_groups.contains_key(&device) {
+            return Err(TsFileError::IllegalState {
+                source: Some(format!("Device {} is already registered", device)),
+            });
+        }
+        for (measurement_id, measurement_schema) in measurement_group.measurement_schemas.iter() {
+            Self::check_encoding(measurement_id, measurement_schema)?;
+        }
+        // The device may have points in the chunk groups of a recovered file
+        let last_times = self
+            .non_aligned_timeseries_last_time_map
+            .get(&device)
+            .cloned()
+            .unwrap_or_default();
+        let group_writer = GroupWriter::new(&device, &measurement_group, &self.config, last_times);
+        self.group_writers.insert(device.clone(), group_writer);
+        self.schema
+            .measurement_groups
+            .insert(device, measurement_group);
+        Ok(())
+    }
+
+    /// Adds a series to a registered device. The new column of an aligned device would be
+    /// missing in its buffered rows, so all chunk groups are flushed first in that case.
+    pub fn register_timeseries(
+        &mut self,
+        device: &str,
+        measurement_id: impl Into<String>,
+        measurement_schema: MeasurementSchema,
+    ) -> Result<(), TsFileError> {
+        let measurement_id = measurement_id.into();
+        let aligned = match self.schema.measurement_groups.get(device) {
+            Some(group) if group.measurement_schemas.contains_key(&measurement_id) => {
+                return Err(TsFileError::IllegalState {
+                    source: Some(format!(
+                        "Timeseries {}.{} is already registered",
+                        device, measurement_id
+                    )),
+                });
+            }
+            Some(group) => group.is_aligned(),
+            None => {
+                return Err(TsFileError::IllegalState {
+                    source: Some(format!("Unknown device {}", device)),
+                });
+            }
+        };
+        Self::check_encoding(&measurement_id, &measurement_schema)?;
+        if aligned {
+            self.flush_all_chunk_groups()?;
+        }
+        let (group, group_writer) = match (
+            self.schema.measurement_groups.get_mut(device),
+            self.group_writers.get_mut(device),
+        ) {
+            (Some(group), Some(group_writer)) => (group, group_writer),
+            _ => {
+                return Err(TsFileError::IllegalState {
+                    source: Some(format!("No group writer for device {}", device)),
+                });
+            }
+        };
+        match aligned {
+            true => {
+                group
+                    .measurement_schemas
+                    .insert(measurement_id, measurement_schema);
+                let last_time_map = group_writer.get_last_time_map();
+                *group_writer = GroupWriter::new(device, group, &self.config, last_time_map);
+            }
+            false => {
+                group_writer.add_timeseries(&measurement_id, &measurement_schema, &self.config)?;
+                group
+                    .measurement_schemas
+                    .insert(measurement_id, measurement_schema);
+            }
+        }
         Ok(())
     }
 
+    fn check_encoding(
+        measurement_id: &str,
+        measurement_schema: &MeasurementSchema,
+    ) -> Result<(), TsFileError> {
+        match <dyn Encoder>::new(measurement_schema.data_type, measurement_schema.encoding) {
+            Ok(_) => Ok(()),
+            Err(_) => Err(TsFileError::IllegalState {
+                source: Some(format!(
+                    "Encoding {:?} does not support the data type {:?} of {}",
+                    measurement_schema.encoding, measurement_schema.data_type, measurement_id
+                )),
+            }),
+        }
+    }
+
+    /// Registers the device and series of a point if they are unknown, the data type is
+    /// taken from the value and the encoding and compression from the config
+    fn auto_create_timeseries(
+        &mut self,
+        device: &str,
+        measurement_id: &str,
+        value: &IoTDBValue,
+    ) -> Result<(), TsFileError> {
+        let device_known = match self.schema.measurement_groups.get(device) {
+            Some(group) if group.measurement_schemas.contains_key(measurement_id) => return Ok(()),
+            Some(_) => true,
+            None => false,
+        };
+        let data_type = value.data_type();
+        let measurement_schema = MeasurementSchema::new(
+            data_type,
+            self.config.default_encoding(data_type),
+            self.config.default_compression,
+        );
+        match device_known {
+            true => self.register_timeseries(device, measurement_id, measurement_schema),
+            false => self.register_device(
+                device,
+                MeasurementGroup {
+                    measurement_schemas: HashMap::from([(
+                        measurement_id.to_owned(),
+                        measurement_schema,
+                    )]),
+                    aligned: false,
+                    time_compression: CompressionType::UNCOMPRESSED,
+                },
+            ),
+        }
+    }
+
+    /// Flushes if the buffered points of the unsequence mode exceed the memory budget
+    fn check_unsequence_buffer_and_may_flush(&mut self) -> Result<bool, TsFileError> {
+        if let Some(memory_budget) = self.config.unsequence_memory_budget {
+            let buffer_size: usize = self
+                .group_writers
+                .values()
+                .map(|group| group.unsequence_buffer_size())
+                .sum();
+            if buffer_size > memory_budget as usize {
+                return self.flush_all_chunk_groups();
+            }
+        }
+        Ok(false)
+    }
+
     fn check_memory_size_and_may_flush_chunks(&mut self) -> Result<bool, TsFileError> {
         if self.record_count >= self.record_count_for_next_mem_check {
             let mem_size = self.calculate_mem_size_for_all_groups();
+            let group_size_threshold = self.config.group_size_in_byte;
             log::trace!("Memcount calculated: {}", mem_size);
             log::trace!(
                 "{:.2?}% - {} / {} for flushing",
-                mem_size as f64 / CHUNK_GROUP_SIZE_THRESHOLD_BYTE as f64 * 100.0,
+                mem_size as f64 / group_size_threshold as f64 * 100.0,
                 mem_size,
-                CHUNK_GROUP_SIZE_THRESHOLD_BYTE
+                group_size_threshold
             );
-            if mem_size > CHUNK_GROUP_SIZE_THRESHOLD_BYTE {
-                self.record_count_for_next_mem_check = (self.record_count_for_next_mem_check as u64
-                    * CHUNK_GROUP_SIZE_THRESHOLD_BYTE as u64
-                    / mem_size as u64)
-                    as u32;
+            if mem_size > group_size_threshold {
+                self.record_count_for_next_mem_check =
+                    (self.record_count_for_next_mem_check as u64 * group_size_threshold as u64
+                        / mem_size as u64) as u32;
                 return self.flush_all_chunk_groups();
             } else {
                 // println!("Record Count: {}, CHUNK_GROUP_SIZE_THRESHOLD_BYTE: {}, memsize: {}", self.record_count_for_next_mem_check, CHUNK_GROUP_SIZE_THRESHOLD_BYTE, mem_size);
                 // in the java impl there can be an overflow...
-                self.record_count_for_next_mem_check = (self.record_count_for_next_mem_check as u64
-                    * CHUNK_GROUP_SIZE_THRESHOLD_BYTE as u64
-                    / mem_size as u64)
-                    as u32;
+                self.record_count_for_next_mem_check =
+                    (self.record_count_for_next_mem_check as u64 * group_size_threshold as u64
+                        / mem_size as u64) as u32;
                 log::trace!(
                     "Next record count for check {}",
                     self.record_count_for_next_mem_check
@@ -202,12 +398,12 @@ impl<'a, T: PositionedWrite> TsFileWriter<'a, T> {
 
     fn flush_all_chunk_groups(&mut self) -> Result<bool, TsFileError> {
         if self.record_count > 0 {
-            for (&device_id, group_writer) in self.group_writers.iter_mut() {
+            for (device_id, group_writer) in self.group_writers.iter_mut() {
                 // self.file_writer.start_chunk_group(device_id);
                 // self.file_writer
                 self.file_io_writer.start_chunk_group(device_id)?;
                 let pos = self.file_io_writer.out.get_position();
-                let data_size = group_writer.flush_to_filewriter(&mut self.file_io_writer);
+                let data_size = group_writer.flush_to_filewriter(&mut self.file_io_writer)?;
 
                 if self.file_io_writer.out.get_position() - pos != data_size {
                     return Err(TsFileError::IllegalState {
@@ -218,7 +414,7 @@ impl<'a, T: PositionedWrite> TsFileWriter<'a, T> {
                 self.file_io_writer.end_chunk_group();
 
                 self.non_aligned_timeseries_last_time_map
-                    .insert(device_id, group_writer.get_last_time_map());
+                    .insert(device_id.clone(), group_writer.get_last_time_map());
             }
             self.reset();
         }
@@ -241,45 +437,29 @@ impl<'a, T: PositionedWrite> TsFileWriter<'a, T> {
     fn reset(&mut self) {
         self.record_count = 0;
         // Reset Group Writers
+        let config = self.config;
         let schema = self.schema.clone();
+        let last_time_map = &self.non_aligned_timeseries_last_time_map;
         self.group_writers = schema
             .measurement_groups
             .into_iter()
             .map(|(path, v)| {
-                (
-                    path,
-                    GroupWriter {
-                        path,
-                        chunk_writers: v
-                            .measurement_schemas
-                            .iter()
-                            .map(|(&measurement_id, measurement_schema)| {
-                                (
-                                    measurement_id,
-                                    ChunkWriter::new(
-                                        measurement_id,
-                                        measurement_schema.data_type,
-                                        measurement_schema.compression,
-                                        measurement_schema.encoding,
-                                    ),
-                                )
-                            })
-                            .collect(),
-                        last_time_map: BTreeMap::new(),
-                    },
-                )
+                // Points must not precede or duplicate the flushed ones
+                let last_times = last_time_map.get(&path).cloned().unwrap_or_default();
+                let group_writer = GroupWriter::new(&path, &v, &config, last_times);
+                (path, group_writer)
             })
             .collect();
     }
 }
 
-impl<'a> TsFileWriter<'a, WriteWrapper<File>> {
+impl TsFileWriter<WriteWrapper<File>> {
     // "Default" constructor to use... writes to a file
     pub fn new(
-        filename: &'a str,
-        schema: Schema<'a>,
+        filename: &str,
+        schema: Schema,
         config: TsFileConfig,
-    ) -> Result<TsFileWriter<'a, WriteWrapper<File>>, TsFileError> {
+    ) -> Result<TsFileWriter<WriteWrapper<File>>, TsFileError> {
         // Create directory, if not exists
         let folder = match std::path::Path::new(filename).parent() {
             Some(f) => f,
@@ -295,44 +475,36 @@ impl<'a> TsFileWriter<'a, WriteWrapper<File>> {
     }
 }
 
-impl<'a, T: PositionedWrite> TsFileWriter<'a, T> {
+impl<T: PositionedWrite> TsFileWriter<T> {
     pub(crate) fn new_from_writer(
-        schema: Schema<'a>,
+        schema: Schema,
         file_writer: T,
         config: TsFileConfig,
-    ) -> Result<TsFileWriter<'a, T>, TsFileError> {
+    ) -> Result<TsFileWriter<T>, TsFileError> {
+        let io_writer = TsFileIoWriter::new(file_writer, config)?;
+        Ok(TsFileWriter::new_from_io_writer(schema, io_writer, config))
+    }
+
+    /// The points of the writer have to follow the ones of the chunks that `io_writer` has
+    /// written already, e.g. the ones of a recovered file
+    pub(crate) fn new_from_io_writer(
+        schema: Schema,
+        io_writer: TsFileIoWriter<T>,
+        config: TsFileConfig,
+    ) -> TsFileWriter<T> {
+        let last_time_map = io_writer.last_time_map();
         let group_writers = schema
             .clone()
             .measurement_groups
             .into_iter()
             .map(|(path, v)| {
-                (
-                    path.borrow(),
-                    GroupWriter {
-                        path,
-                        chunk_writers: v
-                            .measurement_schemas
-                            .iter()
-                            .map(|(&measurement_id, measurement_schema)| {
-                                (
-                                    measurement_id,
-                                    ChunkWriter::new(
-                                        measurement_id,
-                                        measurement_schema.data_type,
-                                        measurement_schema.compression,
-                                        measurement_schema.encoding,
-                                    ),
-                                )
-                            })
-                            .collect(),
-                        last_time_map: BTreeMap::new(),
-                    },
-                )
+                let last_times = last_time_map.get(&path).cloned().unwrap_or_default();
+                let group_writer = GroupWriter::new(&path, &v, &config, last_times);
+                (path, group_writer)
             })
             .collect();
 
-        let io_writer = TsFileIoWriter::new(file_writer, config)?;
-        Ok(TsFileWriter {
+        TsFileWriter {
             filename: String::from(""),
             schema,
             group_writers,
@@ -340,9 +512,386 @@ impl<'a, T: PositionedWrite> TsFileWriter<'a, T> {
             timeseries_metadata_map: HashMap::new(),
             record_count: 0,
             record_count_for_next_mem_check: 100,
-            non_aligned_timeseries_last_time_map: BTreeMap::new(),
+            non_aligned_timeseries_last_time_map: last_time_map,
             config,
             file_io_writer: io_writer,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::reader::HEADER_SIZE;
+    use crate::writer::compression::CompressionType;
+    use crate::writer::encoding::TSEncoding;
+    use crate::writer::errors::TsFileError;
+    use crate::writer::schema::{AlignedDeviceBuilder, DeviceBuilder, TsFileSchemaBuilder};
+    use crate::writer::ts_file_config::{DuplicatePolicy, TsFileConfig, TsFileConfigBuilder};
+    use crate::writer::tsfile_writer::{DataPoint, TsFileWriter};
+    use crate::writer::{IoTDBValue, MeasurementSchema, Schema, TSDataType};
+
+    #[test]
+    fn unsequence_memory_budget() -> Result<(), TsFileError> {
+        let schema = Schema::simple(
+            "d1",
+            "s1",
+            TSDataType::INT64,
+            TSEncoding::TS2DIFF,
+            CompressionType::UNCOMPRESSED,
+        );
+        let config = TsFileConfig::default().with_unsequence_buffer(10000);
+        let mut writer = TsFileWriter::new_from_writer(schema, Vec::new(), config)?;
+        let mut rejected = 0;
+        for block in 0..100_i64 {
+            for timestamp in (block * 100..block * 100 + 100).rev() {
+                // Points behind a flushed chunk are rejected
+                match writer.write("d1", "s1", timestamp, IoTDBValue::LONG(timestamp)) {
+                    Ok(_) => {}
+                    Err(e) => {
+                        assert_eq!(TsFileError::OutOfOrderData, e);
+                        rejected += 1;
+                    }
+                }
+                let buffer_size: usize = writer
+                    .group_writers
+                    .values()
+                    .map(|group| group.unsequence_buffer_size())
+                    .sum();
+                assert!(buffer_size <= 10000);
+            }
+        }
+        assert!(rejected > 0 && rejected < 5000);
+        // The budget flushes the chunk groups before the file is closed
+        assert!(writer.file_io_writer.out.len() > HEADER_SIZE as usize);
+        assert_eq!(
+            Err(TsFileError::OutOfOrderData),
+            writer.write("d1", "s1", 0, IoTDBValue::LONG(0))
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn group_size_threshold() -> Result<(), TsFileError> {
+        for (group_size_in_byte, flushed) in [(128 * 1024 * 1024, false), (64 * 1024, true)] {
+            let schema = Schema::simple(
+                "d1",
+                "s1",
+                TSDataType::INT64,
+                TSEncoding::PLAIN,
+                CompressionType::UNCOMPRESSED,
+            );
+            let config = TsFileConfigBuilder::new()
+                .group_size_in_byte(group_size_in_byte)
+                .build()?;
+            let mut writer = TsFileWriter::new_from_writer(schema, Vec::new(), config)?;
+            for timestamp in 0..20000 {
+                writer.write("d1", "s1", timestamp, IoTDBValue::LONG(timestamp))?;
+            }
+            // Chunk groups are only written to the file before closing it if they exceed
+            // the threshold
+            assert_eq!(
+                flushed,
+                writer.file_io_writer.out.len() > HEADER_SIZE as usize,
+                "{}",
+                group_size_in_byte
+            );
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn owned_ids() -> Result<(), TsFileError> {
+        // Ids built at runtime, the writer neither borrows them nor the schema
+        let device = format!("root.{}.d1", "sg");
+        let schema = Schema::simple(
+            device.clone(),
+            String::from("s1"),
+            TSDataType::INT32,
+            TSEncoding::PLAIN,
+            CompressionType::UNCOMPRESSED,
+        );
+        let mut writer = TsFileWriter::new_from_writer(schema, Vec::new(), Default::default())?;
+        let writer = std::thread::spawn(move || {
+            for timestamp in 0..10 {
+                let measurement = format!("s{}", 1);
+                writer.write(&device, &measurement, timestamp, IoTDBValue::INT(1))?;
+            }
+            writer.write_many(
+                "root.sg.d1",
+                10,
+                vec![DataPoint::new("s1".to_owned(), IoTDBValue::INT(2))],
+            )?;
+            Ok::<_, TsFileError>(writer)
         })
+        .join()
+        .unwrap()?;
+
+        assert_eq!(11, writer.record_count);
+        let group_writer = &writer.group_writers["root.sg.d1"];
+        assert_eq!("root.sg.d1", group_writer.path);
+        assert_eq!(Some(&10), group_writer.last_time_map.get("s1"));
+        Ok(())
+    }
+
+    #[test]
+    fn write_duplicate_after_flush() -> Result<(), TsFileError> {
+        for policy in [
+            DuplicatePolicy::Reject,
+            DuplicatePolicy::KeepFirst,
+            DuplicatePolicy::KeepLast,
+        ] {
+            let schema = TsFileSchemaBuilder::new()
+                .add(
+                    "d1",
+                    DeviceBuilder::new()
+                        .add(
+                            "s1",
+                            TSDataType::INT64,
+                            TSEncoding::PLAIN,
+                            CompressionType::UNCOMPRESSED,
+                        )
+                        .build(),
+                )
+                .add(
+                    "d2",
+                    AlignedDeviceBuilder::new()
+                        .add(
+                            "s1",
+                            TSDataType::INT64,
+                            TSEncoding::PLAIN,
+                            CompressionType::UNCOMPRESSED,
+                        )
+                        .build(),
+                )
+                .build();
+            let config = TsFileConfigBuilder::new()
+                .group_size_in_byte(128)
+                .page_size_in_byte(64)
+                .duplicate_policy(policy)
+                .build()?;
+            let mut writer = TsFileWriter::new_from_writer(schema, Vec::new(), config)?;
+            // The memory is checked after 100 records, which flushes the tiny chunk groups
+            for timestamp in 0..50 {
+                writer.write("d1", "s1", timestamp, IoTDBValue::LONG(timestamp))?;
+                writer.write_many(
+                    "d2",
+                    timestamp,
+                    vec![DataPoint::new("s1", IoTDBValue::LONG(timestamp))],
+                )?;
+            }
+            assert!(writer.file_io_writer.out.len() as u64 > HEADER_SIZE);
+
+            let expected = match policy {
+                DuplicatePolicy::KeepFirst => Ok(()),
+                _ => Err(TsFileError::OutOfOrderData),
+            };
+            let duplicate = writer.write("d1", "s1", 49, IoTDBValue::LONG(-1));
+            assert_eq!(expected, duplicate, "{:?}", policy);
+            let duplicate = writer.write("d2", "s1", 49, IoTDBValue::LONG(-1));
+            assert_eq!(expected, duplicate, "{:?}", policy);
+            assert_eq!(
+                Err(TsFileError::OutOfOrderData),
+                writer.write("d1", "s1", 10, IoTDBValue::LONG(-1))
+            );
+            writer.write("d1", "s1", 50, IoTDBValue::LONG(50))?;
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn register_timeseries() -> Result<(), TsFileError> {
+        let schema = TsFileSchemaBuilder::new()
+            .add(
+                "d1",
+                DeviceBuilder::new()
+                    .add(
+                        "s1",
+                        TSDataType::INT32,
+                        TSEncoding::PLAIN,
+                        CompressionType::UNCOMPRESSED,
+                    )
+                    .build(),
+            )
+            .add(
+                "d2",
+                AlignedDeviceBuilder::new()
+                    .add(
+                        "s1",
+                        TSDataType::INT64,
+                        TSEncoding::TS2DIFF,
+                        CompressionType::SNAPPY,
+                    )
+                    .build(),
+            )
+            .build();
+        let mut writer = TsFileWriter::new_from_writer(schema, Vec::new(), Default::default())?;
+        writer.write("d1", "s1", 1, IoTDBValue::INT(1))?;
+        writer.write("d2", "s1", 1, IoTDBValue::LONG(1))?;
+
+        let double = MeasurementSchema::new(
+            TSDataType::DOUBLE,
+            TSEncoding::GORILLA,
+            CompressionType::SNAPPY,
+        );
+        writer.register_timeseries("d1", "s2", double)?;
+        writer.register_device(
+            "d3",
+            DeviceBuilder::new()
+                .add(
+                    "s1",
+                    TSDataType::TEXT,
+                    TSEncoding::PLAIN,
+                    CompressionType::UNCOMPRESSED,
+                )
+                .build(),
+        )?;
+        // Points of a new series of a non aligned device are buffered with the others
+        assert_eq!(HEADER_SIZE, writer.file_io_writer.out.len() as u64);
+        let boolean = MeasurementSchema::new(
+            TSDataType::BOOLEAN,
+            TSEncoding::RLE,
+            CompressionType::UNCOMPRESSED,
+        );
+        // The buffered row of d2 lacks the new column, so it is flushed
+        writer.register_timeseries("d2", "s2", boolean)?;
+        assert!(writer.file_io_writer.out.len() as u64 > HEADER_SIZE);
+
+        let d1 = &writer.schema.measurement_groups["d1"];
+        assert_eq!(TSDataType::DOUBLE, d1.measurement_schemas["s2"].data_type);
+        let d2 = &writer.schema.measurement_groups["d2"];
+        assert!(d2.is_aligned());
+        assert_eq!(TSDataType::BOOLEAN, d2.measurement_schemas["s2"].data_type);
+        assert!(writer.schema.measurement_groups.contains_key("d3"));
+        for (device, measurements) in [("d1", vec!["s1", "s2"]), ("d2", vec!["s1", "s2"])] {
+            let chunk_writers = &writer.group_writers[device].chunk_writers;
+            assert_eq!(measurements, chunk_writers.keys().collect::<Vec<_>>());
+        }
+        writer.write("d1", "s2", 2, IoTDBValue::DOUBLE(2.5))?;
+        writer.write("d3", "s1", 2, IoTDBValue::from("v"))?;
+        // The last time of d2 is kept across the new group writer
+        assert_eq!(
+            Err(TsFileError::OutOfOrderData),
+            writer.write("d2", "s2", 1, IoTDBValue::BOOLEAN(true))
+        );
+        writer.write_many(
+            "d2",
+            2,
+            vec![
+                DataPoint::new("s1", IoTDBValue::LONG(2)),
+                DataPoint::new("s2", IoTDBValue::BOOLEAN(true)),
+            ],
+        )?;
+        Ok(())
+    }
+
+    #[test]
+    fn register_timeseries_invalid() -> Result<(), TsFileError> {
+        let schema = Schema::simple(
+            "d1",
+            "s1",
+            TSDataType::INT32,
+            TSEncoding::PLAIN,
+            CompressionType::UNCOMPRESSED,
+        );
+        let mut writer = TsFileWriter::new_from_writer(schema, Vec::new(), Default::default())?;
+        let int32 = MeasurementSchema::new(
+            TSDataType::INT32,
+            TSEncoding::PLAIN,
+            CompressionType::UNCOMPRESSED,
+        );
+        let device = writer.schema.measurement_groups["d1"].clone();
+        assert_eq!(
+            Err(TsFileError::IllegalState {
+                source: Some("Device d1 is already registered".to_owned())
+            }),
+            writer.register_device("d1", device)
+        );
+        assert_eq!(
+            Err(TsFileError::IllegalState {
+                source: Some("Timeseries d1.s1 is already registered".to_owned())
+            }),
+            writer.register_timeseries("d1", "s1", int32.clone())
+        );
+        assert_eq!(
+            Err(TsFileError::IllegalState {
+                source: Some("Unknown device d2".to_owned())
+            }),
+            writer.register_timeseries("d2", "s1", int32)
+        );
+        let boolean_gorilla = MeasurementSchema::new(
+            TSDataType::BOOLEAN,
+            TSEncoding::GORILLA,
+            CompressionType::UNCOMPRESSED,
+        );
+        assert_eq!(
+            Err(TsFileError::IllegalState {
+                source: Some(
+                    "Encoding GORILLA does not support the data type BOOLEAN of s2".to_owned()
+                )
+            }),
+            writer.register_timeseries("d1", "s2", boolean_gorilla)
+        );
+
+        // The schema is unchanged
+        assert_eq!(1, writer.schema.measurement_groups.len());
+        assert_eq!(
+            1,
+            writer.schema.measurement_groups["d1"]
+                .measurement_schemas
+                .len()
+        );
+        assert_eq!(1, writer.group_writers["d1"].chunk_writers.len());
+        Ok(())
+    }
+
+    #[test]
+    fn auto_create_schema() -> Result<(), TsFileError> {
+        let config = TsFileConfigBuilder::new()
+            .auto_create_schema(true)
+            .default_encoding(TSDataType::INT32, TSEncoding::ZIGZAG)
+            .build()?;
+        let schema = Schema::simple(
+            "d1",
+            "s1",
+            TSDataType::INT64,
+            TSEncoding::PLAIN,
+            CompressionType::UNCOMPRESSED,
+        );
+        let mut writer = TsFileWriter::new_from_writer(schema, Vec::new(), config)?;
+        writer.write("d1", "s1", 0, IoTDBValue::LONG(0))?;
+        writer.write("d1", "s2", 0, IoTDBValue::INT(0))?;
+        writer.write_many(
+            "d2",
+            0,
+            vec![
+                DataPoint::new("s1", IoTDBValue::DOUBLE(0.0)),
+                DataPoint::null("s2"),
+            ],
+        )?;
+        // Nulls of an unknown device are dropped without an error
+        assert_eq!(
+            Ok(()),
+            writer.write_many("d3", 0, vec![DataPoint::null("s1")])
+        );
+        assert_eq!(
+            Err(TsFileError::WrongTypeForSeries),
+            writer.write("d1", "s2", 1, IoTDBValue::LONG(1))
+        );
+
+        let groups = &writer.schema.measurement_groups;
+        let mut devices: Vec<&String> = groups.keys().collect();
+        devices.sort();
+        assert_eq!(vec!["d1", "d2"], devices);
+        let s2 = &groups["d1"].measurement_schemas["s2"];
+        assert_eq!(TSDataType::INT32, s2.data_type);
+        assert_eq!(TSEncoding::ZIGZAG, s2.encoding);
+        assert_eq!(CompressionType::SNAPPY, s2.compression);
+        let d2 = &groups["d2"];
+        assert!(!d2.is_aligned());
+        assert_eq!(1, d2.measurement_schemas.len());
+        assert_eq!(TSEncoding::GORILLA, d2.measurement_schemas["s1"].encoding);
+        assert!(writer.group_writers.contains_key("d2"));
+        assert!(!writer.group_writers.contains_key("d3"));
+        Ok(())
     }
 }
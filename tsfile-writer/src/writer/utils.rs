@@ -18,6 +18,14 @@ pub fn write_var_u32(num: u32, buffer: &mut dyn PositionedWrite) -> Result<u8, T
     Ok(position)
 }
 
+/// Number of bytes needed to hold the given number of bits
+pub(crate) fn bytes_for_bits(bits: usize) -> usize {
+    match bits % 8 {
+        0 => bits / 8,
+        _ => bits / 8 + 1,
+    }
+}
+
 pub fn size_var_i32(num: i32) -> u8 {
     let mut u_value = num << 1;
     if num < 0 {
@@ -44,22 +52,48 @@ pub fn write_var_i32(num: i32, buffer: &mut dyn PositionedWrite) -> Result<u8, T
     write_var_u32(u_value as u32, buffer)
 }
 
-#[allow(dead_code)]
-fn read_byte(buffer: &mut dyn Read) -> Result<u8, TsFileError> {
+pub fn read_byte(buffer: &mut dyn Read) -> Result<u8, TsFileError> {
     let mut read_buffer: [u8; 1] = [0];
     buffer.read_exact(&mut read_buffer)?;
     Ok(read_buffer[0])
 }
 
-#[allow(dead_code)]
+/// Reads a varint of at most 5 bytes, longer ones are corrupt
 pub fn read_var_u32(buffer: &mut dyn Read) -> Result<u32, TsFileError> {
     let mut value: u32 = 0;
-    let mut i: u8 = 0;
-    let mut b = read_byte(buffer)?;
-    while b != u8::MAX && (b & 0x80) != 0 {
-        value |= ((b & 0x7F) as u32) << i;
-        i += 7;
-        b = read_byte(buffer)?;
+    for shift in (0..32).step_by(7) {
+        let b = read_byte(buffer)?;
+        value |= ((b & 0x7F) as u32) << shift;
+        if (b & 0x80) == 0 {
+            return Ok(value);
+        }
     }
-    Ok(value | ((b as u32) << i))
+    Err(TsFileError::ReadError {
+        source: Some("Varint exceeds 5 bytes".to_owned()),
+    })
 }
+
+pub fn read_var_i32(buffer: &mut dyn Read) -> Result<i32, TsFileError> {
+    let u_value = read_var_u32(buffer)?;
+    let value = (u_value >> 1) as i32;
+    if (u_value & 1) != 0 {
+        Ok(!value)
+    } else {
+        Ok(value)
+    }
+}
+
+macro_rules! read_be {
+    ( $name:ident, $type:ty ) => {
+        pub fn $name(buffer: &mut dyn Read) -> Result<$type, TsFileError> {
+            let mut bytes = [0_u8; std::mem::size_of::<$type>()];
+            buffer.read_exact(&mut bytes)?;
+            Ok(<$type>::from_be_bytes(bytes))
+        }
+    };
+}
+
+read_be!(read_i32, i32);
+read_be!(read_i64, i64);
+read_be!(read_f32, f32);
+read_be!(read_f64, f64);